@@ -0,0 +1,107 @@
+//! Compares `SegmentMap` with `HalfOpenSegmentMap`, which only stores the two
+//! ends of each (half-open) range.
+//!
+//! Sizes of each stored entry (key and value, as laid out in the tree's
+//! nodes) are printed before the timings. On a 64-bit target:
+//!
+//! | Key    | Value  | `SegmentMap` | `HalfOpenSegmentMap` |
+//! |--------|--------|-------------:|---------------------:|
+//! | `u32`  | `()`   |     16 bytes |              8 bytes |
+//! | `u32`  | `bool` |     17 bytes |             12 bytes |
+//! | `u64`  | `()`   |     32 bytes |             16 bytes |
+//! | `u64`  | `u32`  |     36 bytes |             24 bytes |
+//! | `u128` | `()`   |     64 bytes |             32 bytes |
+
+#[macro_use]
+extern crate criterion;
+
+use criterion::{BenchmarkId, Criterion};
+use rand::prelude::*;
+use segmap::{HalfOpenSegmentMap, Segment, SegmentMap};
+use std::{mem::size_of, ops::Range};
+
+/// Bytes stored per range by each map, as (`SegmentMap`, `HalfOpenSegmentMap`)
+fn entry_sizes<K, V>() -> (usize, usize) {
+    (
+        size_of::<Segment<K>>() + size_of::<V>(),
+        size_of::<K>() + size_of::<(K, V)>(),
+    )
+}
+
+fn print_sizes() {
+    let sizes = [
+        ("u32", "()", entry_sizes::<u32, ()>()),
+        ("u32", "bool", entry_sizes::<u32, bool>()),
+        ("u64", "()", entry_sizes::<u64, ()>()),
+        ("u64", "u32", entry_sizes::<u64, u32>()),
+        ("u128", "()", entry_sizes::<u128, ()>()),
+    ];
+    println!("Entry sizes (SegmentMap vs HalfOpenSegmentMap):");
+    for (key, value, (general, half_open)) in sizes {
+        println!(
+            "  {:>4} => {:<4}: {:>2} vs {:>2} bytes",
+            key, value, general, half_open
+        );
+    }
+}
+
+fn random_ranges(n: u32) -> Vec<(Range<u32>, bool)> {
+    let mut rng = thread_rng();
+    (0..n)
+        .map(|_| {
+            let start = rng.gen_range(0..n * 10);
+            let end = start + rng.gen_range(1..20);
+            (start..end, random())
+        })
+        .collect()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    print_sizes();
+
+    let mut group = c.benchmark_group("half open");
+    for n in [1_000u32, 100_000] {
+        let kvs = random_ranges(n);
+        let points: Vec<u32> = (0..n).map(|i| i * 10).collect();
+
+        group.bench_with_input(BenchmarkId::new("SegmentMap set", n), &kvs, |b, kvs| {
+            b.iter(|| {
+                let mut map = SegmentMap::new();
+                for (range, value) in kvs {
+                    map.set(range.clone(), *value);
+                }
+                map
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("HalfOpenSegmentMap set", n),
+            &kvs,
+            |b, kvs| {
+                b.iter(|| {
+                    let mut map = HalfOpenSegmentMap::new();
+                    for (range, value) in kvs {
+                        map.set(range.clone(), *value);
+                    }
+                    map
+                })
+            },
+        );
+
+        let map: SegmentMap<u32, bool> = kvs.iter().cloned().collect();
+        group.bench_with_input(
+            BenchmarkId::new("SegmentMap get", n),
+            &points,
+            |b, points| b.iter(|| points.iter().filter(|at| map.get(at).is_some()).count()),
+        );
+        let map: HalfOpenSegmentMap<u32, bool> = kvs.iter().cloned().collect();
+        group.bench_with_input(
+            BenchmarkId::new("HalfOpenSegmentMap get", n),
+            &points,
+            |b, points| b.iter(|| points.iter().filter(|at| map.get(at).is_some()).count()),
+        );
+    }
+    group.finish()
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
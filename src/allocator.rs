@@ -0,0 +1,547 @@
+//! Region allocation on top of [`SegmentSet`]
+//!
+//! [`RegionAllocator`] manages a contiguous universe of integer-like keys
+//! (addresses, file offsets, IDs, ...) and hands out non-overlapping regions
+//! of it. Free space is tracked as a coalesced [`SegmentSet`], so freeing a
+//! region automatically merges it with any adjacent free space.
+//!
+//! All regions handled by the allocator are half-open (`[start, end)`).
+//! Ranges given with other bounds are normalized before use.
+
+use core::{
+    fmt,
+    ops::{Bound::*, Range, RangeBounds},
+};
+
+use crate::{Segment, SegmentSet};
+
+#[cfg(test)]
+mod tests;
+
+/// Integer-like key types that a [`RegionAllocator`] can do arithmetic on
+///
+/// This is implemented for all of the unsigned integer primitives.
+pub trait Address: Copy + Ord {
+    /// The additive identity
+    const ZERO: Self;
+
+    /// The smallest step between two adjacent addresses
+    const ONE: Self;
+
+    /// Checked addition, returning `None` on overflow
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    /// Checked subtraction, returning `None` on underflow
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+
+    /// Remainder of `self / rhs` (`rhs` is never zero)
+    fn rem(self, rhs: Self) -> Self;
+
+    /// Lossy conversion, used for reporting ratios
+    fn as_f64(self) -> f64;
+
+    /// Round `self` up to the next multiple of `align`, returning `None` on
+    /// overflow
+    fn align_up(self, align: Self) -> Option<Self> {
+        let rem = self.rem(align);
+        if rem == Self::ZERO {
+            Some(self)
+        } else {
+            self.checked_add(align.checked_sub(rem)?)
+        }
+    }
+}
+
+macro_rules! impl_address {
+    ($($t:ty),*) => {
+        $(
+            impl Address for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+                #[inline]
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+                #[inline]
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+                #[inline]
+                fn rem(self, rhs: Self) -> Self {
+                    self % rhs
+                }
+                #[inline]
+                fn as_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_address!(u8, u16, u32, u64, u128, usize);
+
+/// Strategy used by [`RegionAllocator::alloc`] to pick a free region
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FitPolicy {
+    /// Use the lowest free region large enough for the request
+    #[default]
+    FirstFit,
+
+    /// Use the smallest free region large enough for the request (ties go to
+    /// the lowest region)
+    BestFit,
+
+    /// Like [`FitPolicy::FirstFit`], but start searching where the previous
+    /// allocation ended (wrapping around to the start of the universe)
+    NextFit,
+}
+
+/// Errors returned by [`RegionAllocator`] operations
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AllocError {
+    /// A zero-sized region was requested
+    ZeroSize,
+
+    /// An alignment of zero was requested
+    ZeroAlignment,
+
+    /// No free region is large enough to satisfy the request
+    OutOfSpace,
+
+    /// The region is not (entirely) inside the managed universe
+    OutOfBounds,
+
+    /// Some part of the region requested by [`RegionAllocator::alloc_at`] is
+    /// already allocated
+    AlreadyAllocated,
+
+    /// Some part of the region given to [`RegionAllocator::free`] is already
+    /// free
+    DoubleFree,
+
+    /// The universe can't shrink because the region being released is in use
+    InUse,
+
+    /// Key arithmetic overflowed
+    Overflow,
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AllocError::ZeroSize => "zero-sized region requested",
+            AllocError::ZeroAlignment => "zero alignment requested",
+            AllocError::OutOfSpace => "no free region large enough",
+            AllocError::OutOfBounds => "region outside of the managed universe",
+            AllocError::AlreadyAllocated => "region is already allocated",
+            AllocError::DoubleFree => "region is already free",
+            AllocError::InUse => "region is in use",
+            AllocError::Overflow => "key arithmetic overflowed",
+        })
+    }
+}
+
+/// A snapshot of how free space is laid out in a [`RegionAllocator`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FragmentationStats<K> {
+    /// Total size of all free regions
+    pub free: K,
+
+    /// Total size of all allocated regions
+    pub allocated: K,
+
+    /// Number of (maximally coalesced) free regions
+    pub free_regions: usize,
+
+    /// Size of the largest free region, or zero if there are none
+    pub largest_free: K,
+}
+
+impl<K: Address> FragmentationStats<K> {
+    /// External fragmentation, as the fraction of free space that is *not* in
+    /// the largest free region.
+    ///
+    /// Returns `0.0` when there is no free space or when all of it is
+    /// contiguous, and approaches `1.0` as free space is scattered across many
+    /// small regions.
+    pub fn fragmentation(&self) -> f64 {
+        if self.free == K::ZERO {
+            0.0
+        } else {
+            1.0 - self.largest_free.as_f64() / self.free.as_f64()
+        }
+    }
+}
+
+/// # RegionAllocator
+///
+/// Hands out non-overlapping, aligned regions of a contiguous key space,
+/// tracking free space in a coalesced [`SegmentSet`].
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// use segmap::allocator::{AllocError, RegionAllocator};
+///
+/// let mut heap = RegionAllocator::new(0u64..1024);
+///
+/// let a = heap.alloc(100, 1).unwrap();
+/// let b = heap.alloc(100, 64).unwrap();
+/// assert_eq!(a, Segment::from(0..100));
+/// assert_eq!(b, Segment::from(128..228));
+///
+/// // Freed space is coalesced with its neighbours
+/// heap.free(a).unwrap();
+/// assert_eq!(heap.free(0..10), Err(AllocError::DoubleFree));
+/// heap.free(b).unwrap();
+/// assert!(heap.free_regions().iter().eq(&[Segment::from(0..1024)]));
+/// ```
+#[derive(Clone)]
+pub struct RegionAllocator<K> {
+    start: K,
+    end: K,
+    free: SegmentSet<K>,
+    policy: FitPolicy,
+
+    /// Where the last allocation ended, for [`FitPolicy::NextFit`]
+    cursor: K,
+}
+
+impl<K: Address + fmt::Debug> fmt::Debug for RegionAllocator<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegionAllocator")
+            .field("universe", &self.universe())
+            .field("free", &self.free)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl<K: Address> RegionAllocator<K> {
+    /// Makes a new allocator managing `universe`, which is entirely free.
+    /// Allocations use [`FitPolicy::FirstFit`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// use segmap::allocator::RegionAllocator;
+    ///
+    /// let ids = RegionAllocator::new(1u32..100);
+    /// assert_eq!(ids.universe(), Segment::from(1..100));
+    /// ```
+    pub fn new(universe: Range<K>) -> Self {
+        Self::with_policy(universe, FitPolicy::default())
+    }
+
+    /// Makes a new allocator managing `universe`, using the given policy for
+    /// allocations.
+    pub fn with_policy(universe: Range<K>, policy: FitPolicy) -> Self {
+        let Range { start, end } = universe;
+        let end = core::cmp::max(start, end);
+        let mut free = SegmentSet::new();
+        if start < end {
+            free.insert(start..end);
+        }
+        RegionAllocator {
+            start,
+            end,
+            free,
+            policy,
+            cursor: start,
+        }
+    }
+
+    /// The policy used to choose free regions in [`RegionAllocator::alloc`]
+    pub fn policy(&self) -> FitPolicy {
+        self.policy
+    }
+
+    /// Change the policy used for subsequent allocations
+    pub fn set_policy(&mut self, policy: FitPolicy) {
+        self.policy = policy;
+    }
+
+    /// The full range of keys managed by this allocator
+    pub fn universe(&self) -> Segment<K> {
+        Segment::from(self.start..self.end)
+    }
+
+    /// All currently free regions, in order
+    pub fn free_regions(&self) -> &SegmentSet<K> {
+        &self.free
+    }
+
+    /// Returns `true` if `at` is inside the universe and currently allocated
+    pub fn is_allocated(&self, at: &K) -> bool {
+        self.start <= *at && *at < self.end && !self.free.contains(at)
+    }
+
+    /// Allocate a region of `size` keys, starting at a multiple of `align`.
+    ///
+    /// The free region used is chosen according to [`RegionAllocator::policy`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// use segmap::allocator::{AllocError, FitPolicy, RegionAllocator};
+    ///
+    /// let mut heap = RegionAllocator::with_policy(0u32..100, FitPolicy::BestFit);
+    /// let a = heap.alloc(10, 1).unwrap();
+    /// let _ = heap.alloc(50, 1).unwrap();
+    /// let _ = heap.alloc(5, 1).unwrap();
+    /// heap.free(a).unwrap();
+    ///
+    /// // [0, 10) and [65, 100) are free, the smaller one is the best fit
+    /// assert_eq!(heap.alloc(8, 1), Ok(Segment::from(0..8)));
+    /// assert_eq!(heap.alloc(40, 1), Err(AllocError::OutOfSpace));
+    /// ```
+    pub fn alloc(&mut self, size: K, align: K) -> Result<Segment<K>, AllocError> {
+        if size == K::ZERO {
+            return Err(AllocError::ZeroSize);
+        }
+        if align == K::ZERO {
+            return Err(AllocError::ZeroAlignment);
+        }
+
+        let free_regions = self.free.iter().map(Self::region_of);
+        let found = match self.policy {
+            FitPolicy::FirstFit => free_regions
+                .filter_map(|(start, end)| Self::fit(start, end, size, align))
+                .next(),
+            FitPolicy::BestFit => free_regions
+                .filter_map(|(start, end)| {
+                    Some((end.checked_sub(start)?, Self::fit(start, end, size, align)?))
+                })
+                .min_by_key(|(len, _)| *len)
+                .map(|(_, at)| at),
+            FitPolicy::NextFit => {
+                let cursor = self.cursor;
+
+                // Search everything at or after the cursor, then wrap around
+                let after = self
+                    .free
+                    .iter()
+                    .map(Self::region_of)
+                    .filter(|(_, end)| *end > cursor)
+                    .map(|(start, end)| (core::cmp::max(start, cursor), end));
+                let before = self
+                    .free
+                    .iter()
+                    .map(Self::region_of)
+                    .take_while(|(start, _)| *start < cursor);
+                after
+                    .chain(before)
+                    .filter_map(|(start, end)| Self::fit(start, end, size, align))
+                    .next()
+            }
+        };
+
+        let start = found.ok_or(AllocError::OutOfSpace)?;
+        let end = start.checked_add(size).ok_or(AllocError::Overflow)?;
+        self.free.remove(start..end);
+        self.cursor = end;
+        Ok(Segment::from(start..end))
+    }
+
+    /// Allocate a specific region, failing if any part of it is outside of
+    /// the universe or already allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// use segmap::allocator::{AllocError, RegionAllocator};
+    ///
+    /// let mut heap = RegionAllocator::new(0u16..100);
+    /// assert_eq!(heap.alloc_at(10..20), Ok(Segment::from(10..20)));
+    /// assert_eq!(heap.alloc_at(15..25), Err(AllocError::AlreadyAllocated));
+    /// assert_eq!(heap.alloc_at(90..110), Err(AllocError::OutOfBounds));
+    ///
+    /// // First fit now skips over the fixed allocation
+    /// assert_eq!(heap.alloc(15, 1), Ok(Segment::from(20..35)));
+    /// ```
+    pub fn alloc_at<R: RangeBounds<K>>(&mut self, region: R) -> Result<Segment<K>, AllocError> {
+        let (start, end) = self.normalize(&region)?;
+        match self.free.get_range_for(&start) {
+            Some(free) if Self::region_of(free).1 >= end => {
+                self.free.remove(start..end);
+                Ok(Segment::from(start..end))
+            }
+            _ => Err(AllocError::AlreadyAllocated),
+        }
+    }
+
+    /// Return a region to the free space.
+    ///
+    /// The region doesn't need to match a previous allocation exactly (so it
+    /// is possible to release only part of one), but all of it must currently
+    /// be allocated. Otherwise, [`AllocError::DoubleFree`] is returned and
+    /// nothing is freed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// use segmap::allocator::{AllocError, RegionAllocator};
+    ///
+    /// let mut heap = RegionAllocator::new(0u64..100);
+    /// let region = heap.alloc(10, 1).unwrap();
+    ///
+    /// assert_eq!(heap.free(region), Ok(()));
+    /// assert_eq!(heap.free(region), Err(AllocError::DoubleFree));
+    /// ```
+    pub fn free<R: RangeBounds<K>>(&mut self, region: R) -> Result<(), AllocError> {
+        let (start, end) = self.normalize(&region)?;
+        if self.free.map.iter_in(start..end).next().is_some() {
+            return Err(AllocError::DoubleFree);
+        }
+        self.free.insert(start..end);
+        Ok(())
+    }
+
+    /// Compute statistics about the layout of free space
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segmap::allocator::RegionAllocator;
+    ///
+    /// let mut heap = RegionAllocator::new(0u32..100);
+    /// let a = heap.alloc(10, 1).unwrap();
+    /// let _ = heap.alloc(10, 1).unwrap();
+    /// heap.free(a).unwrap();
+    ///
+    /// let stats = heap.stats();
+    /// assert_eq!(stats.free, 90);
+    /// assert_eq!(stats.allocated, 10);
+    /// assert_eq!(stats.free_regions, 2);
+    /// assert_eq!(stats.largest_free, 80);
+    /// assert!((stats.fragmentation() - 1.0 / 9.0).abs() < 1e-9);
+    /// ```
+    pub fn stats(&self) -> FragmentationStats<K> {
+        let mut free = K::ZERO;
+        let mut largest_free = K::ZERO;
+        for region in self.free.iter() {
+            let (start, end) = Self::region_of(region);
+            // Free regions are always inside the universe, so none of this
+            // can overflow
+            let size = end.checked_sub(start).unwrap_or(K::ZERO);
+            free = free.checked_add(size).unwrap_or(free);
+            largest_free = core::cmp::max(largest_free, size);
+        }
+
+        FragmentationStats {
+            free,
+            allocated: self
+                .end
+                .checked_sub(self.start)
+                .and_then(|total| total.checked_sub(free))
+                .unwrap_or(K::ZERO),
+            free_regions: self.free.len(),
+            largest_free,
+        }
+    }
+
+    /// Extend the end of the universe by `by` keys, which become free.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// use segmap::allocator::RegionAllocator;
+    ///
+    /// let mut heap = RegionAllocator::new(0u8..10);
+    /// heap.grow(10).unwrap();
+    /// assert_eq!(heap.universe(), Segment::from(0..20));
+    /// assert!(heap.free_regions().iter().eq(&[Segment::from(0..20)]));
+    /// ```
+    pub fn grow(&mut self, by: K) -> Result<(), AllocError> {
+        let end = self.end.checked_add(by).ok_or(AllocError::Overflow)?;
+        if end > self.end {
+            self.free.insert(self.end..end);
+            self.end = end;
+        }
+        Ok(())
+    }
+
+    /// Release `by` keys from the end of the universe.
+    ///
+    /// All of the released keys must be free, otherwise [`AllocError::InUse`]
+    /// is returned and the universe is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// use segmap::allocator::{AllocError, RegionAllocator};
+    ///
+    /// let mut heap = RegionAllocator::new(0u8..100);
+    /// heap.alloc_at(50..60).unwrap();
+    ///
+    /// assert_eq!(heap.shrink(60), Err(AllocError::InUse));
+    /// assert_eq!(heap.shrink(40), Ok(()));
+    /// assert_eq!(heap.universe(), Segment::from(0..60));
+    /// ```
+    pub fn shrink(&mut self, by: K) -> Result<(), AllocError> {
+        let end = self.end.checked_sub(by).ok_or(AllocError::OutOfBounds)?;
+        if end < self.start {
+            return Err(AllocError::OutOfBounds);
+        }
+        if end == self.end {
+            return Ok(());
+        }
+        match self.free.get_range_for(&end) {
+            Some(free) if Self::region_of(free).1 >= self.end => {
+                self.free.remove(end..);
+                self.end = end;
+                self.cursor = core::cmp::min(self.cursor, end);
+                Ok(())
+            }
+            _ => Err(AllocError::InUse),
+        }
+    }
+
+    /// Find where an aligned region of `size` could start in `[start, end)`
+    fn fit(start: K, end: K, size: K, align: K) -> Option<K> {
+        let at = start.align_up(align)?;
+        if at.checked_add(size)? <= end {
+            Some(at)
+        } else {
+            None
+        }
+    }
+
+    /// Unpack a stored free region, which is always half-open
+    fn region_of(region: &Segment<K>) -> (K, K) {
+        (
+            *region.start_value().expect("unbounded free region"),
+            *region.end_value().expect("unbounded free region"),
+        )
+    }
+
+    /// Convert a range to a non-empty, half-open `(start, end)` pair inside
+    /// the universe. Unbounded sides extend to the edge of the universe.
+    fn normalize<R: RangeBounds<K>>(&self, range: &R) -> Result<(K, K), AllocError> {
+        let start = match range.start_bound() {
+            Included(s) => *s,
+            Excluded(s) => s.checked_add(K::ONE).ok_or(AllocError::Overflow)?,
+            Unbounded => self.start,
+        };
+        let end = match range.end_bound() {
+            Included(e) => e.checked_add(K::ONE).ok_or(AllocError::Overflow)?,
+            Excluded(e) => *e,
+            Unbounded => self.end,
+        };
+        if start >= end {
+            Err(AllocError::ZeroSize)
+        } else if start < self.start || end > self.end {
+            Err(AllocError::OutOfBounds)
+        } else {
+            Ok((start, end))
+        }
+    }
+}
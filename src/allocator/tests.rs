@@ -0,0 +1,97 @@
+use super::*;
+use alloc::{vec, vec::Vec};
+
+#[test]
+fn first_fit_skips_regions_too_small() {
+    let mut heap = RegionAllocator::new(0u32..100);
+    let a = heap.alloc(10, 1).unwrap();
+    let _b = heap.alloc(10, 1).unwrap();
+    heap.free(a).unwrap();
+
+    // [0, 10) is free but too small
+    assert_eq!(heap.alloc(20, 1), Ok(Segment::from(20..40)));
+    assert_eq!(heap.alloc(10, 1), Ok(Segment::from(0..10)));
+}
+
+#[test]
+fn alignment_leaves_padding_free() {
+    let mut heap = RegionAllocator::new(0u32..100);
+    heap.alloc(3, 1).unwrap();
+    assert_eq!(heap.alloc(8, 16), Ok(Segment::from(16..24)));
+
+    // The padding before the aligned region is still free
+    assert_eq!(
+        heap.free_regions().iter().collect::<Vec<_>>(),
+        vec![&Segment::from(3..16), &Segment::from(24..100)]
+    );
+}
+
+#[test]
+fn next_fit_wraps_around() {
+    let mut heap = RegionAllocator::with_policy(0u32..30, FitPolicy::NextFit);
+    let a = heap.alloc(10, 1).unwrap();
+    let b = heap.alloc(10, 1).unwrap();
+    heap.free(a).unwrap();
+
+    // Continues after `b` rather than reusing `a`
+    assert_eq!(heap.alloc(5, 1), Ok(Segment::from(20..25)));
+    heap.free(b).unwrap();
+    assert_eq!(heap.alloc(5, 1), Ok(Segment::from(25..30)));
+
+    // Nothing left past the cursor, wrap to the start
+    assert_eq!(heap.alloc(15, 1), Ok(Segment::from(0..15)));
+}
+
+#[test]
+fn free_partial_and_double_free() {
+    let mut heap = RegionAllocator::new(0u64..100);
+    let a = heap.alloc(50, 1).unwrap();
+
+    // Freeing part of an allocation is allowed
+    heap.free(10..20).unwrap();
+    assert!(heap.is_allocated(&9));
+    assert!(!heap.is_allocated(&10));
+    assert!(heap.is_allocated(&20));
+
+    // But freeing the whole thing again overlaps free space
+    assert_eq!(heap.free(a), Err(AllocError::DoubleFree));
+    assert_eq!(heap.free(150..160), Err(AllocError::OutOfBounds));
+
+    heap.free(0..10).unwrap();
+    heap.free(20..50).unwrap();
+    assert_eq!(heap.free_regions().len(), 1);
+}
+
+#[test]
+fn stats_track_allocations() {
+    let mut heap = RegionAllocator::new(0u8..100);
+    assert_eq!(heap.stats().fragmentation(), 0.0);
+    let regions = (0..10)
+        .map(|_| heap.alloc(10, 1).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(heap.alloc(1, 1), Err(AllocError::OutOfSpace));
+
+    for region in regions.iter().step_by(2) {
+        heap.free(region).unwrap();
+    }
+    let stats = heap.stats();
+    assert_eq!(stats.free, 50);
+    assert_eq!(stats.allocated, 50);
+    assert_eq!(stats.free_regions, 5);
+    assert_eq!(stats.largest_free, 10);
+    assert!((stats.fragmentation() - 0.8).abs() < 1e-9);
+}
+
+#[test]
+fn grow_coalesces_with_free_tail() {
+    let mut heap = RegionAllocator::new(0u16..10);
+    heap.alloc(5, 1).unwrap();
+    heap.grow(10).unwrap();
+    assert!(heap.free_regions().iter().eq(&[Segment::from(5..20)]));
+    assert_eq!(heap.grow(u16::MAX), Err(AllocError::Overflow));
+
+    heap.shrink(15).unwrap();
+    assert!(heap.free_regions().is_empty());
+    assert_eq!(heap.shrink(1), Err(AllocError::InUse));
+    assert_eq!(heap.shrink(6), Err(AllocError::OutOfBounds));
+}
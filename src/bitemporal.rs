@@ -0,0 +1,359 @@
+//! A segment map over two time axes
+//!
+//! [`BitemporalSegmentMap`] records facts that hold over a range of *valid
+//! time* (when something was true in the world), along with the range of
+//! *recorded time* (when we believed it). Recording a correction closes the
+//! recorded time of the old belief rather than overwriting it, so the map can
+//! always answer "what did we believe at time `t`?".
+
+use alloc::vec::Vec;
+use core::{fmt, ops::RangeBounds};
+
+use crate::{map::collect_coalesced, Segment, SegmentMap};
+
+#[cfg(test)]
+mod tests;
+
+/// A single belief held by a [`BitemporalSegmentMap`]: `value` was believed
+/// to hold over `valid` for the duration of `recorded`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Fact<K, T, V> {
+    /// The valid time range this fact applies to
+    pub valid: Segment<K>,
+
+    /// When this fact was believed. Facts that are still believed have an
+    /// unbounded end.
+    pub recorded: Segment<T>,
+
+    /// The value believed to hold
+    pub value: V,
+}
+
+impl<K: Clone, T: Clone, V: Clone> Fact<&K, &T, &V> {
+    /// Clone the borrowed ranges and value into an owned `Fact`
+    pub fn cloned(&self) -> Fact<K, T, V> {
+        Fact {
+            valid: self.valid.cloned(),
+            recorded: self.recorded.cloned(),
+            value: self.value.clone(),
+        }
+    }
+}
+
+/// A currently held belief, and when it was recorded
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Belief<T, V> {
+    since: T,
+    value: V,
+}
+
+/// A map of valid time ranges over `K` to values, where every change is
+/// recorded at a transaction time `T`
+///
+/// Current beliefs are kept in a [`SegmentMap`], so they follow the same
+/// insertion and coalescing rules. When part of a belief is corrected or
+/// retracted, that part is moved to the closed history with its recorded time
+/// ending at the correction. The closed history is indexed by valid time, so
+/// [`BitemporalSegmentMap::get_as_of`] only needs a tree search and a binary
+/// search.
+///
+/// Recorded times must not go backwards (they usually come from a clock).
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// // Interest rates by day, recorded by day
+/// let mut rates = BitemporalSegmentMap::new();
+/// rates.record(0..100, 5, 10);
+///
+/// // On day 20 we learn that the rate changed on day 50
+/// rates.record(50..100, 6, 20);
+///
+/// assert_eq!(rates.as_of(&15).get(&60), Some(&5));
+/// assert_eq!(rates.as_of(&25).get(&60), Some(&6));
+/// assert!(rates.as_of(&25).iter().eq(vec![
+///     (&Segment::from(0..50), &5),
+///     (&Segment::from(50..100), &6),
+/// ]));
+///
+/// // Nothing was known before the first recording
+/// assert!(rates.as_of(&5).is_empty());
+/// ```
+#[derive(Clone)]
+pub struct BitemporalSegmentMap<K, T, V> {
+    current: SegmentMap<K, Belief<T, V>>,
+    closed: Vec<Fact<K, T, V>>,
+
+    /// Indices into `closed` of the facts covering each range of valid time,
+    /// in the order they were closed (which is also recorded time order,
+    /// since facts about the same key never overlap in recorded time)
+    index: SegmentMap<K, Vec<usize>>,
+    latest: Option<T>,
+}
+
+impl<K, T, V> BitemporalSegmentMap<K, T, V> {
+    /// Makes a new, empty `BitemporalSegmentMap`.
+    pub fn new() -> Self
+    where
+        K: Ord,
+    {
+        BitemporalSegmentMap {
+            current: SegmentMap::new(),
+            closed: Vec::new(),
+            index: SegmentMap::new(),
+            latest: None,
+        }
+    }
+
+    /// The most recent recorded time, if anything has been recorded
+    pub fn latest_recorded(&self) -> Option<&T> {
+        self.latest.as_ref()
+    }
+
+    /// Returns a reference to the value currently believed to hold at `at`
+    /// (in valid time), if any.
+    pub fn get(&self, at: &K) -> Option<&V>
+    where
+        K: Clone + Ord,
+    {
+        self.current.get(at).map(|belief| &belief.value)
+    }
+
+    /// Record that `value` holds over the `valid` range, as of recorded time
+    /// `at`.
+    ///
+    /// Any current beliefs overlapping `valid` are closed at `at`. Beliefs
+    /// recorded at exactly `at` are replaced, since they were never held for
+    /// any length of recorded time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is before [`BitemporalSegmentMap::latest_recorded`].
+    pub fn record<R>(&mut self, valid: R, value: V, at: T)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        T: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let valid = Segment::from(&valid);
+        self.close(&valid, &at);
+        self.current.set(valid, Belief { since: at, value });
+    }
+
+    /// Record that nothing is known to hold over the `valid` range, as of
+    /// recorded time `at`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is before [`BitemporalSegmentMap::latest_recorded`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = BitemporalSegmentMap::new();
+    /// map.record(0..10, "a", 1);
+    /// map.retract(5..10, 2);
+    ///
+    /// assert_eq!(map.as_of(&1).get(&7), Some(&"a"));
+    /// assert_eq!(map.as_of(&2).get(&7), None);
+    /// assert_eq!(map.get(&3), Some(&"a"));
+    /// ```
+    pub fn retract<R>(&mut self, valid: R, at: T)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        T: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.close(&Segment::from(&valid), &at);
+    }
+
+    /// Get the map of values over valid time, as it was believed at recorded
+    /// time `at`.
+    ///
+    /// This scans the closed history, so it takes linear time in the number
+    /// of facts ever recorded. To look up a single key, use
+    /// [`BitemporalSegmentMap::get_as_of`] instead.
+    pub fn as_of(&self, at: &T) -> SegmentMap<K, V>
+    where
+        K: Clone + Ord,
+        T: Ord,
+        V: Clone + Eq,
+    {
+        let mut believed: Vec<(Segment<K>, V)> = self
+            .closed
+            .iter()
+            .filter(|fact| fact.recorded.contains(at))
+            .map(|fact| (fact.valid.clone(), fact.value.clone()))
+            .chain(
+                self.current
+                    .iter()
+                    .filter(|(_, belief)| belief.since <= *at)
+                    .map(|(range, belief)| (range.clone(), belief.value.clone())),
+            )
+            .collect();
+
+        // Beliefs held at the same time never overlap, so ordering by start
+        // is enough to coalesce them
+        believed.sort_by(|(a, _), (b, _)| a.start.cmp(&b.start));
+        collect_coalesced(believed.into_iter())
+    }
+
+    /// Returns a reference to the value believed to hold at `at` (in valid
+    /// time), as of recorded time `recorded`, if any.
+    ///
+    /// This is the same as `self.as_of(recorded).get(at)`, without rebuilding
+    /// the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = BitemporalSegmentMap::new();
+    /// map.record(0..10, "a", 1);
+    /// map.record(5..10, "b", 3);
+    /// map.retract(0..10, 5);
+    ///
+    /// assert_eq!(map.get_as_of(&7, &0), None);
+    /// assert_eq!(map.get_as_of(&7, &2), Some(&"a"));
+    /// assert_eq!(map.get_as_of(&7, &4), Some(&"b"));
+    /// assert_eq!(map.get_as_of(&7, &5), None);
+    /// ```
+    pub fn get_as_of(&self, at: &K, recorded: &T) -> Option<&V>
+    where
+        K: Clone + Ord,
+        T: Ord,
+    {
+        if let Some(belief) = self.current.get(at) {
+            if belief.since <= *recorded {
+                return Some(&belief.value);
+            }
+        }
+
+        // Closed facts end at the time they were closed, so skip those that
+        // ended by `recorded`. The next one holds unless it started later.
+        let indices = self.index.get(at)?;
+        let next = indices.partition_point(|&i| {
+            self.closed[i]
+                .recorded
+                .end_value()
+                .is_some_and(|end| end <= recorded)
+        });
+        let fact = &self.closed[*indices.get(next)?];
+        if fact.recorded.contains(recorded) {
+            Some(&fact.value)
+        } else {
+            None
+        }
+    }
+
+    /// Get every fact ever believed about `at` (in valid time), ordered by
+    /// when it was recorded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = BitemporalSegmentMap::new();
+    /// map.record(0..10, "a", 1);
+    /// map.record(5..10, "b", 2);
+    ///
+    /// let history: Vec<_> = map.history(&7).iter().map(|f| f.cloned()).collect();
+    /// assert_eq!(
+    ///     history,
+    ///     vec![
+    ///         bitemporal::Fact {
+    ///             valid: Segment::from(5..10),
+    ///             recorded: Segment::from(1..2),
+    ///             value: "a",
+    ///         },
+    ///         bitemporal::Fact {
+    ///             valid: Segment::from(5..10),
+    ///             recorded: Segment::from(2..),
+    ///             value: "b",
+    ///         },
+    ///     ]
+    /// );
+    /// ```
+    pub fn history(&self, at: &K) -> Vec<Fact<&K, &T, &V>>
+    where
+        K: Clone + Ord,
+        T: Ord,
+    {
+        let mut facts: Vec<Fact<&K, &T, &V>> = self
+            .index
+            .get(at)
+            .into_iter()
+            .flatten()
+            .map(|&i| {
+                let fact = &self.closed[i];
+                Fact {
+                    valid: fact.valid.as_ref(),
+                    recorded: fact.recorded.as_ref(),
+                    value: &fact.value,
+                }
+            })
+            .collect();
+
+        // The current belief (if any) is always the most recent
+        if let Some((range, belief)) = self.current.get_range_value(at) {
+            facts.push(Fact {
+                valid: range.as_ref(),
+                recorded: Segment::from(&belief.since..),
+                value: &belief.value,
+            });
+        }
+        facts
+    }
+
+    /// Move current beliefs overlapping `valid` into the closed history,
+    /// ending at `at`
+    fn close(&mut self, valid: &Segment<K>, at: &T)
+    where
+        K: Clone + Ord,
+        T: Clone + Ord,
+        V: Clone + Eq,
+    {
+        if let Some(latest) = &self.latest {
+            assert!(at >= latest, "recorded time went backwards");
+        }
+        self.latest = Some(at.clone());
+
+        for (range, belief) in self.current.remove(valid).into_iter().flatten() {
+            if belief.since < *at {
+                // Make sure the whole range has a (possibly empty) list of
+                // facts, then add this one to each part of it
+                let closed = self.closed.len();
+                self.index.insert_in_gaps(&range, Vec::new());
+                for (part, mut indices) in self.index.remove(&range).into_iter().flatten() {
+                    indices.push(closed);
+                    self.index.set(part, indices);
+                }
+
+                self.closed.push(Fact {
+                    valid: range,
+                    recorded: Segment::from(belief.since..at.clone()),
+                    value: belief.value,
+                });
+            }
+        }
+    }
+}
+
+impl<K: Ord, T, V> Default for BitemporalSegmentMap<K, T, V> {
+    fn default() -> Self {
+        BitemporalSegmentMap::new()
+    }
+}
+
+impl<K: fmt::Debug, T: fmt::Debug, V: fmt::Debug> fmt::Debug for BitemporalSegmentMap<K, T, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BitemporalSegmentMap")
+            .field("current", &self.current.map)
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
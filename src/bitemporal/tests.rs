@@ -0,0 +1,78 @@
+use super::*;
+use alloc::{vec, vec::Vec};
+
+#[test]
+fn as_of_matches_state_after_each_recording() {
+    let mut map: BitemporalSegmentMap<u32, u32, u8> = BitemporalSegmentMap::new();
+    let mut plain = SegmentMap::new();
+    let mut snapshots: Vec<(u32, SegmentMap<u32, u8>)> = Vec::new();
+
+    // (recorded at, start, end, value), where `None` retracts
+    let ops: Vec<(u32, u32, u32, Option<u8>)> = vec![
+        (10, 0, 50, Some(1)),
+        (10, 20, 30, Some(2)),
+        (20, 10, 40, Some(3)),
+        (30, 0, 5, None),
+        (30, 45, 60, Some(1)),
+        (40, 25, 35, Some(3)),
+        (50, 0, 100, None),
+        (60, 30, 40, Some(4)),
+    ];
+    for (at, start, end, value) in ops {
+        match value {
+            Some(value) => {
+                map.record(start..end, value, at);
+                plain.set(start..end, value);
+            }
+            None => {
+                map.retract(start..end, at);
+                plain.clear_range(start..end);
+            }
+        }
+        if snapshots.last().map(|(t, _)| *t) == Some(at) {
+            snapshots.pop();
+        }
+        snapshots.push((at, plain.clone()));
+    }
+
+    assert!(map.as_of(&0).is_empty());
+    for (at, expected) in &snapshots {
+        assert_eq!(&map.as_of(at), expected);
+        assert_eq!(&map.as_of(&(at + 5)), expected);
+    }
+    for k in 0..100 {
+        assert_eq!(map.get(&k), plain.get(&k));
+    }
+
+    // Each key's history is ordered and doesn't overlap in recorded time
+    for k in 0..100 {
+        let history = map.history(&k);
+        for pair in history.windows(2) {
+            assert!(pair[0]
+                .recorded
+                .end
+                .cmp_start(&pair[1].recorded.start)
+                .is_lt());
+        }
+        for fact in &history {
+            let at = fact.recorded.start_value().unwrap();
+            assert_eq!(map.as_of(at).get(&k), Some(fact.value));
+        }
+    }
+
+    // Point lookups agree with rebuilding the whole map
+    for at in 0..70 {
+        let believed = map.as_of(&at);
+        for k in 0..100 {
+            assert_eq!(map.get_as_of(&k, &at), believed.get(&k));
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "recorded time went backwards")]
+fn recording_in_the_past_panics() {
+    let mut map = BitemporalSegmentMap::new();
+    map.record(0..10, "a", 5);
+    map.record(0..10, "b", 4);
+}
@@ -0,0 +1,369 @@
+//! [`SegmentMap`]s shared between threads
+//!
+//! [`ConcurrentSegmentMap`] publishes immutable [`PersistentSegmentMap`]
+//! snapshots, read-copy-update style. Readers grab the latest snapshot (an
+//! `O(1)` reference count bump) and never wait for a writer to finish an
+//! update; writers build the next version off to the side and only swap the
+//! published snapshot once it is complete.
+//!
+//! [`ShardedSegmentMap`] partitions the keys into fixed ranges, each with its
+//! own [`ConcurrentSegmentMap`], so that writers to different shards don't
+//! contend with each other.
+//!
+//! This module requires the `std` feature.
+
+use alloc::vec::Vec;
+use core::{fmt, ops::Deref, ops::RangeBounds};
+
+use crate::{
+    map::collect_coalesced,
+    segment::{End, Start},
+    Bound, PersistentSegmentMap, Segment, SegmentMap,
+};
+
+mod published;
+#[cfg(test)]
+mod tests;
+
+use published::Published;
+
+/// A [`SegmentMap`] that can be read from many threads while being updated
+///
+/// The map holds a published [`PersistentSegmentMap`] snapshot. Reads work
+/// on the snapshot that was current when they started, and never take a lock:
+/// the snapshot is published through an atomic pointer, so readers are never
+/// blocked by a writer (or by each other). Writers are serialized with each
+/// other, and each write (or [batch](ConcurrentSegmentMap::update)) is
+/// published atomically. A replaced snapshot is freed once the last reader
+/// holding it lets go, outside of any lock.
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// use segmap::concurrent::ConcurrentSegmentMap;
+/// use std::{sync::Arc, thread};
+///
+/// let routes = Arc::new(ConcurrentSegmentMap::new());
+/// routes.set(0..100, "primary");
+///
+/// let reader = {
+///     let routes = Arc::clone(&routes);
+///     thread::spawn(move || {
+///         // Always sees either the old or the new route, never a mix
+///         let snapshot = routes.snapshot();
+///         assert!(snapshot.get(&50).is_some());
+///     })
+/// };
+///
+/// routes.update(|batch| {
+///     batch.set(40..60, "secondary");
+///     batch.set(60..80, "secondary");
+/// });
+/// reader.join().unwrap();
+///
+/// assert_eq!(routes.get(&70), Some("secondary"));
+/// ```
+pub struct ConcurrentSegmentMap<K, V> {
+    published: Published<PersistentSegmentMap<K, V>>,
+}
+
+impl<K, V> ConcurrentSegmentMap<K, V> {
+    /// Makes a new, empty `ConcurrentSegmentMap`.
+    pub fn new() -> Self {
+        ConcurrentSegmentMap {
+            published: Published::new(PersistentSegmentMap::new()),
+        }
+    }
+
+    /// Get the latest published version of the map.
+    ///
+    /// This is cheap (`O(1)`), and the snapshot is unaffected by later
+    /// updates.
+    pub fn snapshot(&self) -> PersistentSegmentMap<K, V> {
+        PersistentSegmentMap::clone(&self.published.load())
+    }
+
+    /// Returns a copy of the value covering `at` in the latest snapshot, if
+    /// any.
+    pub fn get(&self, at: &K) -> Option<V>
+    where
+        K: Clone + Ord,
+        V: Clone,
+    {
+        self.snapshot().get(at).cloned()
+    }
+
+    /// Returns `true` if any range in the latest snapshot covers `at`.
+    pub fn contains(&self, at: &K) -> bool
+    where
+        K: Clone + Ord,
+    {
+        self.snapshot().contains(at)
+    }
+
+    /// Set a value for the specified range, like [`SegmentMap::set`].
+    pub fn set<R>(&self, range: R, value: V)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.update(|batch| batch.set(range, value));
+    }
+
+    /// Remove all values in a given range, like [`SegmentMap::clear_range`].
+    pub fn clear_range<R>(&self, range: R)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.update(|batch| batch.clear_range(range));
+    }
+
+    /// Apply a batch of changes, publishing them together.
+    ///
+    /// Readers keep seeing the previous version until `f` returns. Other
+    /// writers wait for the batch to finish.
+    pub fn update<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut Batch<K, V>) -> T,
+    {
+        let mut writer = self.published.write();
+        let mut batch = Batch {
+            map: self.snapshot(),
+        };
+        let result = f(&mut batch);
+        let old = writer.replace(batch.map);
+
+        // Let other writers in before freeing the old version (if nothing
+        // else is holding on to it)
+        drop(writer);
+        drop(old);
+        result
+    }
+}
+
+impl<K, V> Default for ConcurrentSegmentMap<K, V> {
+    fn default() -> Self {
+        ConcurrentSegmentMap::new()
+    }
+}
+
+impl<K, V> From<SegmentMap<K, V>> for ConcurrentSegmentMap<K, V> {
+    fn from(map: SegmentMap<K, V>) -> Self {
+        ConcurrentSegmentMap {
+            published: Published::new(PersistentSegmentMap::from(map)),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for ConcurrentSegmentMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ConcurrentSegmentMap")
+            .field(&self.snapshot())
+            .finish()
+    }
+}
+
+/// A batch of changes to a [`ConcurrentSegmentMap`], published together
+///
+/// See [`ConcurrentSegmentMap::update`]. The batch dereferences to the map as
+/// it will be published, including the changes made so far.
+#[derive(Debug)]
+pub struct Batch<K, V> {
+    map: PersistentSegmentMap<K, V>,
+}
+
+impl<K, V> Batch<K, V> {
+    /// Set a value for the specified range, like [`SegmentMap::set`].
+    pub fn set<R>(&mut self, range: R, value: V)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.map = self.map.set(range, value);
+    }
+
+    /// Insert a value for the specified range, returning any values that
+    /// were overwritten, like [`SegmentMap::insert`].
+    pub fn insert<R>(&mut self, range: R, value: V) -> Option<SegmentMap<K, V>>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let (map, removed) = self.map.insert(range, value);
+        self.map = map;
+        removed
+    }
+
+    /// Remove all values in a given range, returning them, like
+    /// [`SegmentMap::remove`].
+    pub fn remove<R>(&mut self, range: R) -> Option<SegmentMap<K, V>>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let (map, removed) = self.map.remove(range);
+        self.map = map;
+        removed
+    }
+
+    /// Remove all values in a given range, like [`SegmentMap::clear_range`].
+    pub fn clear_range<R>(&mut self, range: R)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.map = self.map.clear_range(range);
+    }
+}
+
+impl<K, V> Deref for Batch<K, V> {
+    type Target = PersistentSegmentMap<K, V>;
+    fn deref(&self) -> &PersistentSegmentMap<K, V> {
+        &self.map
+    }
+}
+
+/// A concurrent map split into shards by fixed key ranges, for write-heavy
+/// workloads
+///
+/// Each shard is a [`ConcurrentSegmentMap`] covering the keys between two
+/// boundaries, so writes to different shards proceed in parallel. A write
+/// spanning several shards is applied to each in turn (in key order), so a
+/// reader may briefly see it applied to some shards and not others.
+///
+/// Ranges are split at shard boundaries internally, but
+/// [`ShardedSegmentMap::snapshot`] coalesces them again.
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// use segmap::concurrent::ShardedSegmentMap;
+///
+/// let map = ShardedSegmentMap::with_boundaries(vec![100, 200, 300]);
+/// assert_eq!(map.shard_count(), 4);
+///
+/// map.set(50..250, "a");
+/// assert_eq!(map.get(&150), Some("a"));
+/// assert!(map.snapshot().iter().eq(vec![(&Segment::from(50..250), &"a")]));
+/// ```
+pub struct ShardedSegmentMap<K, V> {
+    boundaries: Vec<K>,
+    shards: Vec<ConcurrentSegmentMap<K, V>>,
+}
+
+impl<K, V> ShardedSegmentMap<K, V> {
+    /// Makes a new, empty `ShardedSegmentMap`, with shards split at each of
+    /// the given keys (so `n` boundaries give `n + 1` shards).
+    pub fn with_boundaries<I>(boundaries: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Ord,
+    {
+        let mut boundaries: Vec<K> = boundaries.into_iter().collect();
+        boundaries.sort();
+        boundaries.dedup();
+        let shards = (0..=boundaries.len())
+            .map(|_| ConcurrentSegmentMap::new())
+            .collect();
+        ShardedSegmentMap { boundaries, shards }
+    }
+
+    /// The number of shards
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns a copy of the value covering `at`, if any.
+    pub fn get(&self, at: &K) -> Option<V>
+    where
+        K: Clone + Ord,
+        V: Clone,
+    {
+        let shard = self.boundaries.partition_point(|boundary| boundary <= at);
+        self.shards[shard].get(at)
+    }
+
+    /// Combine the latest snapshot of every shard into a single map.
+    ///
+    /// Shards are read one after another, so this isn't an atomic view of
+    /// the whole map when writes span shards.
+    pub fn snapshot(&self) -> SegmentMap<K, V>
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let snapshots: Vec<_> = self.shards.iter().map(|s| s.snapshot()).collect();
+        collect_coalesced(
+            snapshots
+                .iter()
+                .flat_map(|snapshot| snapshot.iter())
+                .map(|(range, value)| (range.clone(), value.clone())),
+        )
+    }
+
+    /// Set a value for the specified range, like [`SegmentMap::set`].
+    pub fn set<R>(&self, range: R, value: V)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        for (shard, piece) in self.split(Segment::from(&range)) {
+            shard.set(piece, value.clone());
+        }
+    }
+
+    /// Remove all values in a given range, like [`SegmentMap::clear_range`].
+    pub fn clear_range<R>(&self, range: R)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        for (shard, piece) in self.split(Segment::from(&range)) {
+            shard.clear_range(piece);
+        }
+    }
+
+    /// Split `range` into the parts covered by each shard
+    fn split(&self, range: Segment<K>) -> Vec<(&ConcurrentSegmentMap<K, V>, Segment<K>)>
+    where
+        K: Clone + Ord,
+    {
+        let mut pieces = Vec::new();
+        for (index, shard) in self.shards.iter().enumerate() {
+            let bounds = Segment {
+                start: Start(match index.checked_sub(1) {
+                    Some(i) => Bound::Included(self.boundaries[i].clone()),
+                    None => Bound::Unbounded,
+                }),
+                end: End(match self.boundaries.get(index) {
+                    Some(boundary) => Bound::Excluded(boundary.clone()),
+                    None => Bound::Unbounded,
+                }),
+            };
+            if let Some(piece) = bounds.intersection(&range) {
+                pieces.push((shard, piece));
+            }
+        }
+        pieces
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for ShardedSegmentMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedSegmentMap")
+            .field("boundaries", &self.boundaries)
+            .field("shards", &self.shards)
+            .finish()
+    }
+}
@@ -0,0 +1,101 @@
+//! A lock-free cell holding the latest published value
+
+use core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering::SeqCst},
+};
+use std::{
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    thread,
+};
+
+/// An atomically replaceable `Arc<T>`
+///
+/// Loading never blocks: a reader announces itself in one of two counters,
+/// loads the pointer and bumps its reference count, then leaves again. A
+/// writer swaps the pointer, then waits for both counters to drain, after
+/// which no reader can still be about to bump the old pointer's count. Only
+/// then does it hand the old value back, so it's dropped by whoever lets go
+/// of it last, outside of any lock.
+///
+/// The two counters are used alternately (picked by `epoch`), so that a
+/// writer only waits for readers that started before it swapped, and not
+/// for a steady stream of new ones.
+pub(super) struct Published<T> {
+    /// Always the result of `Arc::into_raw`, owning one strong count
+    ptr: AtomicPtr<T>,
+    epoch: AtomicUsize,
+    readers: [AtomicUsize; 2],
+    writer: Mutex<()>,
+    _owns: PhantomData<Arc<T>>,
+}
+
+impl<T> Published<T> {
+    pub(super) fn new(value: T) -> Self {
+        Published {
+            ptr: AtomicPtr::new(Arc::into_raw(Arc::new(value)) as *mut T),
+            epoch: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            writer: Mutex::new(()),
+            _owns: PhantomData,
+        }
+    }
+
+    /// Get the latest published value
+    pub(super) fn load(&self) -> Arc<T> {
+        let readers = &self.readers[self.epoch.load(SeqCst) % 2];
+        readers.fetch_add(1, SeqCst);
+        let ptr = self.ptr.load(SeqCst);
+        // SAFETY: `ptr` came from `Arc::into_raw`, and a writer that swapped
+        // it out since waits for `readers` to drain before releasing its
+        // count, so the value is still alive
+        let value = unsafe {
+            Arc::increment_strong_count(ptr);
+            Arc::from_raw(ptr)
+        };
+        readers.fetch_sub(1, SeqCst);
+        value
+    }
+
+    /// Lock out other writers until the returned guard is dropped
+    pub(super) fn write(&self) -> Writer<'_, T> {
+        Writer {
+            // The published value is only ever replaced whole, so it's still
+            // valid even if a writer panicked while holding the lock
+            _guard: self.writer.lock().unwrap_or_else(PoisonError::into_inner),
+            cell: self,
+        }
+    }
+}
+
+impl<T> Drop for Published<T> {
+    fn drop(&mut self) {
+        // SAFETY: the cell owns one strong count of the current pointer
+        drop(unsafe { Arc::from_raw(*self.ptr.get_mut()) });
+    }
+}
+
+/// Exclusive access to replace the value in a [`Published`] cell
+pub(super) struct Writer<'a, T> {
+    cell: &'a Published<T>,
+    _guard: MutexGuard<'a, ()>,
+}
+
+impl<T> Writer<'_, T> {
+    /// Publish `value`, returning the previously published value once no
+    /// reader can still be loading it.
+    pub(super) fn replace(&mut self, value: T) -> Arc<T> {
+        let cell = self.cell;
+        let old = cell
+            .ptr
+            .swap(Arc::into_raw(Arc::new(value)) as *mut T, SeqCst);
+        for _ in 0..2 {
+            let readers = &cell.readers[cell.epoch.fetch_add(1, SeqCst) % 2];
+            while readers.load(SeqCst) != 0 {
+                thread::yield_now();
+            }
+        }
+        // SAFETY: the swap took over the cell's strong count of `old`
+        unsafe { Arc::from_raw(old) }
+    }
+}
@@ -0,0 +1,91 @@
+use super::*;
+use std::{sync::Arc, thread, vec};
+
+#[test]
+fn readers_only_see_whole_batches() {
+    let map = Arc::new(ConcurrentSegmentMap::new());
+    map.set(0..100, 0u32);
+
+    // Each batch sets the two halves separately, so a torn read would see
+    // different values on either side
+    let writer = {
+        let map = Arc::clone(&map);
+        thread::spawn(move || {
+            for i in 1..=200 {
+                map.update(|batch| {
+                    batch.set(0..50, i);
+                    batch.set(50..100, i);
+                });
+            }
+        })
+    };
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let map = Arc::clone(&map);
+            thread::spawn(move || {
+                for _ in 0..500 {
+                    let snapshot = map.snapshot();
+                    assert_eq!(snapshot.len(), 1);
+                    assert_eq!(snapshot.get(&10), snapshot.get(&90));
+                }
+            })
+        })
+        .collect();
+
+    writer.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert_eq!(map.get(&75), Some(200));
+}
+
+#[test]
+fn replaced_snapshots_are_freed() {
+    let tracker = Arc::new(());
+    let map = ConcurrentSegmentMap::new();
+    map.set(0..10, Arc::clone(&tracker));
+    assert_eq!(Arc::strong_count(&tracker), 2);
+
+    // A snapshot keeps the old version alive after it's replaced
+    let snapshot = map.snapshot();
+    map.clear_range(..);
+    assert_eq!(Arc::strong_count(&tracker), 2);
+    drop(snapshot);
+    assert_eq!(Arc::strong_count(&tracker), 1);
+
+    map.set(0..10, Arc::clone(&tracker));
+    drop(map);
+    assert_eq!(Arc::strong_count(&tracker), 1);
+}
+
+#[test]
+fn sharded_writes_match_segment_map() {
+    let sharded = ShardedSegmentMap::with_boundaries(vec![30, 10, 20, 20]);
+    assert_eq!(sharded.shard_count(), 4);
+
+    let mut expected = SegmentMap::new();
+    let ops: Vec<(u32, u32, Option<u8>)> = vec![
+        (0, 40, Some(1)),
+        (5, 15, Some(2)),
+        (18, 32, None),
+        (20, 25, Some(1)),
+        (25, 35, Some(1)),
+        (9, 11, Some(3)),
+    ];
+    for (start, end, value) in ops {
+        match value {
+            Some(value) => {
+                sharded.set(start..end, value);
+                expected.set(start..end, value);
+            }
+            None => {
+                sharded.clear_range(start..end);
+                expected.clear_range(start..end);
+            }
+        }
+        assert_eq!(sharded.snapshot(), expected);
+    }
+    for k in 0..45 {
+        assert_eq!(sharded.get(&k), expected.get(&k).copied());
+    }
+}
@@ -0,0 +1,615 @@
+//! A read-only [`SegmentMap`], laid out for fast lookups
+//!
+//! Finding a point in a [`SegmentMap`] descends a `BTreeMap`, following a
+//! pointer to a new node at each level. [`FrozenSegmentMap`] instead stores
+//! its ranges in a single array, in Eytzinger (breadth-first) order: the
+//! children of the range at index `k` are at `2k + 1` and `2k + 2`. A lookup
+//! is then a binary search with no pointers to follow, and the first few
+//! levels of the search share a handful of cache lines, however many ranges
+//! are stored. Values are kept in a parallel array, in the same order.
+//!
+//! Build one with [`SegmentMap::freeze`], and turn it back into a map to
+//! change it with [`FrozenSegmentMap::thaw`].
+//!
+//! [`FrozenSegmentSlice`] borrows its ranges from a sorted slice instead, so
+//! it can be built in a `const` context.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt::{self, Debug},
+    iter::FusedIterator,
+    ops::{Bound::*, RangeBounds},
+};
+
+use crate::{
+    map::Key,
+    segment::{Segment, Start},
+    SegmentMap, SegmentSet,
+};
+
+mod slice;
+#[cfg(test)]
+mod tests;
+
+pub use slice::{FrozenSegmentSlice, SliceIter};
+
+/// # FrozenSegmentMap
+///
+/// A read-only map of non-overlapping ranges to values, with faster lookups
+/// than a [`SegmentMap`]. See the [module documentation](self) for how it's
+/// laid out.
+///
+/// A `FrozenSegmentMap` is [`Sync`] (as long as its keys and values are), so
+/// a table that's built once at runtime can be shared in a `static` (through
+/// something like `OnceLock`). For a table that's known at compile time, a
+/// [`FrozenSegmentSlice`] can be built in the `static` itself.
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// use std::sync::OnceLock;
+///
+/// static CATEGORIES: OnceLock<FrozenSegmentMap<char, &str>> = OnceLock::new();
+///
+/// fn category(c: char) -> Option<&'static str> {
+///     let categories = CATEGORIES.get_or_init(|| {
+///         let mut map = SegmentMap::new();
+///         map.set('0'..='9', "digit");
+///         map.set('A'..='Z', "upper");
+///         map.set('a'..='z', "lower");
+///         map.freeze()
+///     });
+///     categories.get(&c).copied()
+/// }
+///
+/// assert_eq!(category('q'), Some("lower"));
+/// assert_eq!(category('7'), Some("digit"));
+/// assert_eq!(category('!'), None);
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct FrozenSegmentMap<K, V> {
+    // Both in Eytzinger order
+    ranges: Box<[Segment<K>]>,
+    values: Box<[V]>,
+}
+
+impl<K, V> SegmentMap<K, V> {
+    /// Convert the map into a [`FrozenSegmentMap`], which can't be changed
+    /// but is faster to search.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(20..30, "b");
+    ///
+    /// let frozen = map.clone().freeze();
+    /// assert_eq!(frozen.get(&25), Some(&"b"));
+    /// assert_eq!(frozen.get(&15), None);
+    /// assert_eq!(frozen.thaw(), map);
+    /// ```
+    pub fn freeze(self) -> FrozenSegmentMap<K, V> {
+        let len = self.len();
+
+        // Place each range (in order) at the next position of an in-order
+        // walk over the layout
+        let mut slots: Vec<Option<(Segment<K>, V)>> = (0..len).map(|_| None).collect();
+        let positions = core::iter::successors(first(len), |&k| next_in_order(k, len));
+        for ((key, value), k) in self.map.into_iter().zip(positions) {
+            slots[k] = Some((key.0, value));
+        }
+
+        let (ranges, values): (Vec<_>, Vec<_>) = slots
+            .into_iter()
+            .map(|slot| slot.expect("every position is filled"))
+            .unzip();
+        FrozenSegmentMap {
+            ranges: ranges.into_boxed_slice(),
+            values: values.into_boxed_slice(),
+        }
+    }
+}
+
+impl<K, V> FrozenSegmentMap<K, V> {
+    /// Convert back into a [`SegmentMap`] that can be changed.
+    pub fn thaw(self) -> SegmentMap<K, V>
+    where
+        K: Ord,
+    {
+        let len = self.len();
+        let mut slots: Vec<Option<(Segment<K>, V)>> = Vec::from(self.ranges)
+            .into_iter()
+            .zip(Vec::from(self.values))
+            .map(Some)
+            .collect();
+
+        // Ranges were coalesced when frozen, so they can go straight in
+        let positions = core::iter::successors(first(len), |&k| next_in_order(k, len));
+        SegmentMap {
+            map: positions
+                .map(|k| {
+                    let (range, value) = slots[k].take().expect("each position is visited once");
+                    (Key(range), value)
+                })
+                .collect(),
+            store: Vec::new(),
+        }
+    }
+
+    /// Returns the number of ranges in the map.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Returns `true` if the map contains no ranges.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Returns a reference to the value covering `at`, if any.
+    pub fn get(&self, at: &K) -> Option<&V>
+    where
+        K: Ord,
+    {
+        self.get_range_value(at).map(|(_, value)| value)
+    }
+
+    /// Returns the range-value pair covering `at`, if any.
+    pub fn get_range_value(&self, at: &K) -> Option<(&Segment<K>, &V)>
+    where
+        K: Ord,
+    {
+        let start = Start(Included(at));
+        let k = self.last_where(|range| range.start.as_ref() <= start)?;
+        let range = &self.ranges[k];
+        if range.contains(at) {
+            Some((range, &self.values[k]))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if any range in the map covers `point`.
+    pub fn contains(&self, point: &K) -> bool
+    where
+        K: Ord,
+    {
+        self.get_range_value(point).is_some()
+    }
+
+    /// Gets an iterator over the sorted ranges in the map.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let len = self.len();
+        Iter {
+            walk: Walk {
+                map: self,
+                front: first(len),
+                back: last(len),
+            },
+            len,
+        }
+    }
+
+    /// Gets an iterator over the sorted ranges in the map that overlap
+    /// `range`. This is the same as [`SegmentMap::iter_in`], but only visits
+    /// the ranges it yields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(10..20, "b");
+    /// map.set(30..40, "c");
+    /// let frozen = map.freeze();
+    ///
+    /// assert!(frozen.iter_in(5..25).eq(vec![
+    ///     (&Segment::from(0..10), &"a"),
+    ///     (&Segment::from(10..20), &"b"),
+    /// ]));
+    /// assert_eq!(frozen.iter_in(22..28).next(), None);
+    /// ```
+    pub fn iter_in<R>(&self, range: R) -> IterIn<'_, K, V>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+    {
+        let range = Segment::from(&range);
+        let len = self.len();
+
+        // The first range that isn't entirely before `range`
+        let front = match self.last_where(|r| r.start <= range.start) {
+            Some(k) if self.ranges[k].overlaps(&range) => Some(k),
+            Some(k) => next_in_order(k, len),
+            None => first(len),
+        };
+
+        // If that doesn't overlap, it's after `range`, and so is everything
+        // else. Otherwise, the last range overlapping is the last starting
+        // before `range` ends.
+        let walk = match front {
+            Some(k) if self.ranges[k].overlaps(&range) => Walk {
+                map: self,
+                front,
+                back: self.last_where(|r| !range.end.cmp_start(&r.start).is_lt()),
+            },
+            _ => Walk {
+                map: self,
+                front: None,
+                back: None,
+            },
+        };
+        IterIn(walk)
+    }
+
+    /// Gets an iterator over all maximally-sized gaps between ranges in the
+    /// map. This is the same as [`SegmentMap::iter_gaps`].
+    pub fn iter_gaps(&self) -> Gaps<'_, K, V> {
+        let mut iter = self.iter();
+        Gaps {
+            front: iter.next().map(|(range, _)| range),
+            back: iter.next_back().map(|(range, _)| range),
+            iter,
+        }
+    }
+
+    /// Get all gaps between ranges in the map, as a set. This is the same as
+    /// [`SegmentMap::gaps`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(20..30, "b");
+    /// map.set(40..50, "c");
+    /// let frozen = map.freeze();
+    ///
+    /// assert!(frozen.gaps().iter().eq(&[
+    ///     Segment::from(&10..&20),
+    ///     Segment::from(&30..&40),
+    /// ]));
+    /// ```
+    pub fn gaps(&self) -> SegmentSet<&K>
+    where
+        K: Ord,
+    {
+        SegmentSet {
+            map: SegmentMap {
+                map: self.iter_gaps().map(|r| (Key(r), ())).collect(),
+                store: Vec::new(),
+            },
+        }
+    }
+
+    /// The last range (in order) for which `before` holds, if any. `before`
+    /// must hold for every range up to some point, and none after it.
+    fn last_where<F>(&self, before: F) -> Option<usize>
+    where
+        F: Fn(&Segment<K>) -> bool,
+    {
+        let mut found = None;
+        let mut k = 0;
+        while let Some(range) = self.ranges.get(k) {
+            if before(range) {
+                found = Some(k);
+                k = 2 * k + 2;
+            } else {
+                k = 2 * k + 1;
+            }
+        }
+        found
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for FrozenSegmentMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a FrozenSegmentMap<K, V> {
+    type Item = (&'a Segment<K>, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Walking the layout in order. Ranges at `2k + 1` and below are before the
+// range at `k`, and those at `2k + 2` and below are after it.
+
+/// The position of the first range, in a layout of `len` ranges
+fn first(len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let mut k = 0;
+    while 2 * k + 1 < len {
+        k = 2 * k + 1;
+    }
+    Some(k)
+}
+
+/// The position of the last range, in a layout of `len` ranges
+fn last(len: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let mut k = 0;
+    while 2 * k + 2 < len {
+        k = 2 * k + 2;
+    }
+    Some(k)
+}
+
+/// The position of the range after the one at `k`
+fn next_in_order(mut k: usize, len: usize) -> Option<usize> {
+    // The first range after `k` below it
+    if 2 * k + 2 < len {
+        k = 2 * k + 2;
+        while 2 * k + 1 < len {
+            k = 2 * k + 1;
+        }
+        return Some(k);
+    }
+
+    // Otherwise, the first range above that `k` is before
+    while k > 0 && k % 2 == 0 {
+        k = (k - 1) / 2;
+    }
+    if k == 0 {
+        None
+    } else {
+        Some((k - 1) / 2)
+    }
+}
+
+/// The position of the range before the one at `k`
+fn next_back_in_order(mut k: usize, len: usize) -> Option<usize> {
+    // The last range before `k` below it
+    if 2 * k + 1 < len {
+        k = 2 * k + 1;
+        while 2 * k + 2 < len {
+            k = 2 * k + 2;
+        }
+        return Some(k);
+    }
+
+    // Otherwise, the first range above that `k` is after
+    while k % 2 == 1 {
+        k = (k - 1) / 2;
+    }
+    if k == 0 {
+        None
+    } else {
+        Some((k - 1) / 2)
+    }
+}
+
+/// An in-order walk between two positions (inclusive) in a frozen map
+struct Walk<'a, K, V> {
+    map: &'a FrozenSegmentMap<K, V>,
+    front: Option<usize>,
+    back: Option<usize>,
+}
+
+impl<'a, K, V> Walk<'a, K, V> {
+    fn get(&self, k: usize) -> (&'a Segment<K>, &'a V) {
+        (&self.map.ranges[k], &self.map.values[k])
+    }
+}
+
+impl<'a, K, V> Iterator for Walk<'a, K, V> {
+    type Item = (&'a Segment<K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let k = self.front?;
+        if self.back == Some(k) {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = next_in_order(k, self.map.len());
+        }
+        Some(self.get(k))
+    }
+}
+impl<K, V> DoubleEndedIterator for Walk<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let k = self.back?;
+        if self.front == Some(k) {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = next_back_in_order(k, self.map.len());
+        }
+        Some(self.get(k))
+    }
+}
+
+impl<K, V> Clone for Walk<'_, K, V> {
+    fn clone(&self) -> Self {
+        Walk {
+            map: self.map,
+            front: self.front,
+            back: self.back,
+        }
+    }
+}
+
+/// An iterator over the ranges of a `FrozenSegmentMap`.
+///
+/// This `struct` is created by the [`iter`] method on [`FrozenSegmentMap`].
+/// See its documentation for more.
+///
+/// [`iter`]: FrozenSegmentMap::iter
+pub struct Iter<'a, K, V> {
+    walk: Walk<'a, K, V>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a Segment<K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.walk.next()?;
+        self.len -= 1;
+        Some(next)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let next = self.walk.next_back()?;
+        self.len -= 1;
+        Some(next)
+    }
+}
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            walk: self.walk.clone(),
+            len: self.len,
+        }
+    }
+}
+impl<K: Debug, V: Debug> Debug for Iter<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the ranges of a `FrozenSegmentMap` that overlap a range.
+///
+/// This `struct` is created by the [`iter_in`] method on [`FrozenSegmentMap`].
+/// See its documentation for more.
+///
+/// [`iter_in`]: FrozenSegmentMap::iter_in
+pub struct IterIn<'a, K, V>(Walk<'a, K, V>);
+
+impl<'a, K, V> Iterator for IterIn<'a, K, V> {
+    type Item = (&'a Segment<K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+impl<K, V> DoubleEndedIterator for IterIn<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+impl<K, V> FusedIterator for IterIn<'_, K, V> {}
+
+impl<K, V> Clone for IterIn<'_, K, V> {
+    fn clone(&self) -> Self {
+        IterIn(self.0.clone())
+    }
+}
+impl<K: Debug, V: Debug> Debug for IterIn<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the gaps between ranges of a `FrozenSegmentMap`.
+///
+/// This `struct` is created by the [`iter_gaps`] method on
+/// [`FrozenSegmentMap`]. See its documentation for more.
+///
+/// [`iter_gaps`]: FrozenSegmentMap::iter_gaps
+pub struct Gaps<'a, K, V> {
+    // The ranges on either side of the gaps left to iterate. If there's only
+    // one range left, it's always in `front`.
+    front: Option<&'a Segment<K>>,
+    iter: Iter<'a, K, V>,
+    back: Option<&'a Segment<K>>,
+}
+
+impl<'a, K: Ord, V> Gaps<'a, K, V> {
+    /// The gap between two ranges, or `None` if they touch
+    fn gap(before: &'a Segment<K>, after: &'a Segment<K>) -> Option<Segment<&'a K>> {
+        let gap = Segment {
+            start: before
+                .bound_after()
+                .expect("Unbounded internal range in FrozenSegmentMap"),
+            end: after
+                .bound_before()
+                .expect("Unbounded internal range in FrozenSegmentMap"),
+        };
+        if gap.end.cmp_start(&gap.start).is_lt() {
+            None
+        } else {
+            Some(gap)
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for Gaps<'a, K, V> {
+    type Item = Segment<&'a K>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let before = self.front?;
+            let after = match self.iter.next() {
+                Some((after, _)) => after,
+                None => self.back.take()?,
+            };
+            self.front = Some(after);
+            if let Some(gap) = Self::gap(before, after) {
+                return Some(gap);
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // At most one gap between each pair of ranges, but touching ranges
+        // have none
+        match (self.front, self.back) {
+            (Some(_), Some(_)) => (0, Some(self.iter.len() + 1)),
+            _ => (0, Some(0)),
+        }
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+impl<K: Ord, V> DoubleEndedIterator for Gaps<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            let after = self.back?;
+            self.back = self.iter.next_back().map(|(before, _)| before);
+            let before = self.back.or(self.front)?;
+            if let Some(gap) = Self::gap(before, after) {
+                return Some(gap);
+            }
+        }
+    }
+}
+impl<K: Ord, V> FusedIterator for Gaps<'_, K, V> {}
+
+impl<K, V> Clone for Gaps<'_, K, V> {
+    fn clone(&self) -> Self {
+        Gaps {
+            front: self.front,
+            iter: self.iter.clone(),
+            back: self.back,
+        }
+    }
+}
+impl<K: Debug + Ord, V> Debug for Gaps<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
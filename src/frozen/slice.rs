@@ -0,0 +1,251 @@
+use core::{
+    fmt::{self, Debug},
+    iter::{FusedIterator, Zip},
+    ops::{Bound::*, RangeBounds},
+    slice,
+};
+
+use crate::{
+    segment::{Segment, Start},
+    SegmentMap,
+};
+
+/// # FrozenSegmentSlice
+///
+/// A read-only map of non-overlapping ranges to values, borrowed from a pair
+/// of sorted slices.
+///
+/// Unlike a [`FrozenSegmentMap`], this can be built in a `const` context with
+/// [`FrozenSegmentSlice::from_sorted_slices`], so a fixed table can be a
+/// plain `static` (no lazy initialization or allocation needed). Ranges are
+/// kept in sorted order rather than the [`FrozenSegmentMap`] layout, so
+/// lookups are an ordinary binary search.
+///
+/// [`FrozenSegmentMap`]: super::FrozenSegmentMap
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// const fn chars(start: char, end: char) -> Segment<char> {
+///     Segment::from_bounds(Bound::Included(start), Bound::Included(end))
+/// }
+///
+/// static CATEGORIES: FrozenSegmentSlice<'static, char, &str> = FrozenSegmentSlice::from_sorted_slices(
+///     &[chars('0', '9'), chars('A', 'Z'), chars('a', 'z')],
+///     &["digit", "upper", "lower"],
+/// );
+///
+/// assert_eq!(CATEGORIES.get(&'q'), Some(&"lower"));
+/// assert_eq!(CATEGORIES.get(&'7'), Some(&"digit"));
+/// assert_eq!(CATEGORIES.get(&'!'), None);
+/// ```
+#[derive(PartialEq, Eq, Hash)]
+pub struct FrozenSegmentSlice<'a, K, V> {
+    // Both in sorted order
+    ranges: &'a [Segment<K>],
+    values: &'a [V],
+}
+
+impl<'a, K, V> FrozenSegmentSlice<'a, K, V> {
+    /// Borrow a map from its ranges and their values (at the same indices).
+    ///
+    /// The ranges must be sorted, and must not overlap. This can't be checked
+    /// in a `const` context, so lookups in a map that breaks this give
+    /// meaningless (but safe) results. Touching ranges with the same value
+    /// are allowed, though they wouldn't be coalesced as in a [`SegmentMap`].
+    ///
+    /// # Panics
+    ///
+    /// If `ranges` and `values` aren't the same length.
+    pub const fn from_sorted_slices(ranges: &'a [Segment<K>], values: &'a [V]) -> Self {
+        assert!(
+            ranges.len() == values.len(),
+            "each range needs exactly one value"
+        );
+        FrozenSegmentSlice { ranges, values }
+    }
+
+    /// Returns the number of ranges in the map.
+    pub const fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Returns `true` if the map contains no ranges.
+    pub const fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Returns a reference to the value covering `at`, if any.
+    pub fn get(&self, at: &K) -> Option<&'a V>
+    where
+        K: Ord,
+    {
+        self.get_range_value(at).map(|(_, value)| value)
+    }
+
+    /// Returns the range-value pair covering `at`, if any.
+    pub fn get_range_value(&self, at: &K) -> Option<(&'a Segment<K>, &'a V)>
+    where
+        K: Ord,
+    {
+        let start = Start(Included(at));
+        let k = self
+            .ranges
+            .partition_point(|range| range.start.as_ref() <= start)
+            .checked_sub(1)?;
+        let range = &self.ranges[k];
+        if range.contains(at) {
+            Some((range, &self.values[k]))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if any range in the map covers `point`.
+    pub fn contains(&self, point: &K) -> bool
+    where
+        K: Ord,
+    {
+        self.get_range_value(point).is_some()
+    }
+
+    /// Gets an iterator over the sorted ranges in the map.
+    pub fn iter(&self) -> SliceIter<'a, K, V> {
+        SliceIter(self.ranges.iter().zip(self.values.iter()))
+    }
+
+    /// Gets an iterator over the sorted ranges in the map that overlap
+    /// `range`. This is the same as [`SegmentMap::iter_in`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// use segmap::frozen::FrozenSegmentSlice;
+    ///
+    /// let ranges = [Segment::from(0..10), Segment::from(10..20), Segment::from(30..40)];
+    /// let map = FrozenSegmentSlice::from_sorted_slices(&ranges, &["a", "b", "c"]);
+    ///
+    /// assert!(map.iter_in(5..25).eq(vec![
+    ///     (&Segment::from(0..10), &"a"),
+    ///     (&Segment::from(10..20), &"b"),
+    /// ]));
+    /// assert_eq!(map.iter_in(22..28).next(), None);
+    /// ```
+    pub fn iter_in<R>(&self, range: R) -> SliceIter<'a, K, V>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+    {
+        let range = Segment::from(&range);
+
+        // Ranges entirely before `range`, then those starting before it ends
+        let front = self
+            .ranges
+            .partition_point(|r| r.start < range.start && !r.overlaps(&range));
+        let back = self
+            .ranges
+            .partition_point(|r| !range.end.cmp_start(&r.start).is_lt())
+            .max(front);
+        SliceIter(
+            self.ranges[front..back]
+                .iter()
+                .zip(self.values[front..back].iter()),
+        )
+    }
+
+    /// Copy the ranges and values into a new [`SegmentMap`], coalescing any
+    /// touching ranges with the same value.
+    pub fn to_segment_map(&self) -> SegmentMap<K, V>
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let mut map = SegmentMap::new();
+        for (range, value) in self.iter() {
+            map.set(range.clone(), value.clone());
+        }
+        map
+    }
+}
+
+// Manual impls, so `K` and `V` don't need to be `Clone`
+impl<K, V> Clone for FrozenSegmentSlice<'_, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<K, V> Copy for FrozenSegmentSlice<'_, K, V> {}
+
+impl<K: Debug, V: Debug> Debug for FrozenSegmentSlice<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V> IntoIterator for FrozenSegmentSlice<'a, K, V> {
+    type Item = (&'a Segment<K>, &'a V);
+    type IntoIter = SliceIter<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &FrozenSegmentSlice<'a, K, V> {
+    type Item = (&'a Segment<K>, &'a V);
+    type IntoIter = SliceIter<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K: Ord, V> From<FrozenSegmentSlice<'_, K, V>> for SegmentMap<K, V>
+where
+    K: Clone,
+    V: Clone + Eq,
+{
+    fn from(slice: FrozenSegmentSlice<'_, K, V>) -> Self {
+        slice.to_segment_map()
+    }
+}
+
+/// An iterator over the ranges of a `FrozenSegmentSlice`.
+///
+/// This `struct` is created by the [`iter`] and [`iter_in`] methods on
+/// [`FrozenSegmentSlice`]. See their documentation for more.
+///
+/// [`iter`]: FrozenSegmentSlice::iter
+/// [`iter_in`]: FrozenSegmentSlice::iter_in
+pub struct SliceIter<'a, K, V>(Zip<slice::Iter<'a, Segment<K>>, slice::Iter<'a, V>>);
+
+impl<'a, K, V> Iterator for SliceIter<'a, K, V> {
+    type Item = (&'a Segment<K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+impl<K, V> DoubleEndedIterator for SliceIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+impl<K, V> ExactSizeIterator for SliceIter<'_, K, V> {}
+impl<K, V> FusedIterator for SliceIter<'_, K, V> {}
+
+impl<K, V> Clone for SliceIter<'_, K, V> {
+    fn clone(&self) -> Self {
+        SliceIter(self.0.clone())
+    }
+}
+impl<K: Debug, V: Debug> Debug for SliceIter<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
@@ -0,0 +1,91 @@
+use super::*;
+use alloc::format;
+
+/// A map of `len` ranges, with some touching and some apart
+fn sample(len: u32) -> SegmentMap<u32, u32> {
+    let mut map = SegmentMap::new();
+    for i in 0..len {
+        let start = i * 4 + (i % 3);
+        map.set(start..start + 3, i);
+    }
+    map
+}
+
+#[test]
+fn frozen_matches_map() {
+    // Enough sizes to cover full, partial and lopsided last levels
+    for len in 0..40 {
+        let map = sample(len);
+        let frozen = map.clone().freeze();
+        assert_eq!(frozen.len(), map.len());
+
+        assert!(frozen.iter().eq(map.iter()));
+        assert!(frozen.iter().rev().eq(map.iter().rev()));
+        assert_eq!(frozen.iter().len(), map.len());
+        assert!(frozen.iter_gaps().eq(map.iter_gaps()));
+        assert!(frozen.iter_gaps().rev().eq(map.iter_gaps().rev()));
+        assert_eq!(frozen.gaps(), map.gaps());
+
+        let end = len * 4 + 4;
+        for at in 0..end {
+            assert_eq!(frozen.get_range_value(&at), map.get_range_value(&at));
+        }
+        for start in 0..end {
+            for stop in start..end {
+                assert!(frozen.iter_in(start..stop).eq(map.iter_in(start..stop)));
+                assert!(frozen
+                    .iter_in(start..=stop)
+                    .rev()
+                    .eq(map.iter_in(start..=stop).rev()));
+            }
+            assert!(frozen.iter_in(start..).eq(map.iter_in(start..)));
+        }
+
+        assert_eq!(frozen.thaw(), map);
+    }
+}
+
+#[test]
+fn frozen_can_be_shared() {
+    fn assert_sync<T: Send + Sync>(_: &T) {}
+    let frozen = sample(10).freeze();
+    assert_sync(&frozen);
+    assert_eq!(format!("{:?}", frozen), format!("{:?}", sample(10)));
+}
+
+#[test]
+fn slice_matches_map() {
+    for len in 0..20 {
+        let map = sample(len);
+        let (ranges, values): (Vec<_>, Vec<_>) = map.iter().map(|(r, v)| (*r, *v)).unzip();
+        let slice = FrozenSegmentSlice::from_sorted_slices(&ranges, &values);
+        assert_eq!(slice.len(), map.len());
+        assert!(slice.iter().eq(map.iter()));
+        assert!(slice.iter().rev().eq(map.iter().rev()));
+        assert_eq!(SegmentMap::from(slice), map);
+
+        let end = len * 4 + 4;
+        for at in 0..end {
+            assert_eq!(slice.get_range_value(&at), map.get_range_value(&at));
+        }
+        for start in 0..end {
+            for stop in start..end {
+                assert!(slice.iter_in(start..stop).eq(map.iter_in(start..stop)));
+                assert!(slice.iter_in(start..=stop).eq(map.iter_in(start..=stop)));
+            }
+        }
+    }
+}
+
+#[test]
+fn slice_in_a_static() {
+    const fn range(start: u32, end: u32) -> Segment<u32> {
+        Segment::from_bounds(Included(start), Excluded(end))
+    }
+    static TABLE: FrozenSegmentSlice<'static, u32, char> =
+        FrozenSegmentSlice::from_sorted_slices(&[range(0, 10), range(20, 30)], &['a', 'b']);
+
+    assert_eq!(TABLE.get(&25), Some(&'b'));
+    assert_eq!(TABLE.get(&15), None);
+    assert_eq!(format!("{:?}", TABLE), "{[0, 10): 'a', [20, 30): 'b'}");
+}
@@ -0,0 +1,1246 @@
+//! Compact maps and sets of half-open ranges
+//!
+//! Each key in a [`SegmentMap`] is a [`Segment`], with a [`Bound`] at each
+//! end, so every stored range carries two enum tags (and their padding) on
+//! top of its two values. [`HalfOpenSegmentMap`] and [`HalfOpenSegmentSet`]
+//! only hold ranges like `start..end`, so they store just the two values: each
+//! entry is the start, mapped to the end (and value).
+//!
+//! Ranges are still given out as [`Segment`]s, so results can be compared
+//! with (or converted to) the general types. Sizes of each stored entry, on a
+//! 64-bit target:
+//!
+//! | Key    | `SegmentMap<K, ()>` | `HalfOpenSegmentMap<K, ()>` |
+//! |--------|--------------------:|----------------------------:|
+//! | `u32`  |            16 bytes |                     8 bytes |
+//! | `u64`  |            32 bytes |                    16 bytes |
+//! | `u128` |            64 bytes |                    32 bytes |
+//!
+//! The `half_open` bench prints these for a few more types, and compares the
+//! speed of both maps.
+//!
+//! [`Bound`]: core::ops::Bound
+
+use alloc::collections::{btree_map, BTreeMap};
+use core::{
+    convert::TryFrom,
+    fmt::{self, Debug},
+    iter::{FromIterator, FusedIterator},
+    ops::{Bound::*, Range},
+};
+
+use crate::{
+    map::collect_coalesced,
+    segment::{End, Start},
+    Segment, SegmentIterExt, SegmentMap, SegmentSet,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Error returned when converting a [`SegmentMap`] or [`SegmentSet`] that
+/// holds ranges that aren't half-open (like `start..end`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NotHalfOpen;
+
+impl fmt::Display for NotHalfOpen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ranges are not all half-open")
+    }
+}
+
+/// # HalfOpenSegmentMap
+///
+/// A map of non-overlapping half-open ranges to values, stored more compactly
+/// than a [`SegmentMap`]. Like [`SegmentMap`], adjacent ranges with the same
+/// value are merged.
+///
+/// Only ranges like `start..end` can be stored, so methods take a
+/// [`Range`] rather than any [`RangeBounds`]. Empty (or backwards) ranges hold
+/// nothing, so setting or clearing one does nothing.
+///
+/// Since the stored ranges aren't [`Segment`]s, ranges are handed out as
+/// owned [`Segment`]s built from the stored bounds. That means lookups like
+/// [`HalfOpenSegmentMap::get_range_value`] and the iterators need `K: Clone`,
+/// and return a `Segment<K>` where [`SegmentMap`] returns a `&Segment<K>`.
+///
+/// [`RangeBounds`]: core::ops::RangeBounds
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// let mut map = HalfOpenSegmentMap::new();
+/// map.set(0..5, "a");
+/// map.set(5..10, "a");
+/// map.set(3..7, "b");
+///
+/// assert_eq!(map.get(&2), Some(&"a"));
+/// assert_eq!(map.get_range_value(&4), Some((Segment::from(3..7), &"b")));
+/// assert!(map.into_iter().eq(vec![
+///     (Segment::from(0..3), "a"),
+///     (Segment::from(3..7), "b"),
+///     (Segment::from(7..10), "a"),
+/// ]));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct HalfOpenSegmentMap<K, V> {
+    // Starts of ranges, mapped to their ends and values
+    map: BTreeMap<K, (K, V)>,
+}
+
+impl<K, V> HalfOpenSegmentMap<K, V> {
+    /// Makes a new, empty `HalfOpenSegmentMap`.
+    pub fn new() -> Self
+    where
+        K: Ord,
+    {
+        HalfOpenSegmentMap {
+            map: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of ranges in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no ranges.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Clears the map, removing all elements.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Returns a reference to the value covering `at`, if any.
+    pub fn get(&self, at: &K) -> Option<&V>
+    where
+        K: Ord,
+    {
+        self.get_entry(at).map(|(_, _, value)| value)
+    }
+
+    /// Returns the range-value pair covering `at`, if any.
+    pub fn get_range_value(&self, at: &K) -> Option<(Segment<K>, &V)>
+    where
+        K: Clone + Ord,
+    {
+        self.get_entry(at)
+            .map(|(start, end, value)| (segment(start, end), value))
+    }
+
+    /// Returns `true` if any range in the map covers `point`.
+    pub fn contains(&self, point: &K) -> bool
+    where
+        K: Ord,
+    {
+        self.get_entry(point).is_some()
+    }
+
+    fn get_entry(&self, at: &K) -> Option<(&K, &K, &V)>
+    where
+        K: Ord,
+    {
+        self.map
+            .range(..=at)
+            .next_back()
+            .filter(|(_, (end, _))| end > at)
+            .map(|(start, (end, value))| (start, end, value))
+    }
+
+    /// Gets an iterator over the ranges in the map (as [`Segment`]s), with
+    /// their values, sorted by range.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.map.iter())
+    }
+
+    /// Gets an iterator over the ranges in the map that overlap `range`
+    /// (without truncating them). This is the same as
+    /// [`SegmentMap::iter_in`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = HalfOpenSegmentMap::new();
+    /// map.set(0..5, "a");
+    /// map.set(5..10, "b");
+    /// map.set(20..30, "c");
+    ///
+    /// assert!(map.iter_in(3..6).eq(vec![
+    ///     (Segment::from(0..5), &"a"),
+    ///     (Segment::from(5..10), &"b"),
+    /// ]));
+    /// assert!(map.iter_in(10..20).next().is_none());
+    /// ```
+    pub fn iter_in(&self, range: Range<K>) -> IterIn<'_, K, V>
+    where
+        K: Ord,
+    {
+        let Range { start, end } = range;
+        if start >= end {
+            return IterIn(self.map.range((Included(&start), Excluded(&start))));
+        }
+
+        // Start from the range overlapping the start of `range`, if any
+        let first = self
+            .map
+            .range(..&start)
+            .next_back()
+            .filter(|(_, (before_end, _))| *before_end > start)
+            .map(|(before, _)| before);
+        IterIn(
+            self.map
+                .range((Included(first.unwrap_or(&start)), Excluded(&end))),
+        )
+    }
+
+    /// Gets an iterator over all maximally-sized gaps between ranges in the
+    /// map. Like [`SegmentMap::iter_gaps`], this doesn't include the regions
+    /// before the first range or after the last one, or anything between
+    /// touching ranges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = HalfOpenSegmentMap::new();
+    /// map.set(0..5, "a");
+    /// map.set(5..10, "b");
+    /// map.set(20..30, "c");
+    ///
+    /// assert!(map.iter_gaps().eq(vec![Segment::from(10..20)]));
+    /// ```
+    pub fn iter_gaps(&self) -> Gaps<'_, K, V> {
+        let mut iter = self.map.iter();
+        Gaps {
+            front: iter.next(),
+            back: iter.next_back(),
+            iter,
+        }
+    }
+
+    /// Get all gaps between ranges in the map, as a set. Gaps between
+    /// half-open ranges are half-open too, so this is a
+    /// [`HalfOpenSegmentSet`] (of owned values, unlike [`SegmentMap::gaps`]).
+    pub fn gaps(&self) -> HalfOpenSegmentSet<K>
+    where
+        K: Clone + Ord,
+    {
+        let mut gaps = self.iter_gaps();
+        let mut map = BTreeMap::new();
+        while let Some((start, end)) = gaps.next_pair() {
+            map.insert(start.clone(), (end.clone(), ()));
+        }
+        HalfOpenSegmentSet {
+            map: HalfOpenSegmentMap { map },
+        }
+    }
+
+    /// Insert a value for the specified range, overwriting any overlapping
+    /// ranges, and returning the parts of them that were overwritten with a
+    /// different value (or `None` if there weren't any). This is the same as
+    /// [`SegmentMap::insert`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = HalfOpenSegmentMap::new();
+    /// assert!(map.insert(0..10, "a").is_none());
+    ///
+    /// let overwritten = map.insert(5..15, "b").unwrap();
+    /// assert!(overwritten.into_iter().eq(vec![(Segment::from(5..10), "a")]));
+    /// ```
+    pub fn insert(&mut self, range: Range<K>, value: V) -> Option<Self>
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        // Parts with the same value are merged rather than overwritten
+        let removed = self.remove(range.clone()).and_then(|mut removed| {
+            removed.map.retain(|_, (_, removed)| *removed != value);
+            Some(removed).filter(|removed| !removed.is_empty())
+        });
+        self.set(range, value);
+        removed
+    }
+
+    /// Set a value for the specified range, overwriting any overlapping
+    /// ranges. This is the same as [`SegmentMap::set`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = HalfOpenSegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(4..6, "b");
+    /// map.set(4..6, "a");
+    /// assert!(map.iter().eq(vec![(Segment::from(0..10), &"a")]));
+    /// ```
+    pub fn set(&mut self, range: Range<K>, value: V)
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        if range.start >= range.end {
+            return;
+        }
+        self.clear_internal(&range, None);
+        let Range { mut start, mut end } = range;
+
+        // Merge with touching ranges that have the same value
+        let before = self
+            .map
+            .range(..&start)
+            .next_back()
+            .filter(|(_, (before_end, before_value))| {
+                *before_end == start && *before_value == value
+            })
+            .map(|(before, _)| before.clone());
+        if let Some(before) = before {
+            self.map.remove(&before);
+            start = before;
+        }
+        if self.map.get(&end).is_some_and(|(_, after)| *after == value) {
+            let (after_end, _) = self.map.remove(&end).unwrap();
+            end = after_end;
+        }
+
+        self.map.insert(start, (end, value));
+    }
+
+    /// Remove all values in a given range. Overlapping ranges will be
+    /// truncated at the bounds of this range. This is the same as
+    /// [`SegmentMap::clear_range`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = HalfOpenSegmentMap::new();
+    /// map.set(0..10, 5);
+    /// map.clear_range(2..4);
+    ///
+    /// assert_eq!(map.get(&1), Some(&5));
+    /// assert_eq!(map.get(&3), None);
+    /// assert_eq!(map.get(&4), Some(&5));
+    /// ```
+    pub fn clear_range(&mut self, range: Range<K>)
+    where
+        K: Clone + Ord,
+        V: Clone,
+    {
+        self.clear_internal(&range, None);
+    }
+
+    /// Remove all values in a given range, returning the removed parts of
+    /// ranges (or `None` if nothing was removed). This is the same as
+    /// [`SegmentMap::remove`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = HalfOpenSegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(10..20, "b");
+    ///
+    /// let removed = map.remove(5..15).unwrap();
+    /// assert!(removed.into_iter().eq(vec![
+    ///     (Segment::from(5..10), "a"),
+    ///     (Segment::from(10..15), "b"),
+    /// ]));
+    /// assert!(map.remove(5..15).is_none());
+    /// ```
+    pub fn remove(&mut self, range: Range<K>) -> Option<Self>
+    where
+        K: Clone + Ord,
+        V: Clone,
+    {
+        let mut removed = BTreeMap::new();
+        self.clear_internal(&range, Some(&mut removed));
+        if removed.is_empty() {
+            None
+        } else {
+            Some(HalfOpenSegmentMap { map: removed })
+        }
+    }
+
+    /// Split the map in two at `at`, returning everything after it. A range
+    /// covering `at` is split between the two maps.
+    ///
+    /// This is the same as [`SegmentMap::split_off`] at `Bound::Included(at)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = HalfOpenSegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(20..30, "b");
+    ///
+    /// let after = map.split_off(5);
+    /// assert!(map.into_iter().eq(vec![(Segment::from(0..5), "a")]));
+    /// assert!(after.into_iter().eq(vec![
+    ///     (Segment::from(5..10), "a"),
+    ///     (Segment::from(20..30), "b"),
+    /// ]));
+    /// ```
+    pub fn split_off(&mut self, at: K) -> Self
+    where
+        K: Clone + Ord,
+        V: Clone,
+    {
+        let mut after = self.map.split_off(&at);
+        if let Some((end, value)) = self.map.values_mut().next_back() {
+            if *end > at {
+                let end = core::mem::replace(end, at.clone());
+                after.insert(at, (end, value.clone()));
+            }
+        }
+        HalfOpenSegmentMap { map: after }
+    }
+
+    /// Moves all ranges from `other` into `self`, leaving `other` empty.
+    /// Ranges from `other` overwrite any they overlap in `self`, like
+    /// [`SegmentMap::append`].
+    pub fn append(&mut self, other: &mut Self)
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        for (start, (end, value)) in core::mem::take(&mut other.map) {
+            self.set(start..end, value);
+        }
+    }
+
+    /// Clear `range`, returning whether anything was removed, and adding the
+    /// removed parts to `removed` (if given)
+    fn clear_internal(
+        &mut self,
+        range: &Range<K>,
+        mut removed: Option<&mut BTreeMap<K, (K, V)>>,
+    ) -> bool
+    where
+        K: Clone + Ord,
+        V: Clone,
+    {
+        if range.start >= range.end {
+            return false;
+        }
+        let mut any = false;
+
+        // The range before `range` starts may overlap it, or cover it entirely
+        if let Some((_, (end, value))) = self.map.range_mut(..&range.start).next_back() {
+            if *end > range.start {
+                any = true;
+                if *end > range.end {
+                    if let Some(removed) = removed {
+                        removed.insert(range.start.clone(), (range.end.clone(), value.clone()));
+                    }
+                    let after = core::mem::replace(end, range.start.clone());
+                    let value = value.clone();
+                    self.map.insert(range.end.clone(), (after, value));
+                    return true;
+                }
+                let end = core::mem::replace(end, range.start.clone());
+                if let Some(removed) = removed.as_deref_mut() {
+                    removed.insert(range.start.clone(), (end, value.clone()));
+                }
+            }
+        }
+
+        // Ranges starting inside `range` are removed, keeping any part after
+        // it (only the last one can reach past it)
+        while let Some(start) = self
+            .map
+            .range(&range.start..&range.end)
+            .next()
+            .map(|(start, _)| start.clone())
+        {
+            any = true;
+            let (end, value) = self.map.remove(&start).unwrap();
+            if end > range.end {
+                if let Some(removed) = removed {
+                    removed.insert(start, (range.end.clone(), value.clone()));
+                }
+                self.map.insert(range.end.clone(), (end, value));
+                break;
+            }
+            if let Some(removed) = removed.as_deref_mut() {
+                removed.insert(start, (end, value));
+            }
+        }
+        any
+    }
+}
+
+impl<K: Ord, V> Default for HalfOpenSegmentMap<K, V> {
+    fn default() -> Self {
+        HalfOpenSegmentMap::new()
+    }
+}
+
+impl<K, V> Debug for HalfOpenSegmentMap<K, V>
+where
+    K: Clone + Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> FromIterator<(Range<K>, V)> for HalfOpenSegmentMap<K, V>
+where
+    K: Clone + Ord,
+    V: Clone + Eq,
+{
+    fn from_iter<T: IntoIterator<Item = (Range<K>, V)>>(iter: T) -> Self {
+        let mut map = HalfOpenSegmentMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V> Extend<(Range<K>, V)> for HalfOpenSegmentMap<K, V>
+where
+    K: Clone + Ord,
+    V: Clone + Eq,
+{
+    fn extend<T: IntoIterator<Item = (Range<K>, V)>>(&mut self, iter: T) {
+        for (range, value) in iter {
+            self.set(range, value);
+        }
+    }
+}
+
+impl<K, V> From<HalfOpenSegmentMap<K, V>> for SegmentMap<K, V>
+where
+    K: Ord,
+    V: Eq,
+{
+    fn from(map: HalfOpenSegmentMap<K, V>) -> Self {
+        collect_coalesced(map.into_iter())
+    }
+}
+
+impl<K, V> TryFrom<SegmentMap<K, V>> for HalfOpenSegmentMap<K, V>
+where
+    K: Ord,
+{
+    type Error = NotHalfOpen;
+    fn try_from(map: SegmentMap<K, V>) -> Result<Self, NotHalfOpen> {
+        let map = map
+            .into_iter()
+            .map(|(range, value)| match (range.start.0, range.end.0) {
+                (Included(start), Excluded(end)) => Ok((start, (end, value))),
+                _ => Err(NotHalfOpen),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(HalfOpenSegmentMap { map })
+    }
+}
+
+impl<'a, K: Clone, V> IntoIterator for &'a HalfOpenSegmentMap<K, V> {
+    type Item = (Segment<K>, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> IntoIterator for HalfOpenSegmentMap<K, V> {
+    type Item = (Segment<K>, V);
+    type IntoIter = IntoIter<K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self.map.into_iter())
+    }
+}
+
+/// # HalfOpenSegmentSet
+///
+/// A set of half-open ranges, stored more compactly than a [`SegmentSet`].
+/// The internal representation of this `struct` is a
+/// `HalfOpenSegmentMap<T, ()>`.
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// let mut set = HalfOpenSegmentSet::new();
+/// set.insert(0..5);
+/// set.insert(5..10);
+/// set.insert(20..25);
+/// set.remove(3..6);
+///
+/// assert!(set.contains(&7));
+/// assert!(!set.contains(&12));
+/// assert!(set.into_iter().eq(vec![
+///     Segment::from(0..3),
+///     Segment::from(6..10),
+///     Segment::from(20..25),
+/// ]));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct HalfOpenSegmentSet<T> {
+    map: HalfOpenSegmentMap<T, ()>,
+}
+
+impl<T> HalfOpenSegmentSet<T> {
+    /// Makes a new, empty `HalfOpenSegmentSet`.
+    pub fn new() -> Self
+    where
+        T: Ord,
+    {
+        HalfOpenSegmentSet {
+            map: HalfOpenSegmentMap::new(),
+        }
+    }
+
+    /// Returns the number of ranges in the set.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set contains no ranges.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Clears the set, removing all elements.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Returns `true` if any range in the set covers the specified value.
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: Ord,
+    {
+        self.map.contains(value)
+    }
+
+    /// Returns the range covering `value`, if any.
+    pub fn get_range_for(&self, value: &T) -> Option<Segment<T>>
+    where
+        T: Clone + Ord,
+    {
+        self.map.get_range_value(value).map(|(range, _)| range)
+    }
+
+    /// Gets an iterator over the ranges in the set (as [`Segment`]s), in
+    /// order.
+    pub fn iter(&self) -> SetIter<'_, T> {
+        SetIter(self.map.iter())
+    }
+
+    /// Adds a range to the set, returning `true` if any of it wasn't already
+    /// present. This is the same as [`SegmentSet::insert`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = HalfOpenSegmentSet::new();
+    /// assert!(set.insert(0..5));
+    /// assert!(!set.insert(1..3));
+    /// assert!(set.insert(3..7));
+    /// assert!(set.into_iter().eq(vec![Segment::from(0..7)]));
+    /// ```
+    pub fn insert(&mut self, range: Range<T>) -> bool
+    where
+        T: Clone + Ord,
+    {
+        if range.start >= range.end {
+            return false;
+        }
+
+        // Ranges are coalesced, so anything already covered must be inside
+        // the last range starting at or before this one
+        let covered = self
+            .map
+            .map
+            .range(..=&range.start)
+            .next_back()
+            .is_some_and(|(_, (end, _))| *end >= range.end);
+        if !covered {
+            self.map.set(range, ());
+        }
+        !covered
+    }
+
+    /// Removes a range from the set, returning `true` if any of it was
+    /// present.
+    pub fn remove(&mut self, range: Range<T>) -> bool
+    where
+        T: Clone + Ord,
+    {
+        self.map.clear_internal(&range, None)
+    }
+
+    /// Gets an iterator over the ranges in the set that overlap `range`
+    /// (without truncating them). This is the same as
+    /// [`SegmentSet::iter_in`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let set: HalfOpenSegmentSet<_> = vec![0..5, 8..10, 20..30].into_iter().collect();
+    ///
+    /// assert!(set
+    ///     .iter_in(3..9)
+    ///     .eq(vec![Segment::from(0..5), Segment::from(8..10)]));
+    /// assert!(set.iter_in(10..20).next().is_none());
+    /// ```
+    pub fn iter_in(&self, range: Range<T>) -> SetIterIn<'_, T>
+    where
+        T: Ord,
+    {
+        SetIterIn(self.map.iter_in(range))
+    }
+
+    /// Gets an iterator over all maximally-sized gaps between ranges in the
+    /// set. Like [`SegmentSet::iter_gaps`], this doesn't include the regions
+    /// before the first range or after the last one.
+    pub fn iter_gaps(&self) -> Gaps<'_, T, ()> {
+        self.map.iter_gaps()
+    }
+
+    /// Get all gaps between ranges in the set, as another
+    /// `HalfOpenSegmentSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let set: HalfOpenSegmentSet<_> = vec![0..5, 8..10, 20..30].into_iter().collect();
+    ///
+    /// assert!(set
+    ///     .gaps()
+    ///     .into_iter()
+    ///     .eq(vec![Segment::from(5..8), Segment::from(10..20)]));
+    /// ```
+    pub fn gaps(&self) -> Self
+    where
+        T: Clone + Ord,
+    {
+        self.map.gaps()
+    }
+
+    /// Splits the set in two at `at`. Returns everything at or after `at`,
+    /// splitting a range that covers it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set: HalfOpenSegmentSet<_> = vec![0..10, 20..30].into_iter().collect();
+    ///
+    /// let after = set.split_off(5);
+    /// assert!(set.into_iter().eq(vec![Segment::from(0..5)]));
+    /// assert!(after
+    ///     .into_iter()
+    ///     .eq(vec![Segment::from(5..10), Segment::from(20..30)]));
+    /// ```
+    pub fn split_off(&mut self, at: T) -> Self
+    where
+        T: Clone + Ord,
+    {
+        HalfOpenSegmentSet {
+            map: self.map.split_off(at),
+        }
+    }
+
+    /// Moves all ranges from `other` into `self`, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self)
+    where
+        T: Clone + Ord,
+    {
+        self.map.append(&mut other.map)
+    }
+
+    /// Returns a set of everything in either `self` or `other`.
+    ///
+    /// Like the other set operations, this sweeps over both sets with
+    /// [`SegmentIterExt`]. Use that directly on [`iter`] for a lazy version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let a: HalfOpenSegmentSet<_> = vec![0..5, 10..15].into_iter().collect();
+    /// let b: HalfOpenSegmentSet<_> = vec![3..8, 15..20].into_iter().collect();
+    ///
+    /// assert!(a
+    ///     .union(&b)
+    ///     .into_iter()
+    ///     .eq(vec![Segment::from(0..8), Segment::from(10..20)]));
+    /// ```
+    ///
+    /// [`SegmentIterExt`]: crate::SegmentIterExt
+    /// [`iter`]: HalfOpenSegmentSet::iter
+    pub fn union(&self, other: &Self) -> Self
+    where
+        T: Clone + Ord,
+    {
+        Self::from_segments(self.iter().union(other.iter()))
+    }
+
+    /// Returns a set of everything in both `self` and `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let a: HalfOpenSegmentSet<_> = vec![0..5, 10..15].into_iter().collect();
+    /// let b: HalfOpenSegmentSet<_> = vec![3..12].into_iter().collect();
+    ///
+    /// assert!(a
+    ///     .intersection(&b)
+    ///     .into_iter()
+    ///     .eq(vec![Segment::from(3..5), Segment::from(10..12)]));
+    /// ```
+    pub fn intersection(&self, other: &Self) -> Self
+    where
+        T: Clone + Ord,
+    {
+        Self::from_segments(self.iter().intersection(other.iter()))
+    }
+
+    /// Returns a set of everything in `self` but not in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let a: HalfOpenSegmentSet<_> = vec![0..5, 10..15].into_iter().collect();
+    /// let b: HalfOpenSegmentSet<_> = vec![3..12].into_iter().collect();
+    ///
+    /// assert!(a
+    ///     .difference(&b)
+    ///     .into_iter()
+    ///     .eq(vec![Segment::from(0..3), Segment::from(12..15)]));
+    /// ```
+    pub fn difference(&self, other: &Self) -> Self
+    where
+        T: Clone + Ord,
+    {
+        Self::from_segments(self.iter().difference(other.iter()))
+    }
+
+    /// Returns a set of everything in exactly one of `self` and `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let a: HalfOpenSegmentSet<_> = vec![0..5].into_iter().collect();
+    /// let b: HalfOpenSegmentSet<_> = vec![3..8].into_iter().collect();
+    ///
+    /// assert!(a
+    ///     .symmetric_difference(&b)
+    ///     .into_iter()
+    ///     .eq(vec![Segment::from(0..3), Segment::from(5..8)]));
+    /// ```
+    pub fn symmetric_difference(&self, other: &Self) -> Self
+    where
+        T: Clone + Ord,
+    {
+        Self::from_segments(self.iter().symmetric_difference(other.iter()))
+    }
+
+    /// Collect the sorted, coalesced output of a sweep. Cutting half-open
+    /// ranges at each other's bounds only ever gives more half-open ranges.
+    fn from_segments<I>(segments: I) -> Self
+    where
+        I: Iterator<Item = Segment<T>>,
+        T: Ord,
+    {
+        let map = segments
+            .map(|range| match (range.start.0, range.end.0) {
+                (Included(start), Excluded(end)) => (start, (end, ())),
+                _ => unreachable!("sweep over half-open ranges gave a different kind of range"),
+            })
+            .collect();
+        HalfOpenSegmentSet {
+            map: HalfOpenSegmentMap { map },
+        }
+    }
+}
+
+impl<T: Ord> Default for HalfOpenSegmentSet<T> {
+    fn default() -> Self {
+        HalfOpenSegmentSet::new()
+    }
+}
+
+impl<T: Clone + Debug> Debug for HalfOpenSegmentSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone + Ord> FromIterator<Range<T>> for HalfOpenSegmentSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = HalfOpenSegmentSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Clone + Ord> Extend<Range<T>> for HalfOpenSegmentSet<T> {
+    fn extend<I: IntoIterator<Item = Range<T>>>(&mut self, iter: I) {
+        for range in iter {
+            self.insert(range);
+        }
+    }
+}
+
+impl<T: Ord> From<HalfOpenSegmentSet<T>> for SegmentSet<T> {
+    fn from(set: HalfOpenSegmentSet<T>) -> Self {
+        SegmentSet {
+            map: set.map.into(),
+        }
+    }
+}
+
+impl<T: Ord> TryFrom<SegmentSet<T>> for HalfOpenSegmentSet<T> {
+    type Error = NotHalfOpen;
+    fn try_from(set: SegmentSet<T>) -> Result<Self, NotHalfOpen> {
+        Ok(HalfOpenSegmentSet {
+            map: HalfOpenSegmentMap::try_from(set.map)?,
+        })
+    }
+}
+
+impl<'a, T: Clone> IntoIterator for &'a HalfOpenSegmentSet<T> {
+    type Item = Segment<T>;
+    type IntoIter = SetIter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for HalfOpenSegmentSet<T> {
+    type Item = Segment<T>;
+    type IntoIter = SetIntoIter<T>;
+    fn into_iter(self) -> Self::IntoIter {
+        SetIntoIter(self.map.into_iter())
+    }
+}
+
+fn segment<K: Clone>(start: &K, end: &K) -> Segment<K> {
+    Segment {
+        start: Start(Included(start.clone())),
+        end: End(Excluded(end.clone())),
+    }
+}
+
+fn entry<'a, K: Clone, V>((start, (end, value)): (&K, &'a (K, V))) -> (Segment<K>, &'a V) {
+    (segment(start, end), value)
+}
+
+fn into_entry<K, V>((start, (end, value)): (K, (K, V))) -> (Segment<K>, V) {
+    let range = Segment {
+        start: Start(Included(start)),
+        end: End(Excluded(end)),
+    };
+    (range, value)
+}
+
+/// An iterator over the ranges of a `HalfOpenSegmentMap`, with their values.
+///
+/// This `struct` is created by the [`iter`] method on [`HalfOpenSegmentMap`].
+/// See its documentation for more.
+///
+/// [`iter`]: HalfOpenSegmentMap::iter
+pub struct Iter<'a, K, V>(btree_map::Iter<'a, K, (K, V)>);
+
+impl<'a, K: Clone, V> Iterator for Iter<'a, K, V> {
+    type Item = (Segment<K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(entry)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+impl<K: Clone, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(entry)
+    }
+}
+impl<K: Clone, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K: Clone, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter(self.0.clone())
+    }
+}
+impl<K: Clone + Debug, V: Debug> Debug for Iter<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the ranges of a `HalfOpenSegmentMap` that overlap a
+/// range, with their values.
+///
+/// This `struct` is created by the [`iter_in`] method on
+/// [`HalfOpenSegmentMap`]. See its documentation for more.
+///
+/// [`iter_in`]: HalfOpenSegmentMap::iter_in
+pub struct IterIn<'a, K, V>(btree_map::Range<'a, K, (K, V)>);
+
+impl<'a, K: Clone, V> Iterator for IterIn<'a, K, V> {
+    type Item = (Segment<K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(entry)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+impl<K: Clone, V> DoubleEndedIterator for IterIn<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(entry)
+    }
+}
+impl<K: Clone, V> FusedIterator for IterIn<'_, K, V> {}
+
+impl<K, V> Clone for IterIn<'_, K, V> {
+    fn clone(&self) -> Self {
+        IterIn(self.0.clone())
+    }
+}
+impl<K: Clone + Debug, V: Debug> Debug for IterIn<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the gaps between ranges of a `HalfOpenSegmentMap` or
+/// `HalfOpenSegmentSet`.
+///
+/// This `struct` is created by the [`iter_gaps`] method on
+/// [`HalfOpenSegmentMap`] (or [`HalfOpenSegmentSet`]). See its documentation
+/// for more.
+///
+/// [`iter_gaps`]: HalfOpenSegmentMap::iter_gaps
+pub struct Gaps<'a, K, V> {
+    // The entries on either side of the gaps left to iterate. If there's only
+    // one entry left, it's always in `front`.
+    front: Option<(&'a K, &'a (K, V))>,
+    iter: btree_map::Iter<'a, K, (K, V)>,
+    back: Option<(&'a K, &'a (K, V))>,
+}
+
+impl<'a, K: PartialEq, V> Gaps<'a, K, V> {
+    /// The start and end of the next gap, skipping touching ranges
+    fn next_pair(&mut self) -> Option<(&'a K, &'a K)> {
+        loop {
+            let (_, (start, _)) = self.front?;
+            let after = match self.iter.next() {
+                Some(after) => after,
+                None => self.back.take()?,
+            };
+            self.front = Some(after);
+            if start != after.0 {
+                return Some((start, after.0));
+            }
+        }
+    }
+
+    /// The start and end of the last gap, skipping touching ranges
+    fn next_back_pair(&mut self) -> Option<(&'a K, &'a K)> {
+        loop {
+            let (end, _) = self.back?;
+            self.back = self.iter.next_back();
+            let (_, (start, _)) = self.back.or(self.front)?;
+            if start != end {
+                return Some((start, end));
+            }
+        }
+    }
+}
+
+impl<K: Clone + PartialEq, V> Iterator for Gaps<'_, K, V> {
+    type Item = Segment<K>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_pair().map(|(start, end)| segment(start, end))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // At most one gap between each pair of ranges
+        match (self.front, self.back) {
+            (Some(_), Some(_)) => (0, Some(self.iter.len() + 1)),
+            _ => (0, Some(0)),
+        }
+    }
+}
+impl<K: Clone + PartialEq, V> DoubleEndedIterator for Gaps<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.next_back_pair()
+            .map(|(start, end)| segment(start, end))
+    }
+}
+impl<K: Clone + PartialEq, V> FusedIterator for Gaps<'_, K, V> {}
+
+impl<K, V> Clone for Gaps<'_, K, V> {
+    fn clone(&self) -> Self {
+        Gaps {
+            front: self.front,
+            iter: self.iter.clone(),
+            back: self.back,
+        }
+    }
+}
+impl<K: Clone + Debug + PartialEq, V> Debug for Gaps<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An owning iterator over the ranges of a `HalfOpenSegmentMap`, with their
+/// values.
+///
+/// This `struct` is created by the [`into_iter`] method on
+/// [`HalfOpenSegmentMap`] (provided by the `IntoIterator` trait). See its
+/// documentation for more.
+///
+/// [`into_iter`]: IntoIterator::into_iter
+pub struct IntoIter<K, V>(btree_map::IntoIter<K, (K, V)>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (Segment<K>, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(into_entry)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(into_entry)
+    }
+}
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+impl<K: Debug, V: Debug> Debug for IntoIter<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// An iterator over the ranges of a `HalfOpenSegmentSet`.
+///
+/// This `struct` is created by the [`iter`] method on [`HalfOpenSegmentSet`].
+/// See its documentation for more.
+///
+/// [`iter`]: HalfOpenSegmentSet::iter
+pub struct SetIter<'a, T>(Iter<'a, T, ()>);
+
+impl<T: Clone> Iterator for SetIter<'_, T> {
+    type Item = Segment<T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(range, _)| range)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+impl<T: Clone> DoubleEndedIterator for SetIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(range, _)| range)
+    }
+}
+impl<T: Clone> ExactSizeIterator for SetIter<'_, T> {}
+impl<T: Clone> FusedIterator for SetIter<'_, T> {}
+
+impl<T> Clone for SetIter<'_, T> {
+    fn clone(&self) -> Self {
+        SetIter(self.0.clone())
+    }
+}
+impl<T: Clone + Debug> Debug for SetIter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the ranges of a `HalfOpenSegmentSet` that overlap a
+/// range.
+///
+/// This `struct` is created by the [`iter_in`] method on
+/// [`HalfOpenSegmentSet`]. See its documentation for more.
+///
+/// [`iter_in`]: HalfOpenSegmentSet::iter_in
+pub struct SetIterIn<'a, T>(IterIn<'a, T, ()>);
+
+impl<T: Clone> Iterator for SetIterIn<'_, T> {
+    type Item = Segment<T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(range, _)| range)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+impl<T: Clone> DoubleEndedIterator for SetIterIn<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(range, _)| range)
+    }
+}
+impl<T: Clone> FusedIterator for SetIterIn<'_, T> {}
+
+impl<T> Clone for SetIterIn<'_, T> {
+    fn clone(&self) -> Self {
+        SetIterIn(self.0.clone())
+    }
+}
+impl<T: Clone + Debug> Debug for SetIterIn<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An owning iterator over the ranges of a `HalfOpenSegmentSet`.
+///
+/// This `struct` is created by the [`into_iter`] method on
+/// [`HalfOpenSegmentSet`] (provided by the `IntoIterator` trait). See its
+/// documentation for more.
+///
+/// [`into_iter`]: IntoIterator::into_iter
+pub struct SetIntoIter<T>(IntoIter<T, ()>);
+
+impl<T> Iterator for SetIntoIter<T> {
+    type Item = Segment<T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(range, _)| range)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+impl<T> DoubleEndedIterator for SetIntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(range, _)| range)
+    }
+}
+impl<T> ExactSizeIterator for SetIntoIter<T> {}
+impl<T> FusedIterator for SetIntoIter<T> {}
+
+impl<T: Debug> Debug for SetIntoIter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
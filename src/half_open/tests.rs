@@ -0,0 +1,217 @@
+use super::*;
+use crate::map::Key;
+use alloc::{vec, vec::Vec};
+use core::mem::size_of;
+
+#[test]
+fn matches_segment_map() {
+    let mut map = HalfOpenSegmentMap::new();
+    let mut expected: SegmentMap<u32, u8> = SegmentMap::new();
+
+    // Every range in 0..8, set or cleared, in a scrambled order
+    let mut ops = Vec::new();
+    for start in 0..8 {
+        for end in start + 1..=8 {
+            ops.push((start, end, ((start * 3 + end) % 4) as u8));
+        }
+    }
+    for (i, &(start, end, value)) in ops.iter().enumerate() {
+        let (start, end) = ((start * 5 + i as u32) % 8, (end * 3 + i as u32) % 9);
+        match value {
+            3 => {
+                let removed = map.remove(start..end).map(SegmentMap::from);
+                if start < end {
+                    assert_eq!(removed, expected.remove(start..end));
+                } else {
+                    assert_eq!(removed, None);
+                }
+            }
+            2 => {
+                let overwritten = map.insert(start..end, value).map(SegmentMap::from);
+                if start < end {
+                    assert_eq!(overwritten, expected.insert(start..end, value));
+                } else {
+                    assert_eq!(overwritten, None);
+                }
+            }
+            1 => {
+                map.clear_range(start..end);
+                if start < end {
+                    expected.clear_range(start..end);
+                }
+            }
+            _ => {
+                map.set(start..end, value);
+                if start < end {
+                    expected.set(start..end, value);
+                }
+            }
+        }
+
+        assert!(map
+            .iter()
+            .map(|(r, v)| (r, *v))
+            .eq(expected.iter().map(|(r, v)| (*r, *v))));
+        for at in 0..10 {
+            assert_eq!(map.get(&at), expected.get(&at));
+            assert_eq!(
+                map.get_range_value(&at),
+                expected.get_range_value(&at).map(|(r, v)| (*r, v))
+            );
+        }
+        for (start, end) in [(0, 9), (2, 5), (3, 4), (5, 5), (6, 2)] {
+            let expected_in = if start < end {
+                expected.iter_in(start..end).map(|(r, v)| (*r, v)).collect()
+            } else {
+                Vec::new()
+            };
+            assert!(map.iter_in(start..end).eq(expected_in));
+        }
+        assert!(map
+            .iter_gaps()
+            .eq(expected.iter_gaps().map(|gap| gap.cloned())));
+        assert!(map
+            .iter_gaps()
+            .rev()
+            .eq(expected.iter_gaps().rev().map(|gap| gap.cloned())));
+        assert!(SegmentSet::from(map.gaps())
+            .iter()
+            .map(Segment::as_ref)
+            .eq(expected.iter_gaps()));
+        assert_eq!(SegmentMap::from(map.clone()), expected);
+        assert_eq!(
+            HalfOpenSegmentMap::try_from(expected.clone()),
+            Ok(map.clone())
+        );
+    }
+}
+
+#[test]
+fn split_off_and_append() {
+    let mut map = HalfOpenSegmentMap::new();
+    map.set(0..4, 'a');
+    map.set(4..8, 'b');
+    map.set(10..12, 'c');
+    let mut expected = SegmentMap::from(map.clone());
+
+    for at in 0..14 {
+        let mut left = map.clone();
+        let mut right = left.split_off(at);
+        let mut expected_left = expected.clone();
+        let expected_right = expected_left.split_off(Included(at));
+        assert_eq!(SegmentMap::from(left.clone()), expected_left);
+        assert_eq!(SegmentMap::from(right.clone()), expected_right);
+
+        left.append(&mut right);
+        assert!(right.is_empty());
+        assert_eq!(left, map);
+    }
+
+    // Appended ranges overwrite existing ones
+    let mut other = HalfOpenSegmentMap::new();
+    other.set(2..6, 'd');
+    map.append(&mut other);
+    expected.set(2..6, 'd');
+    assert_eq!(SegmentMap::from(map), expected);
+}
+
+#[test]
+fn half_open_set() {
+    let mut set = HalfOpenSegmentSet::new();
+    assert!(set.insert(0..5));
+    assert!(set.insert(10..15));
+    assert!(!set.insert(11..13));
+    assert!(!set.insert(4..4));
+    assert!(set.insert(5..8));
+    assert!(set.remove(2..12));
+    assert!(!set.remove(2..12));
+
+    assert_eq!(set.len(), 2);
+    assert_eq!(set.get_range_for(&13), Some(Segment::from(12..15)));
+    assert!(set
+        .iter()
+        .rev()
+        .eq(vec![Segment::from(12..15), Segment::from(0..2)]));
+
+    let general = SegmentSet::from(set.clone());
+    assert!(general
+        .iter()
+        .eq(&[Segment::from(0..2), Segment::from(12..15)]));
+    assert_eq!(HalfOpenSegmentSet::try_from(general), Ok(set));
+
+    let mut closed = SegmentSet::new();
+    closed.insert(0..=5);
+    assert_eq!(HalfOpenSegmentSet::try_from(closed), Err(NotHalfOpen));
+}
+
+#[test]
+fn set_operations_match_segment_set() {
+    // Two deterministic sets of overlapping, touching and disjoint ranges
+    let mut seed = 11u32;
+    let mut sets = [
+        (HalfOpenSegmentSet::new(), SegmentSet::new()),
+        (HalfOpenSegmentSet::new(), SegmentSet::new()),
+    ];
+    for i in 0..120 {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        let start = (seed >> 8) % 150;
+        let end = start + 1 + (seed >> 20) % 12;
+        let (half_open, general) = &mut sets[i % 2];
+        if seed % 4 == 0 {
+            half_open.remove(start..end);
+            general.remove(start..end);
+        } else {
+            half_open.insert(start..end);
+            general.insert(start..end);
+        }
+    }
+    let [(a, general_a), (b, general_b)] = sets;
+    let same = |half_open: HalfOpenSegmentSet<u32>, general: SegmentSet<&u32>| {
+        assert!(half_open
+            .into_iter()
+            .eq(general.into_iter().map(|range| range.cloned())));
+    };
+    same(a.union(&b), general_a.union(&general_b));
+    same(a.intersection(&b), general_a.intersection(&general_b));
+    same(a.difference(&b), general_a.difference(&general_b));
+    same(
+        a.symmetric_difference(&b),
+        general_a.symmetric_difference(&general_b),
+    );
+    same(a.gaps(), general_a.gaps());
+    assert!(a
+        .iter_gaps()
+        .rev()
+        .eq(general_a.iter_gaps().rev().map(|range| range.cloned())));
+
+    for start in 0..170 {
+        for end in (start + 1..170).step_by(5) {
+            assert!(a
+                .iter_in(start..end)
+                .eq(general_a.iter_in(start..end).cloned()));
+        }
+
+        let (mut before, mut general_before) = (a.clone(), general_a.clone());
+        let after = before.split_off(start);
+        let general_after = general_before.split_off(Included(start));
+        assert!(before.iter().eq(general_before.iter().cloned()));
+        assert!(after.iter().eq(general_after.iter().cloned()));
+
+        before.append(&mut after.clone());
+        assert_eq!(before, a);
+    }
+}
+
+#[test]
+fn entries_are_smaller() {
+    // Stored keys of a `SegmentMap`, against starts with (end, value) pairs
+    fn check<T>() {
+        assert_eq!(size_of::<T>() + size_of::<(T, ())>(), 2 * size_of::<T>());
+        assert!(size_of::<Key<T>>() > 2 * size_of::<T>());
+    }
+    check::<u8>();
+    check::<u32>();
+    check::<u64>();
+    check::<u128>();
+    check::<i64>();
+}
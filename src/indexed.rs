@@ -0,0 +1,273 @@
+//! A [`SegmentMap`] with a secondary index from values to ranges
+//!
+//! [`SegmentMap::ranges_for`] has to scan the whole map to find where a value
+//! is stored. [`IndexedSegmentMap`] instead keeps a [`SegmentSet`] of ranges
+//! for each distinct value, updated on every insertion and removal, so that
+//! these lookups are a single tree search.
+
+use alloc::collections::BTreeMap;
+use core::{
+    fmt::{self, Debug},
+    ops::RangeBounds,
+};
+
+use crate::{Segment, SegmentMap, SegmentSet};
+
+#[cfg(test)]
+mod tests;
+
+/// A [`SegmentMap`] that also indexes its ranges by value
+///
+/// Reads go through [`IndexedSegmentMap::as_map`] (or the shortcuts defined
+/// here), while all modification must go through this wrapper so the index
+/// stays in sync. Each update costs an extra index update per range that it
+/// overwrites.
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// let mut roster = IndexedSegmentMap::new();
+/// roster.set(0..7, "Alice");
+/// roster.set(7..14, "Bob");
+/// roster.set(14..21, "Alice");
+///
+/// // Bob covers part of Alice's second shift
+/// roster.set(14..16, "Bob");
+///
+/// assert!(roster.ranges_for(&"Alice").unwrap().iter().eq(vec![
+///     &Segment::from(0..7),
+///     &Segment::from(16..21),
+/// ]));
+/// assert!(roster.ranges_for(&"Bob").unwrap().iter().eq(vec![
+///     &Segment::from(7..16),
+/// ]));
+/// ```
+#[derive(Clone)]
+pub struct IndexedSegmentMap<K, V> {
+    map: SegmentMap<K, V>,
+    index: BTreeMap<V, SegmentSet<K>>,
+}
+
+impl<K, V> IndexedSegmentMap<K, V> {
+    /// Makes a new, empty `IndexedSegmentMap`.
+    pub fn new() -> Self
+    where
+        K: Ord,
+    {
+        IndexedSegmentMap {
+            map: SegmentMap::new(),
+            index: BTreeMap::new(),
+        }
+    }
+
+    /// Get the underlying map
+    pub fn as_map(&self) -> &SegmentMap<K, V> {
+        &self.map
+    }
+
+    /// Discard the index, returning the underlying map
+    pub fn into_map(self) -> SegmentMap<K, V> {
+        self.map
+    }
+
+    /// Get the full index, mapping each distinct value to the ranges that hold
+    /// it.
+    pub fn index(&self) -> &BTreeMap<V, SegmentSet<K>> {
+        &self.index
+    }
+
+    /// Get the ranges that hold `value`, or `None` if `value` isn't stored in
+    /// the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = IndexedSegmentMap::new();
+    /// map.set(0..5, true);
+    /// map.set(10..15, true);
+    ///
+    /// assert_eq!(map.ranges_for(&true).unwrap().len(), 2);
+    /// assert!(map.ranges_for(&false).is_none());
+    /// ```
+    pub fn ranges_for(&self, value: &V) -> Option<&SegmentSet<K>>
+    where
+        V: Ord,
+    {
+        self.index.get(value)
+    }
+
+    /// Returns a reference to the value covering `at`, if any.
+    pub fn get(&self, at: &K) -> Option<&V>
+    where
+        K: Clone + Ord,
+    {
+        self.map.get(at)
+    }
+
+    /// Returns the range-value pair covering `at`, if any.
+    pub fn get_range_value(&self, at: &K) -> Option<(&Segment<K>, &V)>
+    where
+        K: Clone + Ord,
+    {
+        self.map.get_range_value(at)
+    }
+
+    /// Returns `true` if any range in the map covers `point`.
+    pub fn contains(&self, point: &K) -> bool
+    where
+        K: Clone + Ord,
+    {
+        self.map.contains(point)
+    }
+
+    /// Returns the number of ranges in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map contains no ranges.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Clears the map and its index.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.index.clear();
+    }
+
+    /// Insert a value for the specified range, returning any values that were
+    /// overwritten, like [`SegmentMap::insert`].
+    pub fn insert<R>(&mut self, range: R, value: V) -> Option<SegmentMap<K, V>>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Ord,
+    {
+        let range = Segment::from(&range);
+        let removed = self.map.insert(range.clone(), value.clone());
+        if let Some(removed) = &removed {
+            self.unindex(removed);
+        }
+        self.index.entry(value).or_default().insert(range);
+        removed
+    }
+
+    /// Set a value for the specified range, like [`SegmentMap::set`].
+    pub fn set<R>(&mut self, range: R, value: V)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Ord,
+    {
+        self.insert(range, value);
+    }
+
+    /// Remove all values in a given range, returning the removed values, like
+    /// [`SegmentMap::remove`].
+    pub fn remove<R>(&mut self, range: R) -> Option<SegmentMap<K, V>>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Ord,
+    {
+        let removed = self.map.remove(range);
+        if let Some(removed) = &removed {
+            self.unindex(removed);
+        }
+        removed
+    }
+
+    /// Remove all values in a given range, like [`SegmentMap::clear_range`].
+    pub fn clear_range<R>(&mut self, range: R)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Ord,
+    {
+        self.remove(range);
+    }
+
+    /// Remove every range holding `value`, returning those ranges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut roster = IndexedSegmentMap::new();
+    /// roster.set(0..7, "Alice");
+    /// roster.set(7..14, "Bob");
+    /// roster.set(14..21, "Alice");
+    ///
+    /// assert_eq!(roster.remove_value(&"Alice").unwrap().len(), 2);
+    /// assert_eq!(roster.len(), 1);
+    /// assert!(roster.get(&3).is_none());
+    /// ```
+    pub fn remove_value(&mut self, value: &V) -> Option<SegmentSet<K>>
+    where
+        K: Clone + Ord,
+        V: Clone + Ord,
+    {
+        let ranges = self.index.remove(value)?;
+        for range in ranges.iter() {
+            self.map.clear_range(range);
+        }
+        Some(ranges)
+    }
+
+    /// Remove overwritten or removed pieces from the index
+    fn unindex(&mut self, removed: &SegmentMap<K, V>)
+    where
+        K: Clone + Ord,
+        V: Ord,
+    {
+        for (range, value) in removed.iter() {
+            if let Some(ranges) = self.index.get_mut(value) {
+                ranges.remove(range);
+                if ranges.is_empty() {
+                    self.index.remove(value);
+                }
+            }
+        }
+    }
+}
+
+impl<K: Ord, V> Default for IndexedSegmentMap<K, V> {
+    fn default() -> Self {
+        IndexedSegmentMap::new()
+    }
+}
+
+impl<K, V> From<SegmentMap<K, V>> for IndexedSegmentMap<K, V>
+where
+    K: Clone + Ord,
+    V: Clone + Ord,
+{
+    fn from(map: SegmentMap<K, V>) -> Self {
+        let index = map
+            .group_by_value()
+            .into_iter()
+            .map(|(value, ranges)| (value.clone(), ranges.cloned()))
+            .collect();
+        IndexedSegmentMap { map, index }
+    }
+}
+
+// Like `SegmentMap`, only show the stored ranges (the index is derived from
+// them anyway)
+impl<K: Debug, V: Debug> Debug for IndexedSegmentMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.map.iter()).finish()
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for IndexedSegmentMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        // The index is derived from the map
+        self.map == other.map
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for IndexedSegmentMap<K, V> {}
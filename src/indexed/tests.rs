@@ -0,0 +1,57 @@
+use super::*;
+use alloc::{vec, vec::Vec};
+
+/// Check the index against a freshly built one
+fn assert_consistent(map: &IndexedSegmentMap<u32, u8>) {
+    let expected = IndexedSegmentMap::from(map.as_map().clone());
+    assert_eq!(map.index(), expected.index());
+}
+
+#[test]
+fn index_tracks_overwrites_and_removals() {
+    let mut map = IndexedSegmentMap::new();
+    let ops: Vec<(u32, u32, Option<u8>)> = vec![
+        (0, 10, Some(1)),
+        (5, 15, Some(2)),
+        (15, 20, Some(1)),
+        (8, 12, Some(1)),
+        (3, 18, None),
+        (0, 30, Some(3)),
+        (10, 20, Some(1)),
+        (12, 14, None),
+        (12, 14, Some(1)),
+    ];
+    for (start, end, value) in ops {
+        match value {
+            Some(value) => map.set(start..end, value),
+            None => map.clear_range(start..end),
+        }
+        assert_consistent(&map);
+    }
+
+    assert!(map
+        .ranges_for(&1)
+        .unwrap()
+        .iter()
+        .eq(&[Segment::from(10..20)]));
+    assert!(map.ranges_for(&2).is_none());
+
+    map.remove_value(&3);
+    assert_consistent(&map);
+    assert!(map.as_map().iter().eq(vec![(&Segment::from(10..20), &1)]));
+}
+
+#[test]
+fn index_matches_group_by_value() {
+    let mut map = SegmentMap::new();
+    for i in 0..20u32 {
+        map.set(i * 5..i * 5 + 3, (i % 3) as u8);
+    }
+    let indexed = IndexedSegmentMap::from(map.clone());
+    let groups = map.group_by_value();
+    assert_eq!(groups.len(), indexed.index().len());
+    for (value, ranges) in groups {
+        assert_eq!(ranges, map.ranges_for(value));
+        assert_eq!(ranges.cloned(), indexed.ranges_for(value).unwrap().clone());
+    }
+}
@@ -70,10 +70,10 @@ This crate can work without the full standard library available
 but relies on the presence of a global allocator &mdash;
 i.e. it links the `core` and `alloc` crates, but not `std`.
 
-Presently there is no functionality in the crate that require
-the standard library. Such functionality will likely be
-introduced in the future, and will be gated behind a default-on
-`std` feature.
+Functionality that requires the standard library is gated behind
+the default-on `std` feature. Presently, that is only the
+[`concurrent`] module (which needs threads and locks). Disable
+default features to build without it.
 
 See [The Rust Programming Language](https://doc.rust-lang.org/1.7.0/book/no-stdlib.html)
 book for general information about operating without the standard library.
@@ -85,15 +85,32 @@ book for general information about operating without the standard library.
 
 #![no_std]
 extern crate alloc;
-
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod allocator;
+pub mod bitemporal;
+#[cfg(feature = "std")]
+pub mod concurrent;
+pub mod frozen;
+pub mod half_open;
+pub mod indexed;
 pub mod map;
+pub mod persistent;
 pub mod segment;
 pub mod set;
+pub mod versioned;
 
 #[cfg(test)]
 mod stupid_range_map;
 
+pub use bitemporal::BitemporalSegmentMap;
 pub use core::ops::{Bound, RangeBounds};
-pub use map::SegmentMap;
+pub use frozen::{FrozenSegmentMap, FrozenSegmentSlice};
+pub use half_open::{HalfOpenSegmentMap, HalfOpenSegmentSet};
+pub use indexed::IndexedSegmentMap;
+pub use map::{Change, ChangeSet, CheckedAdd, SegmentMap, SpaceOverflow, Stickiness};
+pub use persistent::PersistentSegmentMap;
 pub use segment::Segment;
-pub use set::SegmentSet;
+pub use set::{ops::sweep::SegmentIterExt, SegmentSet};
+pub use versioned::VersionedSegmentMap;
@@ -7,10 +7,35 @@ use core::{
 };
 
 use crate::segment::{Segment, Start};
+pub use bulk::NotSorted;
+pub use cursor::{Cursor, CursorMut};
+pub use diff::{Change, Diff};
+pub use drain::{DrainRange, ExtractIf};
+pub use join::{Measure, OverlapJoin, Within};
 pub(crate) use key::Key;
-
+pub use lookup::LookupSorted;
+pub use report::ChangeSet;
+pub(crate) use report::Tracker;
+pub use space::{CheckedAdd, SpaceOverflow, Stickiness};
+pub use transaction::{Savepoint, Transaction};
+pub use transform::NotMonotonic;
+pub(crate) use transform::{collect_checked, collect_coalesced};
+pub use view::{ViewGaps, ViewMut};
+
+mod bulk;
+mod cursor;
+mod diff;
+mod drain;
 pub mod iterators;
+mod join;
 mod key;
+mod lookup;
+pub mod ops;
+mod report;
+pub(crate) mod space;
+mod transaction;
+mod transform;
+mod view;
 
 #[cfg(test)]
 mod tests;
@@ -23,6 +48,14 @@ mod tests;
 /// Internally, [`SegmentMap`] is represented by a [`BTreeMap`] in which the keys
 /// are represented by a concrete [`Range`] type, sorted by their start values.
 ///
+/// # Clone requirements
+///
+/// Most changes need `K: Clone`, since splitting or truncating a range stores
+/// the same bound in two ranges (the end of one, and the start of the next),
+/// and the overwritten or removed parts are handed back as owned ranges too.
+/// For the same reason, methods that could split a range need `V: Clone`,
+/// even if a given call doesn't.
+///
 /// # Examples
 ///
 /// TODO
@@ -255,6 +288,88 @@ impl<K, V> SegmentMap<K, V> {
         self.map.iter().next_back().map(|(range, _)| &range.0.end.0)
     }
 
+    /// Returns the first range in the map and its value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// assert_eq!(map.first(), None);
+    /// map.insert(5..9, "b");
+    /// map.insert(0..2, "a");
+    ///
+    /// assert_eq!(map.first(), Some((&Segment::from(0..2), &"a")));
+    /// ```
+    pub fn first(&self) -> Option<(&Segment<K>, &V)> {
+        self.map
+            .iter()
+            .next()
+            .map(|(range, value)| (&range.0, value))
+    }
+
+    /// Returns the last range in the map and its value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// assert_eq!(map.last(), None);
+    /// map.insert(5..9, "b");
+    /// map.insert(0..2, "a");
+    ///
+    /// assert_eq!(map.last(), Some((&Segment::from(5..9), &"b")));
+    /// ```
+    pub fn last(&self) -> Option<(&Segment<K>, &V)> {
+        self.map
+            .iter()
+            .next_back()
+            .map(|(range, value)| (&range.0, value))
+    }
+
+    /// Removes and returns the first range in the map and its value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.insert(0..2, "a");
+    /// map.insert(5..9, "b");
+    ///
+    /// assert_eq!(map.pop_first(), Some((Segment::from(0..2), "a")));
+    /// assert_eq!(map.pop_first(), Some((Segment::from(5..9), "b")));
+    /// assert_eq!(map.pop_first(), None);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<(Segment<K>, V)>
+    where
+        K: Ord,
+    {
+        self.map.pop_first().map(|(range, value)| (range.0, value))
+    }
+
+    /// Removes and returns the last range in the map and its value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.insert(0..2, "a");
+    /// map.insert(5..9, "b");
+    ///
+    /// assert_eq!(map.pop_last(), Some((Segment::from(5..9), "b")));
+    /// assert_eq!(map.pop_last(), Some((Segment::from(0..2), "a")));
+    /// assert_eq!(map.pop_last(), None);
+    /// ```
+    pub fn pop_last(&mut self) -> Option<(Segment<K>, V)>
+    where
+        K: Ord,
+    {
+        self.map.pop_last().map(|(range, value)| (range.0, value))
+    }
+
     /// Retains only the elements specified by the predicate.
     ///
     /// In other words, remove all pairs `(k, v)` such that `f(&k, &mut v)`
@@ -359,11 +474,11 @@ impl<K, V> SegmentMap<K, V> {
     /// # See Also
     ///
     /// - [`SegmentMap::set`] if you don't want to return the values
-    /// overwritten
+    ///   overwritten
     /// - [`SegmentMap::insert_if_empty`] if you only want to insert the value if
-    /// no overlaps occur
+    ///   no overlaps occur
     /// - [`SegmentMap::insert_in_gaps`] if you only want to insert the value for
-    /// the empty parts of the range, not overwriting any values.
+    ///   the empty parts of the range, not overwriting any values.
     ///
     pub fn insert<R>(&mut self, range: R, value: V) -> Option<Self>
     where
@@ -428,9 +543,9 @@ impl<K, V> SegmentMap<K, V> {
     ///
     /// - [`SegmentMap::insert`] if you want the value overwritten
     /// - [`SegmentMap::insert_if_empty`] if you only want to insert the value if
-    /// no overlaps occur
+    ///   no overlaps occur
     /// - [`SegmentMap::insert_in_gaps`] if you only want to insert the value for
-    /// the empty parts of the range, not overwriting any values.
+    ///   the empty parts of the range, not overwriting any values.
     ///
     pub fn set<R>(&mut self, range: R, value: V)
     where
@@ -460,9 +575,9 @@ impl<K, V> SegmentMap<K, V> {
     /// # See Also
     ///
     /// - [`SegmentMap::insert`] or [`SegmentMap::set`] if you want to overwrite
-    /// existing values
+    ///   existing values
     /// - [`SegmentMap::insert_in_gaps`] if you only want to insert the value for
-    /// the empty parts of the range
+    ///   the empty parts of the range
     ///
     pub fn insert_if_empty<R>(&mut self, range: R, value: V) -> Option<V>
     where
@@ -520,11 +635,11 @@ impl<K, V> SegmentMap<K, V> {
     /// # See Also
     ///
     /// - [`SegmentMap::insert`] or [`SegmentMap::set`] if you want to overwrite
-    /// existing values
+    ///   existing values
     /// - [`SegmentMap::insert_if_empty`] if you only want to insert the value if
-    /// no overlaps occur
+    ///   no overlaps occur
     /// - [`SegmentMap::with_value`] if you'd instead like to construct your map
-    /// with a default value for all possible ranges
+    ///   with a default value for all possible ranges
     ///
     pub fn insert_in_gaps<R>(&mut self, range: R, value: V)
     where
@@ -532,95 +647,7 @@ impl<K, V> SegmentMap<K, V> {
         K: Clone + Ord,
         V: Clone + Eq,
     {
-        let mut range = Segment::from(&range);
-
-        // In case this is an empty map, exit early
-        if self.map.is_empty() {
-            self.map.insert(Key(range), value);
-            return;
-        }
-
-        // Similar to insert, we need to see if any preceeding ranges overlap
-        // or touch this one
-
-        let leftmost = self
-            .map
-            .range(..=range.start.clone())
-            .rev()
-            .take_while(|(r, _)| r.0.touches(&range))
-            .last()
-            .map(|(k, v)| (k.clone(), v));
-
-        if let Some((leftmost_touching_range, leftmost_touching_value)) = leftmost {
-            // And merge if they have the same value
-            if value.eq(leftmost_touching_value) {
-                range.start = leftmost_touching_range.0.start.clone(); // Min is implied
-                if leftmost_touching_range.0.end > range.end {
-                    range.end = leftmost_touching_range.0.end.clone();
-                }
-                self.map.remove(&leftmost_touching_range);
-            } else if leftmost_touching_range.0.end < range.end {
-                // If this range extends past the end of the previous range,
-                // truncate this range.
-                range.start = leftmost_touching_range.0.bound_after().unwrap().cloned();
-            } else {
-                // Otherwise, we've exhausted the insertion range and don't need
-                // to add anything
-                return;
-            }
-        }
-
-        // Get successors of this insertion range. Both are treated the same
-        // (unlike in insert)
-        self.store.clear();
-        self.store.extend(
-            if let Some(bound_after) = range.bound_after().map(|b| b.cloned()) {
-                self.map.range(range.start.clone()..=bound_after)
-            } else {
-                self.map.range(range.start.clone()..)
-            }
-            .map(|(k, _)| k.clone()),
-        );
-
-        // Keep marching along the insertion range and insert gaps as we find them
-        for successor in self.store.drain(..) {
-            let successor_value = self.map.get(&successor).unwrap();
-            // If we can merge ranges, do so
-            if value.eq(successor_value) {
-                let (removed_range, _) = self.map.remove_entry(&successor).unwrap();
-                range.end = max(removed_range.0.end, range.end);
-            } else {
-                // Otherwise, we may need to insert a gap. We can only
-                // insert if the range starts before the successor
-                // (it shouldn't ever by greater, but could be equal)
-                if successor.0.start > range.start {
-                    self.map.insert(
-                        Key(Segment {
-                            start: range.start.clone(),
-                            end: successor
-                                .0
-                                .bound_before()
-                                .expect("Unexpected unbounded start")
-                                .cloned(),
-                        }),
-                        value.clone(),
-                    );
-                }
-
-                // After inserting the gap, move the start of the range to
-                // the end of this successor, if it exists. If not, this
-                // successor extends to the end and we're done.
-                if let Some(next_gap_start) = successor.0.bound_after() {
-                    range.start = next_gap_start.cloned();
-                } else {
-                    // Shouldn't actually be necessary, as it would be the last itme
-                    break;
-                }
-            }
-        }
-
-        // Any leftover range can then be inserted as a "last gap"
-        self.map.insert(Key(range), value);
+        self.insert_in_gaps_internal(Segment::from(&range), value, &mut MaybeMap::Never);
     }
 
     /// Remove all values in a given range, returning the removed values.
@@ -725,6 +752,15 @@ impl<K, V> SegmentMap<K, V> {
     /// assert_eq!(a[&4], "f");
     /// ```
     pub fn append(&mut self, other: &mut Self)
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.append_internal(other, &mut MaybeMap::Never);
+    }
+
+    /// Internal implementation for [`append`]
+    fn append_internal(&mut self, other: &mut Self, tracker: &mut impl Tracker<K, V>)
     where
         K: Clone + Ord,
         V: Clone + Eq,
@@ -737,12 +773,17 @@ impl<K, V> SegmentMap<K, V> {
             }
 
             // Self is empty, swap it with other
-            (None, _) => core::mem::swap(self, other),
+            (None, _) => {
+                core::mem::swap(self, other);
+                for range in self.map.keys() {
+                    tracker.added(&range.0);
+                }
+            }
 
             // Overlapping ranges, we must insert each range in other
             (Some(a), Some(b)) if a.overlaps(&b) => {
                 for (range, value) in core::mem::take(&mut other.map) {
-                    self.set(range.0, value)
+                    self.insert_internal(range.0, value, tracker)
                 }
             }
 
@@ -750,6 +791,7 @@ impl<K, V> SegmentMap<K, V> {
             // items in other directly into the inner map
             (Some(_), Some(_)) => {
                 for (range, value) in core::mem::take(&mut other.map) {
+                    tracker.added(&range.0);
                     self.map.insert(range, value);
                 }
             }
@@ -875,19 +917,119 @@ impl<K, V> SegmentMap<K, V> {
 
     // }
 
-    /// Internal implementation for [`insert`], [`set`], and similar
-    fn insert_internal(
+    /// Internal implementation for [`insert_in_gaps`]
+    fn insert_in_gaps_internal(
         &mut self,
         mut range: Segment<K>,
         value: V,
-        removed_ranges: &mut MaybeMap<K, V>,
+        tracker: &mut impl Tracker<K, V>,
     ) where
         K: Clone + Ord,
         V: Clone + Eq,
     {
         // In case this is an empty map, exit early
         if self.map.is_empty() {
-            self.map.insert(Key(range), value);
+            tracker.added(&range);
+            store(&mut self.map, Key(range), value, tracker);
+            return;
+        }
+
+        // Similar to insert, we need to see if any preceeding ranges overlap
+        // or touch this one
+
+        let leftmost = self
+            .map
+            .range(..=range.start.clone())
+            .rev()
+            .take_while(|(r, _)| r.0.touches(&range))
+            .last()
+            .map(|(k, v)| (k.clone(), v));
+
+        if let Some((leftmost_touching_range, leftmost_touching_value)) = leftmost {
+            // And merge if they have the same value
+            if value.eq(leftmost_touching_value) {
+                range.start = leftmost_touching_range.0.start.clone(); // Min is implied
+                if leftmost_touching_range.0.end > range.end {
+                    range.end = leftmost_touching_range.0.end.clone();
+                }
+                unstore(&mut self.map, &leftmost_touching_range, tracker);
+                tracker.merged(&leftmost_touching_range.0);
+            } else if leftmost_touching_range.0.end < range.end {
+                // If this range extends past the end of the previous range,
+                // truncate this range.
+                range.start = leftmost_touching_range.0.bound_after().unwrap().cloned();
+            } else {
+                // Otherwise, we've exhausted the insertion range and don't need
+                // to add anything
+                return;
+            }
+        }
+
+        // Get successors of this insertion range. Both are treated the same
+        // (unlike in insert)
+        self.store.clear();
+        self.store.extend(
+            if let Some(bound_after) = range.bound_after().map(|b| b.cloned()) {
+                self.map.range(range.start.clone()..=bound_after)
+            } else {
+                self.map.range(range.start.clone()..)
+            }
+            .map(|(k, _)| k.clone()),
+        );
+
+        // Keep marching along the insertion range and insert gaps as we find them
+        for successor in self.store.drain(..) {
+            let successor_value = self.map.get(&successor).unwrap();
+            // If we can merge ranges, do so
+            if value.eq(successor_value) {
+                let (removed_range, _) = unstore(&mut self.map, &successor, tracker);
+                tracker.merged(&removed_range.0);
+                range.end = max(removed_range.0.end, range.end);
+            } else {
+                // Otherwise, we may need to insert a gap. We can only
+                // insert if the range starts before the successor
+                // (it shouldn't ever by greater, but could be equal)
+                if successor.0.start > range.start {
+                    let gap = Segment {
+                        start: range.start.clone(),
+                        end: successor
+                            .0
+                            .bound_before()
+                            .expect("Unexpected unbounded start")
+                            .cloned(),
+                    };
+                    tracker.added(&gap);
+                    store(&mut self.map, Key(gap), value.clone(), tracker);
+                }
+
+                // If this successor covers the rest of the range, there's no
+                // gap left to fill
+                if successor.0.end >= range.end {
+                    return;
+                }
+
+                // Otherwise move the start of the range to the end of this
+                // successor (which can't be unbounded, since it's less than
+                // `range.end`)
+                range.start = successor.0.bound_after().unwrap().cloned();
+            }
+        }
+
+        // Any leftover range can then be inserted as a "last gap"
+        tracker.added(&range);
+        store(&mut self.map, Key(range), value, tracker);
+    }
+
+    /// Internal implementation for [`insert`], [`set`], and similar
+    fn insert_internal(&mut self, mut range: Segment<K>, value: V, tracker: &mut impl Tracker<K, V>)
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        // In case this is an empty map, exit early
+        if self.map.is_empty() {
+            tracker.added(&range);
+            store(&mut self.map, Key(range), value, tracker);
             return;
         }
 
@@ -918,10 +1060,11 @@ impl<K, V> SegmentMap<K, V> {
                 if leftmost_touching_range.0.end > range.end {
                     range.end = leftmost_touching_range.0.end.clone();
                 }
-                self.map.remove(&leftmost_touching_range);
+                unstore(&mut self.map, &leftmost_touching_range, tracker);
+                tracker.merged(&leftmost_touching_range.0);
             } else if range.overlaps(&leftmost_touching_range.0) {
                 // Split an overlapping range to preserve non-overlapped values
-                self.split_key(&leftmost_touching_range, &range, removed_ranges);
+                self.split_key(&leftmost_touching_range, &range, tracker);
             }
             // Otherwise, touches (with a different value) but doesn't overlap,
             // leave the existing range alone.
@@ -954,7 +1097,9 @@ impl<K, V> SegmentMap<K, V> {
                         // For merging, we don't care if this is a touching or
                         // overlapping range, just that we may need to extend the
                         // end of the inserted range to merge with it.
-                        let (successor, _) = entry.remove_entry();
+                        let (successor, successor_value) = entry.remove_entry();
+                        tracker.unstored(&successor, &successor_value);
+                        tracker.merged(&successor.0);
                         if successor.0.end > range.end {
                             range.end = successor.0.end;
                         }
@@ -962,10 +1107,13 @@ impl<K, V> SegmentMap<K, V> {
                         // Otherwise, if the range is overlapping (not just
                         // touching), it will need to be partially or fully removed
                         let (mut successor, successor_value) = entry.remove_entry();
+                        tracker.unstored(&successor, &successor_value);
 
                         // If overlapping, we need to split and reinsert it
                         if successor.0.end > range.end {
-                            self.map.insert(
+                            tracker.split(&successor.0);
+                            store(
+                                &mut self.map,
                                 Key(Segment {
                                     start: bound_after,
                                     end: core::mem::replace(
@@ -974,12 +1122,13 @@ impl<K, V> SegmentMap<K, V> {
                                     ),
                                 }),
                                 successor_value.clone(),
+                                tracker,
                             );
-                            removed_ranges.insert(successor, successor_value);
+                            tracker.removed(successor, successor_value);
                             break;
                         } else {
                             // Store the removed portion
-                            removed_ranges.insert(successor, successor_value);
+                            tracker.removed(successor, successor_value);
                         }
                     }
                     // Otherwise (touching and different value), leave the successor alone
@@ -994,15 +1143,18 @@ impl<K, V> SegmentMap<K, V> {
                 .collect::<alloc::vec::Vec<_>>();
 
             for successor in successors {
-                let v = self.map.remove(&successor).unwrap();
+                let (successor, v) = unstore(&mut self.map, &successor, tracker);
                 if value != v {
-                    removed_ranges.insert(successor, v);
+                    tracker.removed(successor, v);
+                } else {
+                    tracker.merged(&successor.0);
                 }
             }
         }
 
         // Finally, insert the new range and return the removed ranges
-        self.map.insert(Key(range), value);
+        tracker.added(&range);
+        store(&mut self.map, Key(range), value, tracker);
     }
 
     /// Remove a specified range (`range_to_remove`) from an area of the map
@@ -1015,18 +1167,24 @@ impl<K, V> SegmentMap<K, V> {
         &mut self,
         key: &Key<K>,
         range_to_remove: &Segment<K>,
-        removed_ranges: &mut MaybeMap<K, V>,
+        tracker: &mut impl Tracker<K, V>,
     ) where
         K: Clone + Ord,
         V: Clone,
     {
         // Unwrap here is fine, since the callers of this should have already
         // determined that the key exists
-        let (mut removed_range, value) = self.map.remove_entry(key).unwrap();
+        let (mut removed_range, value) = unstore(&mut self.map, key, tracker);
+        if removed_range.0.start < range_to_remove.start
+            || removed_range.0.end > range_to_remove.end
+        {
+            tracker.split(&removed_range.0);
+        }
 
         // Insert a split of the range to the left (if necessary)
         if removed_range.0.start < range_to_remove.start {
-            self.map.insert(
+            store(
+                &mut self.map,
                 Key(Segment {
                     start: core::mem::replace(
                         &mut removed_range.0.start,
@@ -1035,23 +1193,26 @@ impl<K, V> SegmentMap<K, V> {
                     end: range_to_remove.bound_before().unwrap().cloned(), // From above inequality, this must not be unbound
                 }),
                 value.clone(),
+                tracker,
             );
         }
 
         // Insert a split of the range to the right (if necessary)
         if removed_range.0.end > range_to_remove.end {
-            self.map.insert(
+            store(
+                &mut self.map,
                 Key(Segment {
                     start: range_to_remove.bound_after().unwrap().cloned(), // same as above
                     end: core::mem::replace(&mut removed_range.0.end, range_to_remove.end.clone()),
                 }),
                 value.clone(),
+                tracker,
             );
         }
-        removed_ranges.insert(removed_range, value);
+        tracker.removed(removed_range, value);
     }
 
-    pub(crate) fn remove_internal(&mut self, range: Segment<K>, removed_ranges: &mut MaybeMap<K, V>)
+    pub(crate) fn remove_internal(&mut self, range: Segment<K>, tracker: &mut impl Tracker<K, V>)
     where
         K: Clone + Ord,
         V: Clone,
@@ -1065,46 +1226,49 @@ impl<K, V> SegmentMap<K, V> {
         let previous_range = self
             .map
             .range(..=range.start.clone())
-            .rev()
-            .next()
+            .next_back()
             .map(|(k, _)| k.clone());
         if let Some(previous_range) = previous_range {
             // Split an overlapping range to preserve non-overlapped values
             if range.overlaps(&previous_range.0) {
-                self.split_key(&previous_range, &range, removed_ranges);
+                self.split_key(&previous_range, &range, tracker);
             }
         }
 
         // Check if there are any ranges starting inside the range to remove.
         // Unlike insert, we don't care about touching ranges because we
-        // won't be merging them.
+        // won't be merging them (this also skips the right side of a split
+        // previous range, which starts immediately after).
         self.store.clear();
         self.store.extend(
             if let Some(after) = range.bound_after().map(|b| b.cloned()) {
-                self.map.range(range.start.clone()..=after)
+                self.map.range(range.start.clone()..after)
             } else {
                 self.map.range(range.start.clone()..)
             }
             .map(|(k, _)| k.clone()),
         );
 
-        for mut successor in self.store.drain(..) {
-            let value = self.map.remove(&successor).unwrap();
+        for successor in self.store.drain(..) {
+            let (mut successor, value) = unstore(&mut self.map, &successor, tracker);
 
             // Must be the last range
             if successor.0.end > range.end {
-                self.map.insert(
+                tracker.split(&successor.0);
+                store(
+                    &mut self.map,
                     Key(Segment {
                         start: range.bound_after().unwrap().cloned(), // Implicitly not none due to less than successor end
                         end: successor.0.end.clone(),
                     }),
                     value.clone(),
+                    tracker,
                 );
                 successor.0.end = range.end;
-                removed_ranges.insert(successor, value);
+                tracker.removed(successor, value);
                 break;
             } else {
-                removed_ranges.insert(successor, value);
+                tracker.removed(successor, value);
             }
         }
     }
@@ -1174,6 +1338,31 @@ where
     }
 }
 
+/// Insert into the inner map of a [`SegmentMap`], notifying `tracker`
+fn store<K: Ord, V>(
+    map: &mut BTreeMap<Key<K>, V>,
+    key: Key<K>,
+    value: V,
+    tracker: &mut impl Tracker<K, V>,
+) {
+    tracker.stored(&key);
+    map.insert(key, value);
+}
+
+/// Remove an existing key from the inner map of a [`SegmentMap`], notifying
+/// `tracker`
+fn unstore<K: Ord, V>(
+    map: &mut BTreeMap<Key<K>, V>,
+    key: &Key<K>,
+    tracker: &mut impl Tracker<K, V>,
+) -> (Key<K>, V) {
+    // Callers should have already determined that the key exists
+    let (key, value) = map.remove_entry(key).unwrap();
+    tracker.unstored(&key, &value);
+    (key, value)
+}
+
+/// The simplest [`Tracker`], which only (optionally) collects removed ranges
 pub(crate) enum MaybeMap<K, V> {
     // Never do anything
     Never,
@@ -1186,8 +1375,8 @@ pub(crate) enum MaybeMap<K, V> {
     Uninitialized,
     Map(BTreeMap<Key<K>, V>),
 }
-impl<K: Ord, V> MaybeMap<K, V> {
-    fn insert(&mut self, key: Key<K>, value: V) {
+impl<K: Ord, V> Tracker<K, V> for MaybeMap<K, V> {
+    fn removed(&mut self, key: Key<K>, value: V) {
         match self {
             MaybeMap::Never | MaybeMap::Some => {} //NoOp
             MaybeMap::None => *self = MaybeMap::Some,
@@ -0,0 +1,163 @@
+use alloc::vec::Vec;
+use core::{fmt, ops::RangeBounds};
+
+use super::{collect_checked, collect_coalesced};
+use crate::{segment::Segment, SegmentMap};
+
+/// Error returned by [`SegmentMap::from_sorted_iter`] when the given ranges
+/// are out of order or overlap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NotSorted;
+
+impl fmt::Display for NotSorted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ranges are not sorted, or overlap")
+    }
+}
+
+impl<K, V> SegmentMap<K, V> {
+    /// Build a map from ranges that are already sorted and don't overlap.
+    ///
+    /// Touching ranges with equal values are coalesced, and the internal tree
+    /// is built directly (in linear time) without going through insertion.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NotSorted`] if any range overlaps or comes before the one
+    /// before it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// use segmap::map::NotSorted;
+    ///
+    /// let map = SegmentMap::from_sorted_iter(vec![(0..5, "a"), (5..10, "a"), (20..30, "b")]);
+    /// assert!(map.unwrap().into_iter().eq(vec![
+    ///     (Segment::from(0..10), "a"),
+    ///     (Segment::from(20..30), "b"),
+    /// ]));
+    ///
+    /// let map = SegmentMap::from_sorted_iter(vec![(0..5, "a"), (3..10, "b")]);
+    /// assert_eq!(map, Err(NotSorted));
+    /// ```
+    pub fn from_sorted_iter<I, R>(iter: I) -> Result<Self, NotSorted>
+    where
+        I: IntoIterator<Item = (R, V)>,
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Eq,
+    {
+        collect_checked(
+            iter.into_iter()
+                .map(|(range, value)| (Segment::from(&range), value)),
+        )
+        .ok_or(NotSorted)
+    }
+
+    /// Set values for many ranges at once.
+    ///
+    /// This gives the same result as calling [`SegmentMap::set`] for each
+    /// range in order (so later ranges overwrite earlier ones), but sorts the
+    /// batch and merges it into the map in a single pass. That pass rebuilds
+    /// the map, so for a handful of ranges in a large map, calling
+    /// [`SegmentMap::set`] for each is faster.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(20..30, "b");
+    ///
+    /// map.set_many(vec![(25..40, "c"), (5..8, "c"), (8..22, "b"), (38..45, "d")]);
+    /// assert!(map.into_iter().eq(vec![
+    ///     (Segment::from(0..5), "a"),
+    ///     (Segment::from(5..8), "c"),
+    ///     (Segment::from(8..25), "b"),
+    ///     (Segment::from(25..38), "c"),
+    ///     (Segment::from(38..45), "d"),
+    /// ]));
+    /// ```
+    pub fn set_many<I, R>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (R, V)>,
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let mut batch: Vec<(usize, Segment<K>, V)> = iter
+            .into_iter()
+            .enumerate()
+            .map(|(i, (range, value))| (i, Segment::from(&range), value))
+            .collect();
+        if batch.is_empty() {
+            return;
+        }
+        batch.sort_by(|(_, a, _), (_, b, _)| a.start.cmp(&b.start));
+
+        // Later ranges in the batch win where they overlap. That needs
+        // ordinary insertion to sort out, but only within the batch.
+        let overlapping = batch
+            .windows(2)
+            .any(|pair| !pair[0].1.end.cmp_start(&pair[1].1.start).is_lt());
+        let batch: Vec<(Segment<K>, V)> = if overlapping {
+            batch.sort_by_key(|(i, _, _)| *i);
+            let mut resolved = SegmentMap::new();
+            for (_, range, value) in batch {
+                resolved.set(range, value);
+            }
+            resolved.into_iter().collect()
+        } else {
+            batch
+                .into_iter()
+                .map(|(_, range, value)| (range, value))
+                .collect()
+        };
+
+        let existing = core::mem::take(&mut self.map);
+        let mut merged = Vec::with_capacity(existing.len() + batch.len());
+        let mut batch = batch.into_iter().peekable();
+        for (key, value) in existing {
+            let mut range = key.0;
+            loop {
+                // New ranges entirely before this one go first
+                while let Some((next, _)) = batch.peek() {
+                    if !next.end.cmp_start(&range.start).is_lt() {
+                        break;
+                    }
+                    merged.extend(batch.next());
+                }
+
+                match batch.peek() {
+                    Some((next, _)) if next.overlaps(&range) => {
+                        // Keep any part before the new range
+                        if range.start < next.start {
+                            let before = Segment {
+                                start: range.start.clone(),
+                                end: next.bound_before().unwrap().cloned(),
+                            };
+                            merged.push((before, value.clone()));
+                        }
+
+                        // And carry on with any part after it
+                        if next.end < range.end {
+                            range.start = next.bound_after().unwrap().cloned();
+                            merged.extend(batch.next());
+                        } else {
+                            break;
+                        }
+                    }
+                    _ => {
+                        merged.push((range, value));
+                        break;
+                    }
+                }
+            }
+        }
+        merged.extend(batch);
+
+        self.map = collect_coalesced(merged.into_iter()).map;
+    }
+}
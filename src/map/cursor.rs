@@ -0,0 +1,624 @@
+use alloc::collections::{btree_map, BTreeMap};
+use core::{
+    fmt::{self, Debug},
+    ops::{
+        Bound::{self, Excluded, Included, Unbounded},
+        RangeBounds,
+    },
+};
+
+use super::Key;
+use crate::{
+    segment::{End, Start},
+    Segment, SegmentMap,
+};
+
+/// Where a [`CursorMut`] is: on a stored segment (identified by its key), or
+/// in the gap between two segments
+#[derive(Clone, Debug)]
+enum Position<K> {
+    Segment(Key<K>),
+    Gap(Segment<K>),
+}
+
+/// Where a [`Cursor`] is, borrowing the segment it's on from the map
+enum Place<'a, K, V> {
+    Segment(&'a Key<K>, &'a V),
+    Gap(Segment<K>),
+}
+
+/// A read-only cursor over the segments and gaps of a [`SegmentMap`]
+///
+/// Created by [`SegmentMap::cursor_at`]. The cursor is always either on a
+/// stored segment or in a gap (a maximal range with no value), and moves
+/// between them in order.
+///
+/// The cursor keeps iterators over the segments on either side of it, so
+/// moving in the same direction just steps them (in amortized constant time).
+/// Only turning around needs a new `O(log n)` lookup, to find the segments
+/// behind it again.
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// let mut map = SegmentMap::new();
+/// map.set(0..10, "a");
+/// map.set(10..20, "b");
+/// map.set(30..40, "c");
+///
+/// let mut cursor = map.cursor_at(&5);
+/// assert_eq!(cursor.segment(), Some((&Segment::from(0..10), &"a")));
+///
+/// cursor.move_next();
+/// assert_eq!(cursor.segment(), Some((&Segment::from(10..20), &"b")));
+///
+/// cursor.move_next();
+/// assert_eq!(cursor.gap(), Some(&Segment::from(20..30)));
+///
+/// assert!(cursor.move_next_segment());
+/// assert_eq!(cursor.segment(), Some((&Segment::from(30..40), &"c")));
+/// ```
+pub struct Cursor<'a, K, V> {
+    map: &'a BTreeMap<Key<K>, V>,
+    place: Place<'a, K, V>,
+
+    /// Segments starting after the cursor, or `None` if they need to be
+    /// looked up again (after moving backwards)
+    ahead: Option<btree_map::Range<'a, Key<K>, V>>,
+
+    /// Segments starting before the cursor, or `None` if they need to be
+    /// looked up again (after moving forwards)
+    behind: Option<btree_map::Range<'a, Key<K>, V>>,
+}
+
+/// A cursor over the segments and gaps of a [`SegmentMap`], which can edit
+/// the map in place
+///
+/// Created by [`SegmentMap::cursor_mut_at`]. Moves work like [`Cursor`], but
+/// since the standard `BTreeMap` has no stable mutable cursors, each move is
+/// a single `O(log n)` lookup (from the current key) rather than a step. Edits
+/// only touch the current segment and its direct neighbours, and every edit
+/// keeps the map coalesced: when a changed segment ends up touching
+/// a neighbour with the same value, they are merged, and the cursor is left on
+/// the merged segment.
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// let mut map = SegmentMap::new();
+/// map.set(0..10, 1);
+/// map.set(20..30, 2);
+/// map.set(30..40, 3);
+///
+/// // Double every value, and fill the gaps after 0 with zeroes
+/// let mut cursor = map.cursor_mut_at(&0);
+/// loop {
+///     match cursor.segment() {
+///         Some((_, &value)) => {
+///             cursor.replace_value(value * 2);
+///         }
+///         None => {
+///             cursor.insert_in_current_gap(0);
+///         }
+///     }
+///     if !cursor.move_next() {
+///         break;
+///     }
+/// }
+///
+/// assert!(map.iter().eq(vec![
+///     (&Segment::from(0..10), &2),
+///     (&Segment::from(10..20), &0),
+///     (&Segment::from(20..30), &4),
+///     (&Segment::from(30..40), &6),
+///     (&Segment::from(40..), &0),
+/// ]));
+/// ```
+pub struct CursorMut<'a, K, V> {
+    map: &'a mut BTreeMap<Key<K>, V>,
+    position: Position<K>,
+}
+
+impl<K, V> SegmentMap<K, V> {
+    /// Get a [`Cursor`] positioned on the segment (or gap) containing `at`.
+    pub fn cursor_at(&self, at: &K) -> Cursor<'_, K, V>
+    where
+        K: Clone + Ord,
+    {
+        let start = Start(Included(at.clone()));
+        let place = match self.map.range::<Start<K>, _>(..=&start).next_back() {
+            Some((key, value)) if key.0.contains(at) => Place::Segment(key, value),
+            _ => Place::Gap(gap_around(&self.map, &start)),
+        };
+        Cursor {
+            map: &self.map,
+            place,
+            ahead: None,
+            behind: None,
+        }
+    }
+
+    /// Get a [`CursorMut`] positioned on the segment (or gap) containing
+    /// `at`.
+    pub fn cursor_mut_at(&mut self, at: &K) -> CursorMut<'_, K, V>
+    where
+        K: Clone + Ord,
+    {
+        CursorMut {
+            position: position_at(&self.map, at),
+            map: &mut self.map,
+        }
+    }
+}
+
+impl<'a, K: Clone + Ord, V> Cursor<'a, K, V> {
+    /// The segment the cursor is on, if it's not in a gap
+    pub fn segment(&self) -> Option<(&Segment<K>, &V)> {
+        match &self.place {
+            Place::Segment(key, value) => Some((&key.0, *value)),
+            Place::Gap(_) => None,
+        }
+    }
+
+    /// The gap the cursor is in, if it's not on a segment
+    pub fn gap(&self) -> Option<&Segment<K>> {
+        match &self.place {
+            Place::Segment(..) => None,
+            Place::Gap(gap) => Some(gap),
+        }
+    }
+
+    /// Move to the next segment or gap, returning `false` (without moving)
+    /// if there is none.
+    pub fn move_next(&mut self) -> bool {
+        let next = self.ahead().clone().next();
+        match (&self.place, next) {
+            (Place::Segment(key, _), _) if matches!(key.0.end.0, Unbounded) => return false,
+            (Place::Segment(key, _), Some((next, _))) if !next.0.touches(&key.0) => {
+                self.place = Place::Gap(gap_between(Some(&key.0), Some(&next.0)));
+            }
+            (Place::Segment(key, _), None) => {
+                self.place = Place::Gap(gap_between(Some(&key.0), None));
+            }
+            (_, Some((next, value))) => {
+                self.ahead().next();
+                self.place = Place::Segment(next, value);
+            }
+            (Place::Gap(_), None) => return false,
+        }
+        self.behind = None;
+        true
+    }
+
+    /// Move to the previous segment or gap, returning `false` (without
+    /// moving) if there is none.
+    pub fn move_prev(&mut self) -> bool {
+        let prev = self.behind().clone().next_back();
+        match (&self.place, prev) {
+            (Place::Segment(key, _), _) if matches!(key.0.start.0, Unbounded) => return false,
+            (Place::Segment(key, _), Some((prev, _))) if !prev.0.touches(&key.0) => {
+                self.place = Place::Gap(gap_between(Some(&prev.0), Some(&key.0)));
+            }
+            (Place::Segment(key, _), None) => {
+                self.place = Place::Gap(gap_between(None, Some(&key.0)));
+            }
+            (_, Some((prev, value))) => {
+                self.behind().next_back();
+                self.place = Place::Segment(prev, value);
+            }
+            (Place::Gap(_), None) => return false,
+        }
+        self.ahead = None;
+        true
+    }
+
+    /// Move to the next segment, skipping any gap, returning `false` (without
+    /// moving) if there is none.
+    pub fn move_next_segment(&mut self) -> bool {
+        match self.ahead().next() {
+            Some((next, value)) => {
+                self.place = Place::Segment(next, value);
+                self.behind = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move to the previous segment, skipping any gap, returning `false`
+    /// (without moving) if there is none.
+    pub fn move_prev_segment(&mut self) -> bool {
+        match self.behind().next_back() {
+            Some((prev, value)) => {
+                self.place = Place::Segment(prev, value);
+                self.ahead = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The start of the current segment or gap
+    fn start(&self) -> &Start<K> {
+        match &self.place {
+            Place::Segment(key, _) => &key.0.start,
+            Place::Gap(gap) => &gap.start,
+        }
+    }
+
+    fn ahead(&mut self) -> &mut btree_map::Range<'a, Key<K>, V> {
+        if self.ahead.is_none() {
+            let map = self.map;
+            self.ahead = Some(map.range::<Start<K>, _>((Excluded(self.start()), Unbounded)));
+        }
+        self.ahead.as_mut().unwrap()
+    }
+
+    fn behind(&mut self) -> &mut btree_map::Range<'a, Key<K>, V> {
+        if self.behind.is_none() {
+            let map = self.map;
+            self.behind = Some(map.range::<Start<K>, _>((Unbounded, Excluded(self.start()))));
+        }
+        self.behind.as_mut().unwrap()
+    }
+}
+
+impl<K: Clone + Ord, V> CursorMut<'_, K, V> {
+    /// The segment the cursor is on, if it's not in a gap
+    pub fn segment(&self) -> Option<(&Segment<K>, &V)> {
+        segment(self.map, &self.position)
+    }
+
+    /// The gap the cursor is in, if it's not on a segment
+    pub fn gap(&self) -> Option<&Segment<K>> {
+        gap(&self.position)
+    }
+
+    /// Move to the next segment or gap, returning `false` (without moving)
+    /// if there is none.
+    pub fn move_next(&mut self) -> bool {
+        move_next(self.map, &mut self.position)
+    }
+
+    /// Move to the previous segment or gap, returning `false` (without
+    /// moving) if there is none.
+    pub fn move_prev(&mut self) -> bool {
+        move_prev(self.map, &mut self.position)
+    }
+
+    /// Move to the next segment, skipping any gap, returning `false` (without
+    /// moving) if there is none.
+    pub fn move_next_segment(&mut self) -> bool {
+        move_next_segment(self.map, &mut self.position)
+    }
+
+    /// Move to the previous segment, skipping any gap, returning `false`
+    /// (without moving) if there is none.
+    pub fn move_prev_segment(&mut self) -> bool {
+        move_prev_segment(self.map, &mut self.position)
+    }
+
+    /// Replace the value of the current segment, returning the old value, or
+    /// `None` (without changing anything) if the cursor is in a gap.
+    pub fn replace_value(&mut self, value: V) -> Option<V>
+    where
+        V: Eq,
+    {
+        let key = match &self.position {
+            Position::Segment(key) => key.clone(),
+            Position::Gap(_) => return None,
+        };
+        let (Key(range), old) = self.map.remove_entry(&key.0.start).unwrap();
+        self.store_coalesced(range, value);
+        Some(old)
+    }
+
+    /// Split the current segment so that the part starting at `at` holds
+    /// `value`, leaving the cursor on the part before it.
+    ///
+    /// If `value` is the same as the current value, nothing changes (the
+    /// parts would be coalesced again). Returns `false` without changing
+    /// anything if the cursor isn't on a segment, or `at` isn't inside the
+    /// current segment after its start.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(10..20, "b");
+    ///
+    /// let mut cursor = map.cursor_mut_at(&0);
+    /// assert!(cursor.split_at(Bound::Included(5), "b"));
+    /// assert_eq!(cursor.segment(), Some((&Segment::from(0..5), &"a")));
+    ///
+    /// // The new part was coalesced with its neighbour
+    /// assert!(map.iter().eq(vec![
+    ///     (&Segment::from(0..5), &"a"),
+    ///     (&Segment::from(5..20), &"b"),
+    /// ]));
+    /// ```
+    pub fn split_at(&mut self, at: Bound<K>, value: V) -> bool
+    where
+        V: Eq,
+    {
+        let key = match &self.position {
+            Position::Segment(key) => key,
+            Position::Gap(_) => return false,
+        };
+        let at = Start(at);
+        let before = match at.before() {
+            Some(before) => before.cloned(),
+            None => return false,
+        };
+        if at <= key.0.start || key.0.end.cmp_start(&at).is_lt() {
+            return false;
+        }
+
+        let (Key(range), old) = self.map.remove_entry(&key.0.start).unwrap();
+        if old == value {
+            self.map.insert(Key(range), old);
+            return true;
+        }
+
+        let first = Segment {
+            start: range.start,
+            end: before,
+        };
+        let second = Segment {
+            start: at,
+            end: range.end,
+        };
+        self.map.insert(Key(first.clone()), old);
+        self.store_coalesced(second, value);
+        self.position = Position::Segment(Key(first));
+        true
+    }
+
+    /// Remove the current segment, leaving the cursor in the gap where it
+    /// was. Returns `None` if the cursor is already in a gap.
+    pub fn remove_current(&mut self) -> Option<(Segment<K>, V)> {
+        let key = match &self.position {
+            Position::Segment(key) => key.clone(),
+            Position::Gap(_) => return None,
+        };
+        let (Key(range), value) = self.map.remove_entry(&key.0.start).unwrap();
+        self.position = Position::Gap(gap_around(self.map, &range.start));
+        Some((range, value))
+    }
+
+    /// Fill the current gap with `value`, moving the cursor onto the new
+    /// segment. Returns `false` if the cursor is on a segment.
+    pub fn insert_in_current_gap(&mut self, value: V) -> bool
+    where
+        V: Eq,
+    {
+        let gap = match &self.position {
+            Position::Gap(gap) => gap.clone(),
+            Position::Segment(_) => return false,
+        };
+        self.store_coalesced(gap, value);
+        true
+    }
+
+    /// Extend the current segment over the next one (and any gap between
+    /// them), returning the value of the next segment. Returns `None` if the
+    /// cursor is in a gap or there is no next segment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(15..20, "b");
+    /// map.set(20..30, "a");
+    ///
+    /// let mut cursor = map.cursor_mut_at(&0);
+    /// assert_eq!(cursor.merge_with_next(), Some("b"));
+    /// assert_eq!(cursor.segment(), Some((&Segment::from(0..30), &"a")));
+    /// ```
+    pub fn merge_with_next(&mut self) -> Option<V>
+    where
+        V: Eq,
+    {
+        let key = match &self.position {
+            Position::Segment(key) => key.clone(),
+            Position::Gap(_) => return None,
+        };
+        let (next, _) = next_segment(self.map, &key.0.start)?;
+        let next = next.0.start.clone();
+        let (Key(next), next_value) = self.map.remove_entry(&next).unwrap();
+        let (Key(mut range), value) = self.map.remove_entry(&key.0.start).unwrap();
+        range.end = next.end;
+        self.store_coalesced(range, value);
+        Some(next_value)
+    }
+
+    /// Store `range` (which must not overlap anything), merging with
+    /// touching neighbours that have the same value, and move onto it
+    fn store_coalesced(&mut self, mut range: Segment<K>, value: V)
+    where
+        V: Eq,
+    {
+        let prev = prev_segment(self.map, &range.start)
+            .filter(|(prev, prev_value)| prev.0.touches(&range) && **prev_value == value)
+            .map(|(prev, _)| prev.0.start.clone());
+        if let Some(prev) = prev {
+            let (Key(prev), _) = self.map.remove_entry(&prev).unwrap();
+            range.start = prev.start;
+        }
+        let next = next_segment(self.map, &range.start)
+            .filter(|(next, next_value)| next.0.touches(&range) && **next_value == value)
+            .map(|(next, _)| next.0.start.clone());
+        if let Some(next) = next {
+            let (Key(next), _) = self.map.remove_entry(&next).unwrap();
+            range.end = next.end;
+        }
+        self.map.insert(Key(range.clone()), value);
+        self.position = Position::Segment(Key(range));
+    }
+}
+
+// Shared implementation of both cursors
+
+fn prev_segment<'a, K: Ord, V>(
+    map: &'a BTreeMap<Key<K>, V>,
+    start: &Start<K>,
+) -> Option<(&'a Key<K>, &'a V)> {
+    map.range::<Start<K>, _>((Unbounded, Excluded(start)))
+        .next_back()
+}
+
+fn next_segment<'a, K: Ord, V>(
+    map: &'a BTreeMap<Key<K>, V>,
+    start: &Start<K>,
+) -> Option<(&'a Key<K>, &'a V)> {
+    map.range::<Start<K>, _>((Excluded(start), Unbounded))
+        .next()
+}
+
+/// The gap between two (non-touching) segments, either of which may be
+/// missing at the ends of the map
+fn gap_between<K: Clone>(prev: Option<&Segment<K>>, next: Option<&Segment<K>>) -> Segment<K> {
+    Segment {
+        start: prev
+            .and_then(|prev| prev.bound_after())
+            .map_or(Start(Unbounded), |start| start.cloned()),
+        end: next
+            .and_then(|next| next.bound_before())
+            .map_or(End(Unbounded), |end| end.cloned()),
+    }
+}
+
+/// The gap covering `start`, which must not be covered by a segment
+fn gap_around<K: Clone + Ord, V>(map: &BTreeMap<Key<K>, V>, start: &Start<K>) -> Segment<K> {
+    gap_between(
+        prev_segment(map, start).map(|(key, _)| &key.0),
+        next_segment(map, start).map(|(key, _)| &key.0),
+    )
+}
+
+fn position_at<K: Clone + Ord, V>(map: &BTreeMap<Key<K>, V>, at: &K) -> Position<K> {
+    let start = Start(Included(at.clone()));
+    match map.range::<Start<K>, _>(..=&start).next_back() {
+        Some((key, _)) if key.0.contains(at) => Position::Segment(key.clone()),
+        _ => Position::Gap(gap_around(map, &start)),
+    }
+}
+
+fn segment<'a, K: Ord, V>(
+    map: &'a BTreeMap<Key<K>, V>,
+    position: &Position<K>,
+) -> Option<(&'a Segment<K>, &'a V)> {
+    match position {
+        Position::Segment(key) => map
+            .get_key_value(&key.0.start)
+            .map(|(key, value)| (&key.0, value)),
+        Position::Gap(_) => None,
+    }
+}
+
+fn gap<K>(position: &Position<K>) -> Option<&Segment<K>> {
+    match position {
+        Position::Segment(_) => None,
+        Position::Gap(gap) => Some(gap),
+    }
+}
+
+fn move_next<K: Clone + Ord, V>(map: &BTreeMap<Key<K>, V>, position: &mut Position<K>) -> bool {
+    let next = match position {
+        Position::Segment(key) => {
+            if matches!(key.0.end.0, Unbounded) {
+                return false;
+            }
+            match next_segment(map, &key.0.start) {
+                Some((next, _)) if next.0.touches(&key.0) => Position::Segment(next.clone()),
+                next => Position::Gap(gap_between(Some(&key.0), next.map(|(next, _)| &next.0))),
+            }
+        }
+        Position::Gap(gap) => match next_segment(map, &gap.start) {
+            Some((next, _)) => Position::Segment(next.clone()),
+            None => return false,
+        },
+    };
+    *position = next;
+    true
+}
+
+fn move_prev<K: Clone + Ord, V>(map: &BTreeMap<Key<K>, V>, position: &mut Position<K>) -> bool {
+    let prev = match position {
+        Position::Segment(key) => {
+            if matches!(key.0.start.0, Unbounded) {
+                return false;
+            }
+            match prev_segment(map, &key.0.start) {
+                Some((prev, _)) if prev.0.touches(&key.0) => Position::Segment(prev.clone()),
+                prev => Position::Gap(gap_between(prev.map(|(prev, _)| &prev.0), Some(&key.0))),
+            }
+        }
+        Position::Gap(gap) => match prev_segment(map, &gap.start) {
+            Some((prev, _)) => Position::Segment(prev.clone()),
+            None => return false,
+        },
+    };
+    *position = prev;
+    true
+}
+
+fn move_next_segment<K: Clone + Ord, V>(
+    map: &BTreeMap<Key<K>, V>,
+    position: &mut Position<K>,
+) -> bool {
+    let start = match position {
+        Position::Segment(key) => &key.0.start,
+        Position::Gap(gap) => &gap.start,
+    };
+    match next_segment(map, start) {
+        Some((next, _)) => {
+            *position = Position::Segment(next.clone());
+            true
+        }
+        None => false,
+    }
+}
+
+fn move_prev_segment<K: Clone + Ord, V>(
+    map: &BTreeMap<Key<K>, V>,
+    position: &mut Position<K>,
+) -> bool {
+    let start = match position {
+        Position::Segment(key) => &key.0.start,
+        Position::Gap(gap) => &gap.start,
+    };
+    match prev_segment(map, start) {
+        Some((prev, _)) => {
+            *position = Position::Segment(prev.clone());
+            true
+        }
+        None => false,
+    }
+}
+
+impl<K: Debug, V> Debug for Cursor<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = match &self.place {
+            Place::Segment(key, _) => Position::Segment(Key(key.0.as_ref())),
+            Place::Gap(gap) => Position::Gap(gap.as_ref()),
+        };
+        f.debug_struct("Cursor")
+            .field("position", &position)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K: Debug, V> Debug for CursorMut<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CursorMut")
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,258 @@
+use alloc::vec::Vec;
+use core::{fmt, iter::FusedIterator};
+
+use super::iterators::Iter;
+use crate::{Segment, SegmentMap};
+
+/// A single difference between two versions of a [`SegmentMap`], covering
+/// `range`
+///
+/// See [`SegmentMap::diff`] and [`SegmentMap::apply_patch`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Change<K, V> {
+    /// `range` was empty and now holds `new`
+    Added { range: Segment<K>, new: V },
+
+    /// `range` held `old` and is now empty
+    Removed { range: Segment<K>, old: V },
+
+    /// `range` held `old` and now holds `new`
+    Changed { range: Segment<K>, old: V, new: V },
+}
+
+impl<K, V> Change<K, V> {
+    /// The range this change applies to
+    pub fn range(&self) -> &Segment<K> {
+        match self {
+            Change::Added { range, .. }
+            | Change::Removed { range, .. }
+            | Change::Changed { range, .. } => range,
+        }
+    }
+
+    /// The value held in the old version, if any
+    pub fn old_value(&self) -> Option<&V> {
+        match self {
+            Change::Added { .. } => None,
+            Change::Removed { old, .. } | Change::Changed { old, .. } => Some(old),
+        }
+    }
+
+    /// The value held in the new version, if any
+    pub fn new_value(&self) -> Option<&V> {
+        match self {
+            Change::Removed { .. } => None,
+            Change::Added { new, .. } | Change::Changed { new, .. } => Some(new),
+        }
+    }
+}
+
+impl<K: Clone, V: Clone> Change<&K, &V> {
+    /// Clone the borrowed range and values into an owned `Change`
+    pub fn cloned(&self) -> Change<K, V> {
+        match self {
+            Change::Added { range, new } => Change::Added {
+                range: range.cloned(),
+                new: (*new).clone(),
+            },
+            Change::Removed { range, old } => Change::Removed {
+                range: range.cloned(),
+                old: (*old).clone(),
+            },
+            Change::Changed { range, old, new } => Change::Changed {
+                range: range.cloned(),
+                old: (*old).clone(),
+                new: (*new).clone(),
+            },
+        }
+    }
+}
+
+impl<K, V> SegmentMap<K, V> {
+    /// Gets an iterator over the differences between `self` (the old version)
+    /// and `new`.
+    ///
+    /// Both maps are walked once, side by side, and each change is clipped to
+    /// the range where it applies. Ranges with the same value in both maps are
+    /// skipped.
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::diff`] to collect owned changes
+    ///
+    pub fn iter_diff<'a>(&'a self, new: &'a Self) -> Diff<'a, K, V> {
+        Diff {
+            iter_old: self.iter(),
+            prev_old: None,
+            iter_new: new.iter(),
+            prev_new: None,
+        }
+    }
+
+    /// Get the differences between `self` (the old version) and `new`, in
+    /// order.
+    ///
+    /// Applying these to `self` with [`SegmentMap::apply_patch`] reproduces
+    /// `new` exactly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut old = SegmentMap::new();
+    /// old.set(0..10, "a");
+    /// old.set(20..30, "b");
+    ///
+    /// let mut new = old.clone();
+    /// new.set(5..25, "c");
+    /// new.clear_range(28..);
+    ///
+    /// assert_eq!(
+    ///     SegmentMap::diff(&old, &new),
+    ///     vec![
+    ///         Change::Changed { range: Segment::from(5..10), old: "a", new: "c" },
+    ///         Change::Added { range: Segment::from(10..20), new: "c" },
+    ///         Change::Changed { range: Segment::from(20..25), old: "b", new: "c" },
+    ///         Change::Removed { range: Segment::from(28..30), old: "b" },
+    ///     ]
+    /// );
+    /// ```
+    pub fn diff(&self, new: &Self) -> Vec<Change<K, V>>
+    where
+        K: Clone + Ord,
+        V: Clone + PartialEq,
+    {
+        self.iter_diff(new).map(|change| change.cloned()).collect()
+    }
+
+    /// Apply changes produced by [`SegmentMap::diff`] to this map.
+    ///
+    /// If `self` is the old version the changes were computed from, this
+    /// reproduces the new version exactly, including coalescing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut old = SegmentMap::new();
+    /// old.set(0..10, "a");
+    /// old.set(20..30, "b");
+    ///
+    /// let mut new = old.clone();
+    /// new.set(10..20, "a");
+    /// new.clear_range(25..);
+    ///
+    /// let patch = old.diff(&new);
+    /// old.apply_patch(&patch);
+    /// assert_eq!(old, new);
+    /// ```
+    pub fn apply_patch(&mut self, changes: &[Change<K, V>])
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        for change in changes {
+            match change {
+                Change::Removed { range, .. } => self.clear_range(range),
+                Change::Added { range, new } | Change::Changed { range, new, .. } => {
+                    self.set(range, new.clone())
+                }
+            }
+        }
+    }
+}
+
+/// An iterator over the differences between two [`SegmentMap`]s
+///
+/// See [`SegmentMap::iter_diff`]
+pub struct Diff<'a, K, V> {
+    iter_old: Iter<'a, K, V>,
+    prev_old: Option<(Segment<&'a K>, &'a V)>,
+
+    iter_new: Iter<'a, K, V>,
+    prev_new: Option<(Segment<&'a K>, &'a V)>,
+}
+
+impl<'a, K: Ord, V: PartialEq> Iterator for Diff<'a, K, V> {
+    type Item = Change<&'a K, &'a V>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let old = self
+                .prev_old
+                .take()
+                .or_else(|| self.iter_old.next().map(|(r, v)| (r.as_ref(), v)));
+            let new = self
+                .prev_new
+                .take()
+                .or_else(|| self.iter_new.next().map(|(r, v)| (r.as_ref(), v)));
+
+            let ((mut old_range, old), (mut new_range, new)) = match (old, new) {
+                (None, None) => return None,
+                (Some((range, old)), None) => return Some(Change::Removed { range, old }),
+                (None, Some((range, new))) => return Some(Change::Added { range, new }),
+                (Some(old), Some(new)) => (old, new),
+            };
+
+            // Whichever range starts first is unmatched up to the start of
+            // the other
+            if old_range.start < new_range.start {
+                self.prev_new = Some((new_range, new));
+                if old_range.end.cmp_start(&new_range.start).is_lt() {
+                    return Some(Change::Removed {
+                        range: old_range,
+                        old,
+                    });
+                }
+                let mut before = old_range;
+                before.end = new_range.start.borrow_before().unwrap(); // Not unbounded, since greater than `old_range.start`
+                old_range.start = new_range.start;
+                self.prev_old = Some((old_range, old));
+                return Some(Change::Removed { range: before, old });
+            }
+            if new_range.start < old_range.start {
+                self.prev_old = Some((old_range, old));
+                if new_range.end.cmp_start(&old_range.start).is_lt() {
+                    return Some(Change::Added {
+                        range: new_range,
+                        new,
+                    });
+                }
+                let mut before = new_range;
+                before.end = old_range.start.borrow_before().unwrap(); // Same as above
+                new_range.start = old_range.start;
+                self.prev_new = Some((new_range, new));
+                return Some(Change::Added { range: before, new });
+            }
+
+            // Both start together. Compare up to whichever ends first, and
+            // keep the rest of the other.
+            let mut range = old_range;
+            match old_range.end.cmp(&new_range.end) {
+                core::cmp::Ordering::Less => {
+                    new_range.start = old_range.end.borrow_after().unwrap(); // Not unbounded, since less than `new_range.end`
+                    self.prev_new = Some((new_range, new));
+                }
+                core::cmp::Ordering::Greater => {
+                    range.end = new_range.end;
+                    old_range.start = new_range.end.borrow_after().unwrap(); // Same as above
+                    self.prev_old = Some((old_range, old));
+                }
+                core::cmp::Ordering::Equal => {}
+            }
+            if old != new {
+                return Some(Change::Changed { range, old, new });
+            }
+        }
+    }
+}
+
+impl<K: Ord, V: PartialEq> FusedIterator for Diff<'_, K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Diff<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Diff")
+            .field("prev_old", &self.prev_old)
+            .field("prev_new", &self.prev_new)
+            .finish_non_exhaustive()
+    }
+}
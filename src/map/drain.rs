@@ -0,0 +1,242 @@
+use core::{
+    fmt::{self, Debug},
+    iter::FusedIterator,
+    ops::{Bound::*, RangeBounds},
+};
+
+use super::Key;
+use crate::{
+    segment::{Segment, Start},
+    SegmentMap,
+};
+
+impl<K, V> SegmentMap<K, V> {
+    /// Remove all values in a given range, returning an iterator over the
+    /// removed ranges (truncated to `range`) and their values.
+    ///
+    /// Unlike [`SegmentMap::remove`], this doesn't build a new map. Segments
+    /// are removed as the iterator advances, and any that haven't been
+    /// reached are removed when it is dropped. Ranges overlapping the bounds
+    /// of `range` are split, like [`SegmentMap::clear_range`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(10..20, "b");
+    ///
+    /// assert!(map.drain_range(5..15).eq(vec![
+    ///     (Segment::from(5..10), "a"),
+    ///     (Segment::from(10..15), "b"),
+    /// ]));
+    /// assert!(map.iter().eq(vec![
+    ///     (&Segment::from(0..5), &"a"),
+    ///     (&Segment::from(15..20), &"b"),
+    /// ]));
+    /// ```
+    pub fn drain_range<R>(&mut self, range: R) -> DrainRange<'_, K, V>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone,
+    {
+        DrainRange {
+            map: self,
+            remaining: Some(Segment::from(&range)),
+        }
+    }
+
+    /// Create an iterator that removes and yields the ranges for which `pred`
+    /// returns `true`.
+    ///
+    /// Whole ranges are removed, in order. Ranges for which `pred` returns
+    /// `false` are kept, as are any the iterator hasn't reached when it is
+    /// dropped. Like [`SegmentMap::retain`], `pred` may change the values it
+    /// keeps.
+    ///
+    /// Each step looks up the next range in the map, so this costs
+    /// `O(log n)` per range visited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..5, 1);
+    /// map.set(5..10, 2);
+    /// map.set(10..15, 3);
+    ///
+    /// let odd: Vec<_> = map.extract_if(|_, v| *v % 2 == 1).collect();
+    /// assert_eq!(odd, vec![(Segment::from(0..5), 1), (Segment::from(10..15), 3)]);
+    /// assert!(map.iter().eq(vec![(&Segment::from(5..10), &2)]));
+    /// ```
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, K, V, F>
+    where
+        K: Clone + Ord,
+        F: FnMut(&Segment<K>, &mut V) -> bool,
+    {
+        ExtractIf {
+            map: self,
+            last: None,
+            pred,
+        }
+    }
+}
+
+/// A draining iterator over a range of a `SegmentMap`.
+///
+/// This `struct` is created by the [`drain_range`] method on [`SegmentMap`].
+/// See its documentation for more.
+///
+/// [`drain_range`]: SegmentMap::drain_range
+pub struct DrainRange<'a, K: Clone + Ord, V: Clone> {
+    map: &'a mut SegmentMap<K, V>,
+
+    // The part of the drained range after everything yielded so far
+    remaining: Option<Segment<K>>,
+}
+
+impl<K: Clone + Ord, V: Clone> Iterator for DrainRange<'_, K, V> {
+    type Item = (Segment<K>, V);
+    fn next(&mut self) -> Option<(Segment<K>, V)> {
+        let range = self.remaining.as_ref()?;
+
+        // Only the first range can start before the remaining range, but
+        // checking for it each time is cheap enough
+        let key = match self
+            .map
+            .map
+            .range::<Start<K>, _>(..=&range.start)
+            .next_back()
+        {
+            Some((key, _)) if key.0.overlaps(range) => Some(key.clone()),
+            _ => self
+                .map
+                .map
+                .range::<Start<K>, _>(&range.start..)
+                .next()
+                .filter(|(key, _)| key.0.overlaps(range))
+                .map(|(key, _)| key.clone()),
+        };
+        let key = match key {
+            Some(key) => key,
+            None => {
+                self.remaining = None;
+                return None;
+            }
+        };
+        let (Key(segment), value) = self.map.map.remove_entry(&key).unwrap();
+
+        // Put back any parts outside of the range
+        if segment.start < range.start {
+            let before = Segment {
+                start: segment.start.clone(),
+                end: range.bound_before().unwrap().cloned(),
+            };
+            self.map.map.insert(Key(before), value.clone());
+        }
+        if segment.end > range.end {
+            let after = Segment {
+                start: range.bound_after().unwrap().cloned(),
+                end: segment.end.clone(),
+            };
+            self.map.map.insert(Key(after), value.clone());
+        }
+
+        let drained = segment.intersection(range).unwrap();
+        self.remaining = if drained.end < range.end {
+            Some(Segment {
+                start: drained.bound_after().unwrap().cloned(),
+                end: range.end.clone(),
+            })
+        } else {
+            None
+        };
+        Some((drained, value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.remaining.is_some() {
+            (0, Some(self.map.len()))
+        } else {
+            (0, Some(0))
+        }
+    }
+}
+impl<K: Clone + Ord, V: Clone> FusedIterator for DrainRange<'_, K, V> {}
+
+impl<K: Clone + Ord, V: Clone> Drop for DrainRange<'_, K, V> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
+impl<K: Clone + Ord + Debug, V: Clone> Debug for DrainRange<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DrainRange")
+            .field("remaining", &self.remaining)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An iterator that removes the ranges of a `SegmentMap` matching a
+/// predicate.
+///
+/// This `struct` is created by the [`extract_if`] method on [`SegmentMap`].
+/// See its documentation for more.
+///
+/// [`extract_if`]: SegmentMap::extract_if
+pub struct ExtractIf<'a, K, V, F> {
+    map: &'a mut SegmentMap<K, V>,
+
+    // The start of the last range visited
+    last: Option<Start<K>>,
+    pred: F,
+}
+
+impl<K, V, F> Iterator for ExtractIf<'_, K, V, F>
+where
+    K: Clone + Ord,
+    F: FnMut(&Segment<K>, &mut V) -> bool,
+{
+    type Item = (Segment<K>, V);
+    fn next(&mut self) -> Option<(Segment<K>, V)> {
+        loop {
+            let (key, value) = match &self.last {
+                Some(last) => self
+                    .map
+                    .map
+                    .range_mut::<Start<K>, _>((Excluded(last), Unbounded))
+                    .next()?,
+                None => self.map.map.iter_mut().next()?,
+            };
+            self.last = Some(key.0.start.clone());
+            if (self.pred)(&key.0, value) {
+                let key = key.clone();
+                return self
+                    .map
+                    .map
+                    .remove_entry(&key)
+                    .map(|(Key(range), value)| (range, value));
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.map.len()))
+    }
+}
+impl<K, V, F> FusedIterator for ExtractIf<'_, K, V, F>
+where
+    K: Clone + Ord,
+    F: FnMut(&Segment<K>, &mut V) -> bool,
+{
+}
+
+impl<K: Debug, V, F> Debug for ExtractIf<'_, K, V, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtractIf")
+            .field("last", &self.last.as_ref().map(|start| &start.0))
+            .finish_non_exhaustive()
+    }
+}
@@ -4,7 +4,7 @@ use core::{
     ops::Bound::*,
 };
 
-use alloc::vec::Vec;
+use alloc::{collections::BTreeMap, vec::Vec};
 
 use super::Key;
 use crate::{
@@ -189,33 +189,72 @@ impl<K, V> SegmentMap<K, V> {
 
     // fn range_bounds(&self) -> R?
 
-    // TODO: remove K: Clone?
+    /// Gets an iterator over the parts of the ranges in the map that are
+    /// inside `range`, truncating any that overlap its bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(10..20, "b");
+    /// map.set(30..40, "c");
+    ///
+    /// assert!(map.iter_subset(5..35).rev().eq(vec![
+    ///     (Segment::from(30..35), &"c"),
+    ///     (Segment::from(10..20), &"b"),
+    ///     (Segment::from(5..10), &"a"),
+    /// ]));
+    /// ```
+    // Needs `K: Clone`, since items are clipped to `range` (so are owned)
     pub fn iter_subset<R>(&self, range: R) -> IterSubset<'_, K, V>
     where
         R: RangeBounds<K>,
         K: Clone + Ord,
     {
-        let range = Segment::new(range.start_bound(), range.end_bound()).cloned();
-        IterSubset(Some(match (&range.start, &range.end) {
-            (Start(Unbounded), End(Unbounded)) => IterSubsetInner::Full(self.iter()),
-            (Start(Unbounded), bounded_end) => IterSubsetInner::Partial {
-                before: None,
-                iter: self.map.range(..bounded_end.after().unwrap().cloned()),
-                range,
-            },
-            (bounded_start, End(Unbounded)) => IterSubsetInner::Partial {
-                before: Some(self.map.range(..bounded_start.clone())),
-                iter: self.map.range(bounded_start.clone()..),
-                range,
-            },
-            (bounded_start, bounded_end) => IterSubsetInner::Partial {
-                before: Some(self.map.range(..bounded_start.clone())),
-                iter: self
-                    .map
-                    .range(bounded_start.clone()..bounded_end.after().unwrap().cloned()),
-                range,
-            },
-        }))
+        let range = Segment::from(&range);
+        IterSubset {
+            iter: self.overlapping(&range),
+            range,
+        }
+    }
+
+    /// Like [`SegmentMap::iter_subset`] over `clip`, but only including the
+    /// ranges that overlap `range` (which must be inside `clip`), or nothing
+    /// if there is no `range`
+    pub(crate) fn iter_subset_in(
+        &self,
+        range: Option<&Segment<K>>,
+        clip: Segment<K>,
+    ) -> IterSubset<'_, K, V>
+    where
+        K: Clone + Ord,
+    {
+        let iter = match range {
+            Some(range) => self.overlapping(range),
+            None => self
+                .map
+                .range((Included(clip.start.clone()), Excluded(clip.start.clone()))),
+        };
+        IterSubset { iter, range: clip }
+    }
+
+    /// The stored ranges overlapping `range`
+    fn overlapping(&self, range: &Segment<K>) -> alloc::collections::btree_map::Range<'_, Key<K>, V>
+    where
+        K: Clone + Ord,
+    {
+        // Start from the range overlapping the start of `range`, if any
+        let start = match self.map.range::<Start<K>, _>(..=&range.start).next_back() {
+            Some((key, _)) if key.0.overlaps(range) => key.0.start.clone(),
+            _ => range.start.clone(),
+        };
+        let end = match range.end.after() {
+            Some(after) => Excluded(after.cloned()),
+            None => Unbounded,
+        };
+        self.map.range((Included(start), end))
     }
 
     /// Create a `SegmentMap` referencing a subset range in `self`
@@ -230,11 +269,53 @@ impl<K, V> SegmentMap<K, V> {
         }
     }
 
+    /// Gets an iterator over everything not covered by the map, including
+    /// the regions before the first range and after the last range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(20..30, "b");
+    ///
+    /// assert!(map.iter_complement().eq(vec![
+    ///     Segment::from(..&0),
+    ///     Segment::from(&10..&20),
+    ///     Segment::from(&30..),
+    /// ]));
+    /// assert_eq!(map.iter_complement().next_back(), Some(Segment::from(&30..)));
+    /// ```
     pub fn iter_complement(&self) -> IterComplement<'_, K, V> {
-        IterComplement(Some(ComplementInner::Before {
-            first: self.ranges().next(),
-            iter: self.iter(),
-        }))
+        let mut ranges = self.ranges();
+        let (head, tail) = match (ranges.next(), ranges.next_back()) {
+            (None, _) => (
+                Some(Segment {
+                    start: Start(Unbounded),
+                    end: End(Unbounded),
+                }),
+                None,
+            ),
+            (Some(first), last) => {
+                let last = last.unwrap_or(first);
+                (
+                    first.bound_before().map(|end| Segment {
+                        start: Start(Unbounded),
+                        end,
+                    }),
+                    last.bound_after().map(|start| Segment {
+                        start,
+                        end: End(Unbounded),
+                    }),
+                )
+            }
+        };
+        IterComplement {
+            head,
+            gaps: self.iter_gaps(),
+            tail,
+        }
     }
 
     pub fn complement(&self) -> SegmentSet<&K>
@@ -253,11 +334,14 @@ impl<K, V> SegmentMap<K, V> {
     ///
     /// NOTE: Empty regions before and after those stored in this map (i.e.
     /// before the first range and after the last range) will not be included
-    /// in this iterator
+    /// in this iterator. Ranges with different values can touch, and there is
+    /// no gap between those.
     pub fn iter_gaps(&self) -> Gaps<'_, K, V> {
+        let mut iter = self.iter();
         Gaps {
-            iter: self.iter(),
-            prev: None,
+            front: iter.next().map(|(range, _)| range),
+            back: iter.next_back().map(|(range, _)| range),
+            iter,
         }
     }
 
@@ -273,6 +357,89 @@ impl<K, V> SegmentMap<K, V> {
         }
     }
 
+    /// Get all ranges in the map that hold `value`.
+    ///
+    /// This scans the whole map. If you need to do this often, consider
+    /// [`IndexedSegmentMap`](crate::IndexedSegmentMap), which keeps these sets
+    /// up to date as the map changes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut roster = SegmentMap::new();
+    /// roster.set(0..7, "Alice");
+    /// roster.set(7..14, "Bob");
+    /// roster.set(14..21, "Alice");
+    ///
+    /// assert!(roster.ranges_for(&"Alice").iter().eq(vec![
+    ///     &Segment::from(&0..&7),
+    ///     &Segment::from(&14..&21),
+    /// ]));
+    /// assert!(roster.ranges_for(&"Carol").is_empty());
+    /// ```
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::group_by_value`] to get the ranges for every value at
+    ///   once
+    ///
+    pub fn ranges_for(&self, value: &V) -> SegmentSet<&K>
+    where
+        K: Ord,
+        V: PartialEq,
+    {
+        SegmentSet {
+            map: SegmentMap {
+                map: self
+                    .iter()
+                    .filter(|(_, v)| *v == value)
+                    .map(|(r, _)| (Key(r.as_ref()), ()))
+                    .collect(),
+                store: alloc::vec::Vec::new(),
+            },
+        }
+    }
+
+    /// Group the ranges in the map by value, in a single pass.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut roster = SegmentMap::new();
+    /// roster.set(0..7, "Alice");
+    /// roster.set(7..14, "Bob");
+    /// roster.set(14..21, "Alice");
+    ///
+    /// let groups = roster.group_by_value();
+    /// assert_eq!(groups.len(), 2);
+    /// assert_eq!(groups[&"Alice"].len(), 2);
+    /// assert!(groups[&"Bob"].iter().eq(vec![&Segment::from(&7..&14)]));
+    /// ```
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::ranges_for`] to get the ranges for a single value
+    ///
+    pub fn group_by_value(&self) -> BTreeMap<&V, SegmentSet<&K>>
+    where
+        K: Ord,
+        V: Ord,
+    {
+        let mut groups: BTreeMap<&V, SegmentSet<&K>> = BTreeMap::new();
+        for (range, value) in self.iter() {
+            // Ranges come in order, so they can go directly into each set
+            groups
+                .entry(value)
+                .or_default()
+                .map
+                .map
+                .insert(Key(range.as_ref()), ());
+        }
+        groups
+    }
+
     // /// Gets an iterator over all maximally-sized gaps between ranges in the map,
     // /// further bounded by an outer range
     // ///
@@ -319,7 +486,7 @@ impl<R: core::ops::RangeBounds<K>, K: Clone + Ord, V: Clone + Eq> FromIterator<(
 {
     fn from_iter<T: IntoIterator<Item = (R, V)>>(iter: T) -> Self {
         let mut map = Self::new();
-        map.extend(iter);
+        map.set_many(iter);
         map
     }
 }
@@ -679,184 +846,206 @@ impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {
 }
 impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
 
-pub struct IterSubset<'a, K, V>(Option<IterSubsetInner<'a, K, V>>);
-
-enum IterSubsetInner<'a, K, V> {
-    Full(Iter<'a, K, V>),
+/// An iterator over the entries of a `SegmentMap` inside a given range,
+/// truncated to that range.
+///
+/// This `struct` is created by the [`iter_subset`] method on [`SegmentMap`].
+/// See its documentation for more.
+///
+/// [`iter_subset`]: SegmentMap::iter_subset
+pub struct IterSubset<'a, K, V> {
+    // Every range here overlaps `range`
+    iter: alloc::collections::btree_map::Range<'a, Key<K>, V>,
+    range: Segment<K>,
+}
 
-    Partial {
-        before: Option<alloc::collections::btree_map::Range<'a, Key<K>, V>>,
-        iter: alloc::collections::btree_map::Range<'a, Key<K>, V>,
-        range: Segment<K>,
-    },
+impl<K: Clone + Ord + Debug, V: Debug> Debug for IterSubset<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
 }
 
 impl<'a, K: Clone + Ord, V> Iterator for IterSubset<'a, K, V> {
     type Item = (Segment<K>, &'a V);
     fn next(&mut self) -> Option<Self::Item> {
-        match self.0.take()? {
-            IterSubsetInner::Full(mut iter) => {
-                let next = iter.next().map(|(r, v)| (r.clone(), v));
-                if next.is_some() {
-                    self.0.insert(IterSubsetInner::Full(iter));
-                }
-                next
-            }
-            IterSubsetInner::Partial {
-                mut before,
-                mut iter,
-                range,
-            } => {
-                // Check the first previous range from start to see if it
-                // overlaps the given outer range, consuming `before` as there
-                // will only be 1 options there
-                if let Some((Key(r), v)) = before.take().map(|mut x| x.next_back()).flatten() {
-                    let mut r = r.clone();
-
-                    // Check if this overlaps the outer range
-                    // (range iterator means this must start before range start)
-                    if r.end.cmp_start(&range.start).is_gt() {
-                        if r.start < range.start {
-                            r.start = range.start.clone();
-                        };
-
-                        self.0.insert(IterSubsetInner::Partial {
-                            before: None,
-                            iter,
-                            range,
-                        });
-                        return Some((r, v));
-                    }
-                }
-
-                // Otherwise, continue marching through `iter` until we reach
-                // `range.end`
-                let (Key(r), v) = iter.next()?;
-                let mut r = r.clone();
-
-                if r.start.as_ref() > range.end.after().unwrap() {
-                    // Finished!
-                    None
-                } else {
-                    if r.end > range.end {
-                        // If this extends past the end, it must be our last item
-                        r.end = range.end;
-                    } else {
-                        // Otherwise, save everything for next iteration
-                        self.0.insert(IterSubsetInner::Partial {
-                            before: None,
-                            iter,
-                            range,
-                        });
-                    }
-
-                    Some((r, v))
-                }
-            }
+        let (key, value) = self.iter.next()?;
+        Some((key.0.intersection(&self.range).unwrap(), value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+impl<K: Clone + Ord, V> DoubleEndedIterator for IterSubset<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.iter.next_back()?;
+        Some((key.0.intersection(&self.range).unwrap(), value))
+    }
+}
+impl<K: Clone + Ord, V> FusedIterator for IterSubset<'_, K, V> {}
+impl<K: Clone, V> Clone for IterSubset<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            iter: self.iter.clone(),
+            range: self.range.clone(),
         }
     }
 }
 
+/// An iterator over the gaps between the ranges of a `SegmentMap`.
+///
+/// This `struct` is created by the [`iter_gaps`] method on [`SegmentMap`].
+/// See its documentation for more.
+///
+/// [`iter_gaps`]: SegmentMap::iter_gaps
 pub struct Gaps<'a, K, V> {
+    // The ranges on either side of the gaps left to iterate. If there's only
+    // one range left, it's always in `front`.
+    front: Option<&'a Segment<K>>,
     iter: Iter<'a, K, V>,
-    prev: Option<Segment<&'a K>>,
+    back: Option<&'a Segment<K>>,
 }
 
-impl<'a, K, V> Iterator for Gaps<'a, K, V>
-where
-    K: Ord,
-{
-    type Item = Segment<&'a K>;
-    fn next(&mut self) -> Option<Self::Item> {
-        let next = self.iter.next()?.0.as_ref();
-
-        if let Some(prev) = self.prev.take() {
-            // Get the adjacent bound to the end of the previous range
-
-            let start = prev.bound_after()?.cloned(); // If none, no more gaps (this extends forwards to infinity)
-            let end = next
+impl<'a, K: Ord, V> Gaps<'a, K, V> {
+    /// The gap between two ranges, or `None` if they touch
+    fn gap(before: &'a Segment<K>, after: &'a Segment<K>) -> Option<Segment<&'a K>> {
+        let gap = Segment {
+            start: before
+                .bound_after()
+                .expect("Unbounded internal range in SegmentMap"),
+            end: after
                 .bound_before()
-                .expect("Unbounded internal range in SegmentMap")
-                .cloned();
-            self.prev.insert(next);
-            Some(Segment { start, end })
+                .expect("Unbounded internal range in SegmentMap"),
+        };
+        if gap.end.cmp_start(&gap.start).is_lt() {
+            None
         } else {
-            // No previous bound means first gap
-
-            // Get the adjacent bound to the end of the first range
-            // If none, no more gaps (this extends forwards to infinity)
-            let start = next.borrow_bound_after()?;
+            Some(gap)
+        }
+    }
+}
 
-            // Check if we have another range, otherwise only one item (no gaps)
-            let next = self.iter.next()?.0.as_ref();
+impl<K, V> Gaps<'_, K, V> {
+    /// The number of pairs of ranges left, which is the number of gaps left
+    /// unless some of them touch (they never do in a `SegmentSet`)
+    pub(crate) fn pairs(&self) -> usize {
+        match (self.front, self.back) {
+            (Some(_), Some(_)) => self.iter.len() + 1,
+            _ => 0,
+        }
+    }
+}
 
-            // Store the end of the next segment for next iteration
-            // This bound should always exist, because this is not the first
-            // range
-            let end = next.borrow_bound_before().unwrap();
+impl<K: Debug + Ord, V> Debug for Gaps<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
 
-            // Hold on to next
-            self.prev = Some(next);
-            Some(Segment { start, end })
+impl<'a, K: Ord, V> Iterator for Gaps<'a, K, V> {
+    type Item = Segment<&'a K>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let before = self.front?;
+            let after = match self.iter.next() {
+                Some((after, _)) => after,
+                None => self.back.take()?,
+            };
+            self.front = Some(after);
+            if let Some(gap) = Self::gap(before, after) {
+                return Some(gap);
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.pairs()))
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+impl<K: Ord, V> DoubleEndedIterator for Gaps<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            let after = self.back?;
+            self.back = self.iter.next_back().map(|(before, _)| before);
+            let before = self.back.or(self.front)?;
+            if let Some(gap) = Self::gap(before, after) {
+                return Some(gap);
+            }
         }
     }
 }
-
 impl<K: Ord, V> FusedIterator for Gaps<'_, K, V> {}
+impl<K, V> Clone for Gaps<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            front: self.front,
+            iter: self.iter.clone(),
+            back: self.back,
+        }
+    }
+}
 
-pub struct IterComplement<'a, K, V>(Option<ComplementInner<'a, K, V>>);
-enum ComplementInner<'a, K, V> {
-    Before {
-        first: Option<&'a Segment<K>>,
-        iter: Iter<'a, K, V>,
-    },
-    Gaps(Gaps<'a, K, V>), // TODO: make gaps generic over iterator? Then Before can use Peekable
+/// An iterator over everything not covered by a `SegmentMap`.
+///
+/// This `struct` is created by the [`iter_complement`] method on
+/// [`SegmentMap`]. See its documentation for more.
+///
+/// [`iter_complement`]: SegmentMap::iter_complement
+pub struct IterComplement<'a, K, V> {
+    // The gaps before the first range and after the last
+    head: Option<Segment<&'a K>>,
+    gaps: Gaps<'a, K, V>,
+    tail: Option<Segment<&'a K>>,
 }
 
-impl<'a, K, V> Iterator for IterComplement<'a, K, V>
-where
-    K: Ord,
-{
+impl<K, V> IterComplement<'_, K, V> {
+    /// The number of items left, if no ranges touch (see [`Gaps::pairs`])
+    pub(crate) fn max_len(&self) -> usize {
+        self.head.is_some() as usize + self.gaps.pairs() + self.tail.is_some() as usize
+    }
+}
+
+impl<K: Debug + Ord, V> Debug for IterComplement<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for IterComplement<'a, K, V> {
     type Item = Segment<&'a K>;
     fn next(&mut self) -> Option<Self::Item> {
-        match self.0.take()? {
-            ComplementInner::Before { first, iter } => {
-                if let Some(first) = first {
-                    let mut gaps = Gaps { iter, prev: None };
-                    let out = first
-                        .bound_before()
-                        .map(|end| Segment {
-                            start: Start(Unbounded),
-                            end,
-                        })
-                        .or_else(|| gaps.next());
-
-                    self.0.insert(ComplementInner::Gaps(gaps));
-                    out
-                } else {
-                    None
-                }
-            }
-
-            // Use Gaps iterator to iterate all inner gaps
-            ComplementInner::Gaps(mut gaps) => {
-                if let Some(next) = gaps.next() {
-                    // In the gaps iterator
-                    self.0.insert(ComplementInner::Gaps(gaps));
-                    Some(next)
-                } else {
-                    // After the last item in gaps, try to use the `prev`
-                    // element to get the end bound, otherwise no more gaps!
-                    gaps.prev
-                        .map(|p| {
-                            p.borrow_bound_after().map(|start| Segment {
-                                start,
-                                end: End(Unbounded),
-                            })
-                        })
-                        .flatten()
-                }
-            }
+        self.head
+            .take()
+            .or_else(|| self.gaps.next())
+            .or_else(|| self.tail.take())
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let fixed = self.head.is_some() as usize + self.tail.is_some() as usize;
+        (fixed, Some(self.max_len()))
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+impl<K: Ord, V> DoubleEndedIterator for IterComplement<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.tail
+            .take()
+            .or_else(|| self.gaps.next_back())
+            .or_else(|| self.head.take())
+    }
+}
+impl<K: Ord, V> FusedIterator for IterComplement<'_, K, V> {}
+impl<K, V> Clone for IterComplement<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            head: self.head,
+            gaps: self.gaps.clone(),
+            tail: self.tail,
         }
     }
 }
@@ -0,0 +1,263 @@
+use core::{
+    fmt::{self, Debug},
+    iter::FusedIterator,
+};
+
+use super::iterators::Iter;
+use crate::{segment::Segment, SegmentMap};
+
+/// Key types with a distance between values, used by [`SegmentMap::within`]
+///
+/// This is implemented for all of the integer primitives.
+pub trait Measure: Ord {
+    /// The type of distances between keys
+    type Distance: Ord;
+
+    /// The distance from `self` to `other`. This should grow as the values
+    /// get further apart.
+    fn distance(&self, other: &Self) -> Self::Distance;
+}
+
+macro_rules! impl_measure {
+    ($($t:ty => $d:ty),*) => {
+        $(
+            impl Measure for $t {
+                type Distance = $d;
+                #[inline]
+                fn distance(&self, other: &Self) -> $d {
+                    self.abs_diff(*other)
+                }
+            }
+        )*
+    };
+}
+
+impl_measure!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => usize,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize
+);
+
+impl<K, V> SegmentMap<K, V> {
+    /// Gets an iterator over every pair of overlapping ranges in `self` and
+    /// `other`, with their values.
+    ///
+    /// Pairs are sorted by the range from `self`, then by the range from
+    /// `other`. Ranges are given as stored in the maps, rather than clipped to
+    /// the overlap. Both maps are walked together, so this costs
+    /// `O(n + m + k)` for `k` pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut incidents = SegmentMap::new();
+    /// incidents.set(3..5, "outage");
+    /// incidents.set(12..14, "slow");
+    ///
+    /// let mut deploys = SegmentMap::new();
+    /// deploys.set(0..4, "v1");
+    /// deploys.set(4..10, "v2");
+    /// deploys.set(10..20, "v3");
+    ///
+    /// assert!(incidents.overlap_join(&deploys).eq(vec![
+    ///     (&Segment::from(3..5), &"outage", &Segment::from(0..4), &"v1"),
+    ///     (&Segment::from(3..5), &"outage", &Segment::from(4..10), &"v2"),
+    ///     (&Segment::from(12..14), &"slow", &Segment::from(10..20), &"v3"),
+    /// ]));
+    /// ```
+    pub fn overlap_join<'a, W>(&'a self, other: &'a SegmentMap<K, W>) -> OverlapJoin<'a, K, V, W>
+    where
+        K: Ord,
+    {
+        OverlapJoin(JoinState::new(self, other))
+    }
+
+    /// Gets an iterator over every pair of ranges in `self` and `other` that
+    /// overlap, or are at most `distance` apart, with their values.
+    ///
+    /// The distance between two ranges that don't overlap is measured from
+    /// the end of the first to the start of the second, so touching ranges
+    /// are zero apart. Otherwise, this is like [`SegmentMap::overlap_join`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut reads: SegmentMap<u32, char> = SegmentMap::new();
+    /// reads.set(10..20, 'a');
+    /// reads.set(48..50, 'b');
+    ///
+    /// let mut exons = SegmentMap::new();
+    /// exons.set(0..5, 1);
+    /// exons.set(22..30, 2);
+    /// exons.set(40..45, 3);
+    ///
+    /// assert!(reads.within(&exons, 5).eq(vec![
+    ///     (&Segment::from(10..20), &'a', &Segment::from(0..5), &1),
+    ///     (&Segment::from(10..20), &'a', &Segment::from(22..30), &2),
+    ///     (&Segment::from(48..50), &'b', &Segment::from(40..45), &3),
+    /// ]));
+    /// ```
+    pub fn within<'a, W>(
+        &'a self,
+        other: &'a SegmentMap<K, W>,
+        distance: K::Distance,
+    ) -> Within<'a, K, V, W>
+    where
+        K: Measure,
+    {
+        Within {
+            state: JoinState::new(self, other),
+            distance,
+        }
+    }
+}
+
+/// The state of a merge join between two maps
+struct JoinState<'a, K, V, W> {
+    a: Iter<'a, K, V>,
+
+    // The range from `a` being matched, if any
+    current: Option<(&'a Segment<K>, &'a V)>,
+
+    // The first range in `other` that could match `current` (or any later
+    // range), and the next range to try against `current`
+    b_start: Iter<'a, K, W>,
+    b: Iter<'a, K, W>,
+}
+
+impl<'a, K, V, W> JoinState<'a, K, V, W> {
+    fn new(a: &'a SegmentMap<K, V>, b: &'a SegmentMap<K, W>) -> Self {
+        JoinState {
+            a: a.iter(),
+            current: None,
+            b_start: b.iter(),
+            b: b.iter(),
+        }
+    }
+
+    /// Find the next matching pair, where ranges from `other` between those
+    /// `behind` and `ahead` of a range from `self` are matches
+    fn next_by<B, A>(
+        &mut self,
+        behind: B,
+        ahead: A,
+    ) -> Option<(&'a Segment<K>, &'a V, &'a Segment<K>, &'a W)>
+    where
+        B: Fn(&Segment<K>, &Segment<K>) -> bool,
+        A: Fn(&Segment<K>, &Segment<K>) -> bool,
+    {
+        loop {
+            let (a, v) = match self.current {
+                Some(current) => current,
+                None => {
+                    let (a, v) = self.a.next()?;
+
+                    // Ranges behind `a` are behind everything after it too
+                    while let Some((b, _)) = self.b_start.clone().next() {
+                        if !behind(b, a) {
+                            break;
+                        }
+                        self.b_start.next();
+                    }
+                    self.b = self.b_start.clone();
+                    self.current = Some((a, v));
+                    (a, v)
+                }
+            };
+            match self.b.next() {
+                Some((b, w)) if !ahead(a, b) => return Some((a, v, b, w)),
+                _ => self.current = None,
+            }
+        }
+    }
+}
+
+impl<K, V, W> Clone for JoinState<'_, K, V, W> {
+    fn clone(&self) -> Self {
+        JoinState {
+            a: self.a.clone(),
+            current: self.current,
+            b_start: self.b_start.clone(),
+            b: self.b.clone(),
+        }
+    }
+}
+
+/// An iterator over the overlapping ranges of two `SegmentMap`s.
+///
+/// This `struct` is created by the [`overlap_join`] method on [`SegmentMap`].
+/// See its documentation for more.
+///
+/// [`overlap_join`]: SegmentMap::overlap_join
+pub struct OverlapJoin<'a, K, V, W>(JoinState<'a, K, V, W>);
+
+impl<'a, K: Ord, V, W> Iterator for OverlapJoin<'a, K, V, W> {
+    type Item = (&'a Segment<K>, &'a V, &'a Segment<K>, &'a W);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_by(
+            |b, a| b.end.cmp_start(&a.start).is_lt(),
+            |a, b| a.end.cmp_start(&b.start).is_lt(),
+        )
+    }
+}
+impl<K: Ord, V, W> FusedIterator for OverlapJoin<'_, K, V, W> {}
+impl<K, V, W> Clone for OverlapJoin<'_, K, V, W> {
+    fn clone(&self) -> Self {
+        OverlapJoin(self.0.clone())
+    }
+}
+impl<K: Ord + Debug, V: Debug, W: Debug> Debug for OverlapJoin<'_, K, V, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the nearby ranges of two `SegmentMap`s.
+///
+/// This `struct` is created by the [`within`] method on [`SegmentMap`]. See
+/// its documentation for more.
+///
+/// [`within`]: SegmentMap::within
+pub struct Within<'a, K: Measure, V, W> {
+    state: JoinState<'a, K, V, W>,
+    distance: K::Distance,
+}
+
+impl<'a, K: Measure, V, W> Iterator for Within<'a, K, V, W> {
+    type Item = (&'a Segment<K>, &'a V, &'a Segment<K>, &'a W);
+    fn next(&mut self) -> Option<Self::Item> {
+        let distance = &self.distance;
+
+        // Whether `first` ends more than `distance` before `second` starts
+        let far_before = |first: &Segment<K>, second: &Segment<K>| {
+            first.end.cmp_start(&second.start).is_lt()
+                && match (first.end.value(), second.start.value()) {
+                    (Some(end), Some(start)) => &end.distance(start) > distance,
+                    _ => false,
+                }
+        };
+        self.state.next_by(far_before, far_before)
+    }
+}
+impl<K: Measure, V, W> FusedIterator for Within<'_, K, V, W> {}
+impl<K: Measure, V, W> Clone for Within<'_, K, V, W>
+where
+    K::Distance: Clone,
+{
+    fn clone(&self) -> Self {
+        Within {
+            state: self.state.clone(),
+            distance: self.distance.clone(),
+        }
+    }
+}
+impl<K, V: Debug, W: Debug> Debug for Within<'_, K, V, W>
+where
+    K: Measure + Debug,
+    K::Distance: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
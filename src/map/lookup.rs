@@ -0,0 +1,149 @@
+use core::{
+    fmt::{self, Debug},
+    iter::{FusedIterator, Peekable},
+    ops::{Bound::*, RangeBounds},
+};
+
+use super::iterators::Iter;
+use crate::{
+    segment::{Segment, Start},
+    SegmentMap,
+};
+
+impl<K, V> SegmentMap<K, V> {
+    /// Look up each of a sorted sequence of points, yielding each point with
+    /// the range (and value) covering it, if any.
+    ///
+    /// The map is walked alongside `points`, rather than searched again for
+    /// each one, so looking up `m` points costs `O(n + m)` rather than
+    /// `O(m log n)`. Use [`LookupSorted::as_of`] to fall back to the closest
+    /// range before a point that isn't covered.
+    ///
+    /// # Panics
+    ///
+    /// The iterator panics if `points` aren't sorted. Points that are out of
+    /// order, but are still covered by (or after) the range found for the
+    /// previous point, are looked up correctly, so they aren't caught.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut tariffs = SegmentMap::new();
+    /// tariffs.set(0..10, "peak");
+    /// tariffs.set(20..30, "off-peak");
+    ///
+    /// let events = vec![5, 15, 25, 35];
+    /// assert!(tariffs.lookup_sorted(events.clone()).eq(vec![
+    ///     (5, Some((&Segment::from(0..10), &"peak"))),
+    ///     (15, None),
+    ///     (25, Some((&Segment::from(20..30), &"off-peak"))),
+    ///     (35, None),
+    /// ]));
+    ///
+    /// let as_of: Vec<_> = tariffs
+    ///     .lookup_sorted(events)
+    ///     .as_of()
+    ///     .map(|(at, found)| (at, found.map(|(_, v)| *v)))
+    ///     .collect();
+    /// assert_eq!(
+    ///     as_of,
+    ///     vec![
+    ///         (5, Some("peak")),
+    ///         (15, Some("peak")),
+    ///         (25, Some("off-peak")),
+    ///         (35, Some("off-peak")),
+    ///     ]
+    /// );
+    /// ```
+    pub fn lookup_sorted<I>(&self, points: I) -> LookupSorted<'_, K, V, I::IntoIter>
+    where
+        I: IntoIterator<Item = K>,
+        K: Ord,
+    {
+        LookupSorted {
+            ranges: self.iter().peekable(),
+            current: None,
+            points: points.into_iter(),
+            as_of: false,
+        }
+    }
+}
+
+/// An iterator looking up a sorted sequence of points in a `SegmentMap`.
+///
+/// This `struct` is created by the [`lookup_sorted`] method on [`SegmentMap`].
+/// See its documentation for more.
+///
+/// [`lookup_sorted`]: SegmentMap::lookup_sorted
+pub struct LookupSorted<'a, K, V, I> {
+    // Ranges after `current`
+    ranges: Peekable<Iter<'a, K, V>>,
+
+    // The last range starting at or before the previous point
+    current: Option<(&'a Segment<K>, &'a V)>,
+    points: I,
+    as_of: bool,
+}
+
+impl<K, V, I> LookupSorted<'_, K, V, I> {
+    /// Fall back to the closest range before a point, if no range covers it.
+    ///
+    /// Points before the first range in the map still won't find anything.
+    pub fn as_of(mut self) -> Self {
+        self.as_of = true;
+        self
+    }
+}
+
+impl<'a, K, V, I> Iterator for LookupSorted<'a, K, V, I>
+where
+    K: Ord,
+    I: Iterator<Item = K>,
+{
+    type Item = (K, Option<(&'a Segment<K>, &'a V)>);
+    fn next(&mut self) -> Option<Self::Item> {
+        let point = self.points.next()?;
+        let start = Start(Included(&point));
+
+        if let Some((range, _)) = self.current {
+            assert!(range.start.as_ref() <= start, "points must be sorted");
+        }
+        while let Some((range, _)) = self.ranges.peek() {
+            if range.start.as_ref() > start {
+                break;
+            }
+            self.current = self.ranges.next();
+        }
+
+        let found = self
+            .current
+            .filter(|(range, _)| self.as_of || range.contains(&point));
+        Some((point, found))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.points.size_hint()
+    }
+}
+impl<K: Ord, V, I: ExactSizeIterator<Item = K>> ExactSizeIterator for LookupSorted<'_, K, V, I> {}
+impl<K: Ord, V, I: FusedIterator<Item = K>> FusedIterator for LookupSorted<'_, K, V, I> {}
+
+impl<K, V, I: Clone> Clone for LookupSorted<'_, K, V, I> {
+    fn clone(&self) -> Self {
+        LookupSorted {
+            ranges: self.ranges.clone(),
+            current: self.current,
+            points: self.points.clone(),
+            as_of: self.as_of,
+        }
+    }
+}
+
+impl<K: Debug, V: Debug, I> Debug for LookupSorted<'_, K, V, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LookupSorted")
+            .field("current", &self.current)
+            .field("as_of", &self.as_of)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,360 @@
+use alloc::vec::Vec;
+use core::{fmt, iter::FusedIterator};
+
+use super::{iterators::Iter, transform::collect_coalesced, Key};
+use crate::{set::iterators::Iter as SetIter, Segment, SegmentMap, SegmentSet};
+
+impl<K, V> SegmentMap<K, V> {
+    /// Gets an iterator over the parts of the map covered by `set`.
+    ///
+    /// Both the map and the set are walked once, side by side, so this takes
+    /// linear time in the size of both.
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::restricted_to`] to collect this into a map
+    /// - [`SegmentMap::restrict_to`] to do this in place
+    ///
+    pub fn iter_restricted_to<'a>(&'a self, set: &'a SegmentSet<K>) -> RestrictedTo<'a, K, V> {
+        RestrictedTo {
+            iter: self.iter(),
+            prev: None,
+            iter_set: set.iter(),
+            prev_set: None,
+        }
+    }
+
+    /// Create a `SegmentMap` referencing the parts of `self` covered by `set`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut usage = SegmentMap::new();
+    /// usage.set(0..24, "low");
+    /// usage.set(12..14, "high");
+    ///
+    /// let mut business_hours = SegmentSet::new();
+    /// business_hours.insert(9..17);
+    ///
+    /// assert!(usage.restricted_to(&business_hours).into_iter().eq(vec![
+    ///     (Segment::from(&9..&12), &"low"),
+    ///     (Segment::from(&12..&14), &"high"),
+    ///     (Segment::from(&14..&17), &"low"),
+    /// ]));
+    /// ```
+    pub fn restricted_to<'a>(&'a self, set: &'a SegmentSet<K>) -> SegmentMap<&'a K, &'a V>
+    where
+        K: Ord,
+    {
+        // Pieces are produced in order and never overlap or touch with the
+        // same value (they would have been coalesced in `self` or `set`), so
+        // they can go straight into the map
+        SegmentMap {
+            map: self
+                .iter_restricted_to(set)
+                .map(|(r, v)| (Key(r), v))
+                .collect(),
+            store: Vec::new(),
+        }
+    }
+
+    /// Remove every part of the map not covered by `set`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut usage = SegmentMap::new();
+    /// usage.set(0..24, "low");
+    /// usage.set(12..14, "high");
+    ///
+    /// let mut business_hours = SegmentSet::new();
+    /// business_hours.insert(9..17);
+    ///
+    /// usage.restrict_to(&business_hours);
+    /// assert_eq!(usage.bounds(), Some(Segment::from(&9..&17)));
+    /// assert_eq!(usage.len(), 3);
+    /// ```
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::exclude`] to remove the parts covered by `set` instead
+    ///
+    pub fn restrict_to(&mut self, set: &SegmentSet<K>)
+    where
+        K: Clone + Ord,
+        V: Clone,
+    {
+        self.map = self
+            .iter_restricted_to(set)
+            .map(|(r, v)| (Key(r.cloned()), v.clone()))
+            .collect();
+    }
+
+    /// Gets an iterator over the parts of the map not covered by `set`.
+    ///
+    /// Like [`SegmentMap::iter_restricted_to`], this takes linear time.
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::excluding`] to collect this into a map
+    /// - [`SegmentMap::exclude`] to do this in place
+    ///
+    pub fn iter_excluding<'a>(&'a self, set: &'a SegmentSet<K>) -> Excluding<'a, K, V> {
+        Excluding {
+            iter: self.iter(),
+            prev: None,
+            iter_set: set.iter(),
+            prev_set: None,
+        }
+    }
+
+    /// Create a `SegmentMap` referencing the parts of `self` not covered by
+    /// `set`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut availability = SegmentMap::new();
+    /// availability.set(0..100, "up");
+    ///
+    /// let mut maintenance = SegmentSet::new();
+    /// maintenance.insert(20..30);
+    /// maintenance.insert(60..70);
+    ///
+    /// assert!(availability.excluding(&maintenance).into_iter().eq(vec![
+    ///     (Segment::from(&0..&20), &"up"),
+    ///     (Segment::from(&30..&60), &"up"),
+    ///     (Segment::from(&70..&100), &"up"),
+    /// ]));
+    /// ```
+    pub fn excluding<'a>(&'a self, set: &'a SegmentSet<K>) -> SegmentMap<&'a K, &'a V>
+    where
+        K: Ord,
+    {
+        SegmentMap {
+            map: self.iter_excluding(set).map(|(r, v)| (Key(r), v)).collect(),
+            store: Vec::new(),
+        }
+    }
+
+    /// Remove every part of the map covered by `set`.
+    ///
+    /// This is the same as calling [`SegmentMap::clear_range`] for each range
+    /// in `set`, but in a single pass.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut availability = SegmentMap::new();
+    /// availability.set(0..100, "up");
+    ///
+    /// let mut maintenance = SegmentSet::new();
+    /// maintenance.insert(20..30);
+    /// maintenance.insert(60..70);
+    ///
+    /// availability.exclude(&maintenance);
+    /// assert_eq!(availability.len(), 3);
+    /// assert!(availability.get(&25).is_none());
+    /// ```
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::restrict_to`] to keep only the parts covered by `set`
+    ///
+    pub fn exclude(&mut self, set: &SegmentSet<K>)
+    where
+        K: Clone + Ord,
+        V: Clone,
+    {
+        self.map = self
+            .iter_excluding(set)
+            .map(|(r, v)| (Key(r.cloned()), v.clone()))
+            .collect();
+    }
+
+    /// Split the ranges in the map into those whose value matches `pred`
+    /// and those that don't.
+    ///
+    /// Neighbouring ranges that end up on the same side are coalesced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut status = SegmentMap::new();
+    /// status.set(0..10, "running");
+    /// status.set(10..20, "degraded");
+    /// status.set(20..30, "stopped");
+    ///
+    /// let (up, down) = status.partition(|s| *s != "stopped");
+    /// assert!(up.iter().eq(vec![&Segment::from(&0..&20)]));
+    /// assert!(down.iter().eq(vec![&Segment::from(&20..&30)]));
+    /// ```
+    pub fn partition<F>(&self, mut pred: F) -> (SegmentSet<&K>, SegmentSet<&K>)
+    where
+        K: Ord,
+        F: FnMut(&V) -> bool,
+    {
+        let mut matched = Vec::new();
+        let mut unmatched = Vec::new();
+        for (range, value) in self.iter() {
+            if pred(value) {
+                matched.push((range.as_ref(), ()));
+            } else {
+                unmatched.push((range.as_ref(), ()));
+            }
+        }
+        (
+            SegmentSet {
+                map: collect_coalesced(matched.into_iter()),
+            },
+            SegmentSet {
+                map: collect_coalesced(unmatched.into_iter()),
+            },
+        )
+    }
+}
+
+/// An iterator over the parts of a [`SegmentMap`] covered by a
+/// [`SegmentSet`]
+///
+/// See [`SegmentMap::iter_restricted_to`]
+pub struct RestrictedTo<'a, K, V> {
+    iter: Iter<'a, K, V>,
+    prev: Option<(Segment<&'a K>, &'a V)>,
+
+    iter_set: SetIter<'a, K>,
+    prev_set: Option<Segment<&'a K>>,
+}
+
+impl<'a, K: Ord, V> Iterator for RestrictedTo<'a, K, V> {
+    type Item = (Segment<&'a K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Get the next values. If either ran out, we're done
+            let (range, value) = self
+                .prev
+                .take()
+                .or_else(|| self.iter.next().map(|(r, v)| (r.as_ref(), v)))?;
+            let covered = self
+                .prev_set
+                .take()
+                .or_else(|| self.iter_set.next().map(|r| r.as_ref()))?;
+
+            // If `range` is fully before `covered`, grab another range
+            if range.end.cmp_start(&covered.start).is_lt() {
+                self.prev_set = Some(covered);
+                continue;
+            }
+
+            // And likewise the other way around
+            if covered.end.cmp_start(&range.start).is_lt() {
+                self.prev = Some((range, value));
+                continue;
+            }
+
+            // Otherwise they overlap. Keep whichever extends further for the
+            // next iteration.
+            let start = core::cmp::max(range.start, covered.start);
+            let end = core::cmp::min(range.end, covered.end);
+            match range.end.cmp(&covered.end) {
+                core::cmp::Ordering::Less => self.prev_set = Some(covered),
+                core::cmp::Ordering::Greater => self.prev = Some((range, value)),
+                core::cmp::Ordering::Equal => {}
+            }
+            return Some((Segment { start, end }, value));
+        }
+    }
+}
+
+impl<K: Ord, V> FusedIterator for RestrictedTo<'_, K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for RestrictedTo<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RestrictedTo")
+            .field("prev", &self.prev)
+            .field("prev_set", &self.prev_set)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An iterator over the parts of a [`SegmentMap`] not covered by a
+/// [`SegmentSet`]
+///
+/// See [`SegmentMap::iter_excluding`]
+pub struct Excluding<'a, K, V> {
+    iter: Iter<'a, K, V>,
+    prev: Option<(Segment<&'a K>, &'a V)>,
+
+    iter_set: SetIter<'a, K>,
+    prev_set: Option<Segment<&'a K>>,
+}
+
+impl<'a, K: Ord, V> Iterator for Excluding<'a, K, V> {
+    type Item = (Segment<&'a K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (mut range, value) = self
+                .prev
+                .take()
+                .or_else(|| self.iter.next().map(|(r, v)| (r.as_ref(), v)))?;
+
+            // Once the set runs out, everything else is kept
+            let excluded = match self
+                .prev_set
+                .take()
+                .or_else(|| self.iter_set.next().map(|r| r.as_ref()))
+            {
+                Some(excluded) => excluded,
+                None => return Some((range, value)),
+            };
+
+            // If `range` is fully before `excluded`, keep all of it
+            if range.end.cmp_start(&excluded.start).is_lt() {
+                self.prev_set = Some(excluded);
+                return Some((range, value));
+            }
+
+            // If `excluded` is fully before `range`, grab another
+            if excluded.end.cmp_start(&range.start).is_lt() {
+                self.prev = Some((range, value));
+                continue;
+            }
+
+            // Otherwise they overlap. Anything in `range` after `excluded`
+            // needs checking against the next excluded range.
+            if range.end > excluded.end {
+                self.prev = Some((
+                    Segment {
+                        start: excluded.end.borrow_after().unwrap(), // Not unbounded, since less than `range.end`
+                        end: range.end,
+                    },
+                    value,
+                ));
+            } else {
+                self.prev_set = Some(excluded);
+            }
+
+            // And anything in `range` before `excluded` can be kept
+            if range.start < excluded.start {
+                range.end = excluded.start.borrow_before().unwrap(); // Same as above
+                return Some((range, value));
+            }
+        }
+    }
+}
+
+impl<K: Ord, V> FusedIterator for Excluding<'_, K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Excluding<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Excluding")
+            .field("prev", &self.prev)
+            .field("prev_set", &self.prev_set)
+            .finish_non_exhaustive()
+    }
+}
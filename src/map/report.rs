@@ -0,0 +1,279 @@
+use alloc::vec::Vec;
+use core::ops::RangeBounds;
+
+use super::Key;
+use crate::{Segment, SegmentMap, SegmentSet};
+
+/// Observer for the changes made by the internal insertion and removal
+/// methods (`insert_internal`, `split_key`, `remove_internal`, and friends).
+///
+/// Only `removed` is required, since that's all that [`SegmentMap::insert`]
+/// and [`SegmentMap::remove`] need to build their return values. The
+/// `stored` and `unstored` hooks see every individual change to the inner
+/// map, which is enough to undo them (see [`Transaction`](super::Transaction)).
+pub(crate) trait Tracker<K, V> {
+    /// (Part of) a stored range was overwritten or removed. `key` is clipped
+    /// to the part that was actually removed.
+    fn removed(&mut self, key: Key<K>, value: V);
+
+    /// A stored range is about to be cut, keeping part of it with the same
+    /// value. Called with the range as it was before the change.
+    fn split(&mut self, _original: &Segment<K>) {}
+
+    /// A stored range was absorbed into a new range with the same value
+    fn merged(&mut self, _absorbed: &Segment<K>) {}
+
+    /// A new range was stored (after any merging)
+    fn added(&mut self, _range: &Segment<K>) {}
+
+    /// Low level: `key` is about to be inserted into the inner map
+    fn stored(&mut self, _key: &Key<K>) {}
+
+    /// Low level: `key` and `value` were removed from the inner map
+    fn unstored(&mut self, _key: &Key<K>, _value: &V) {}
+}
+
+/// A structured report of everything a single mutation did to a
+/// [`SegmentMap`]
+///
+/// Produced by [`SegmentMap::insert_with_report`] and the other `*_with_report`
+/// methods. Each list is in the order the changes were made (which is also
+/// ascending order of ranges).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangeSet<K, V> {
+    /// Parts of stored ranges that were overwritten or removed, with the
+    /// values they held
+    pub removed: Vec<(Segment<K>, V)>,
+
+    /// Ranges that were newly stored. When merging, these cover the merged
+    /// ranges as well.
+    pub added: Vec<Segment<K>>,
+
+    /// Stored ranges that were cut (keeping the parts outside of the
+    /// mutation), as they were before the change
+    pub split: Vec<Segment<K>>,
+
+    /// Stored ranges that were absorbed into an added range because they had
+    /// the same value
+    pub merged: Vec<Segment<K>>,
+}
+
+impl<K, V> ChangeSet<K, V> {
+    /// Makes a new, empty `ChangeSet`
+    pub fn new() -> Self {
+        ChangeSet {
+            removed: Vec::new(),
+            added: Vec::new(),
+            split: Vec::new(),
+            merged: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the mutation didn't touch any stored ranges
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty()
+            && self.added.is_empty()
+            && self.split.is_empty()
+            && self.merged.is_empty()
+    }
+
+    /// Get every key whose value changed, either by being removed, added, or
+    /// overwritten.
+    ///
+    /// Parts of added ranges that were merged from ranges with the same value
+    /// are not included, since their value didn't change.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(10..20, "b");
+    ///
+    /// let report = map.insert_with_report(5..15, "a");
+    /// assert_eq!(report.added, vec![Segment::from(0..15)]);
+    /// assert!(report.affected().iter().eq(&[Segment::from(10..15)]));
+    /// ```
+    pub fn affected(&self) -> SegmentSet<K>
+    where
+        K: Clone + Ord,
+    {
+        let mut affected = SegmentSet::new();
+        for range in &self.added {
+            affected.insert(range);
+        }
+        for range in &self.merged {
+            affected.remove(range);
+        }
+        for (range, _) in &self.removed {
+            affected.insert(range);
+        }
+        affected
+    }
+}
+
+impl<K, V> Default for ChangeSet<K, V> {
+    fn default() -> Self {
+        ChangeSet::new()
+    }
+}
+
+impl<K: Clone, V> Tracker<K, V> for ChangeSet<K, V> {
+    fn removed(&mut self, key: Key<K>, value: V) {
+        self.removed.push((key.0, value));
+    }
+    fn split(&mut self, original: &Segment<K>) {
+        self.split.push(original.clone());
+    }
+    fn merged(&mut self, absorbed: &Segment<K>) {
+        self.merged.push(absorbed.clone());
+    }
+    fn added(&mut self, range: &Segment<K>) {
+        self.added.push(range.clone());
+    }
+}
+
+impl<K, V> SegmentMap<K, V> {
+    /// Insert a value for the specified range, like [`SegmentMap::insert`],
+    /// reporting everything that changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(10..20, "b");
+    ///
+    /// let report = map.insert_with_report(5..12, "c");
+    /// assert_eq!(
+    ///     report.removed,
+    ///     vec![(Segment::from(5..10), "a"), (Segment::from(10..12), "b")]
+    /// );
+    /// assert_eq!(report.added, vec![Segment::from(5..12)]);
+    /// assert_eq!(report.split, vec![Segment::from(0..10), Segment::from(10..20)]);
+    /// assert!(report.merged.is_empty());
+    /// ```
+    pub fn insert_with_report<R>(&mut self, range: R, value: V) -> ChangeSet<K, V>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let mut report = ChangeSet::new();
+        self.insert_internal(Segment::from(&range), value, &mut report);
+        report
+    }
+
+    /// Set a value for the specified range, like [`SegmentMap::set`],
+    /// reporting everything that changed.
+    ///
+    /// This reports the same changes as [`SegmentMap::insert_with_report`],
+    /// since `set` only differs from `insert` in what it returns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(20..30, "b");
+    ///
+    /// let report = map.set_with_report(10..25, "a");
+    /// assert_eq!(report.removed, vec![(Segment::from(20..25), "b")]);
+    /// assert_eq!(report.added, vec![Segment::from(0..25)]);
+    /// assert_eq!(report.merged, vec![Segment::from(0..10)]);
+    /// assert_eq!(report.split, vec![Segment::from(20..30)]);
+    /// ```
+    pub fn set_with_report<R>(&mut self, range: R, value: V) -> ChangeSet<K, V>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.insert_with_report(range, value)
+    }
+
+    /// Insert a value into the empty regions of the specified range, like
+    /// [`SegmentMap::insert_in_gaps`], reporting everything that changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(5..10, "a");
+    /// map.set(15..20, "b");
+    ///
+    /// let report = map.insert_in_gaps_with_report(0..30, "b");
+    /// assert_eq!(report.added, vec![Segment::from(0..5), Segment::from(10..30)]);
+    /// assert_eq!(report.merged, vec![Segment::from(15..20)]);
+    /// assert!(report.removed.is_empty());
+    /// ```
+    pub fn insert_in_gaps_with_report<R>(&mut self, range: R, value: V) -> ChangeSet<K, V>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let mut report = ChangeSet::new();
+        self.insert_in_gaps_internal(Segment::from(&range), value, &mut report);
+        report
+    }
+
+    /// Remove all values in a given range, like [`SegmentMap::remove`],
+    /// reporting everything that changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    ///
+    /// let report = map.remove_with_report(3..5);
+    /// assert_eq!(report.removed, vec![(Segment::from(3..5), "a")]);
+    /// assert_eq!(report.split, vec![Segment::from(0..10)]);
+    /// assert!(report.added.is_empty());
+    /// ```
+    pub fn remove_with_report<R>(&mut self, range: R) -> ChangeSet<K, V>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone,
+    {
+        let mut report = ChangeSet::new();
+        self.remove_internal(Segment::from(&range), &mut report);
+        report
+    }
+
+    /// Move all elements from `other` into `self`, like
+    /// [`SegmentMap::append`], reporting everything that changed in `self`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut a = SegmentMap::new();
+    /// a.set(0..10, "a");
+    ///
+    /// let mut b = SegmentMap::new();
+    /// b.set(5..15, "a");
+    /// b.set(20..30, "b");
+    ///
+    /// let report = a.append_with_report(&mut b);
+    /// assert_eq!(report.added, vec![Segment::from(0..15), Segment::from(20..30)]);
+    /// assert_eq!(report.merged, vec![Segment::from(0..10)]);
+    /// assert!(b.is_empty());
+    /// ```
+    pub fn append_with_report(&mut self, other: &mut Self) -> ChangeSet<K, V>
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let mut report = ChangeSet::new();
+        self.append_internal(other, &mut report);
+        report
+    }
+}
@@ -0,0 +1,304 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{
+    fmt,
+    ops::{
+        Bound::{self, *},
+        Range, RangeBounds, Sub, SubAssign,
+    },
+};
+
+use super::{Key, MaybeMap};
+use crate::{
+    segment::{End, Start},
+    SegmentMap,
+};
+
+/// Addition that reports overflow, used to move keys when inserting space
+///
+/// See [`SegmentMap::try_insert_space`]. This is implemented for all of the
+/// primitive integer types.
+pub trait CheckedAdd: Sized {
+    /// Returns `self + other`, or `None` if the result doesn't fit.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_add {
+    ($($t:ty),*) => {$(
+        impl CheckedAdd for $t {
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
+        }
+    )*};
+}
+impl_checked_add!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Error returned when inserting space would move a bound past the largest
+/// key. The map is left unchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpaceOverflow;
+
+impl fmt::Display for SpaceOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("inserted space overflows the key type")
+    }
+}
+
+/// Add `by` to a bound, if it has a value
+pub(crate) fn checked_shift<T: CheckedAdd>(bound: &Bound<T>, by: &T) -> Option<Bound<T>> {
+    Some(match bound {
+        Included(value) => Included(value.checked_add(by)?),
+        Excluded(value) => Excluded(value.checked_add(by)?),
+        Unbounded => Unbounded,
+    })
+}
+
+/// Decides which segment grows when space is inserted exactly at the boundary
+/// between two segments (or at the start or end of a single segment)
+///
+/// Space inserted strictly inside a segment always grows that segment, and
+/// segments entirely after the insertion point always move.
+///
+/// See [`SegmentMap::insert_space_with`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Stickiness {
+    /// A segment ending at the insertion point grows to cover the inserted
+    /// space (like typing at the end of a bold word in most editors).
+    #[default]
+    Left,
+
+    /// A segment starting at the insertion point grows to cover the inserted
+    /// space, instead of moving to the right.
+    Right,
+
+    /// Neither segment grows, the inserted space is left as a gap.
+    Neither,
+}
+
+impl<K, V> SegmentMap<K, V> {
+    /// Insert `len` empty keys at `at`, moving everything after `at` to the
+    /// right. A segment containing `at` is stretched to cover the inserted
+    /// space.
+    ///
+    /// This is the same as [`SegmentMap::insert_space_with`] using
+    /// [`Stickiness::Left`].
+    ///
+    /// Since every segment after `at` needs to be rekeyed, this takes linear
+    /// time.
+    ///
+    /// # Panics
+    ///
+    /// If a bound after `at` would be moved past the largest `K`. Nothing is
+    /// moved in that case, but see [`SegmentMap::try_insert_space`] to handle
+    /// it without panicking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut styles = SegmentMap::new();
+    /// styles.set(0..5, "bold");
+    /// styles.set(5..10, "italic");
+    /// styles.set(20..30, "bold");
+    ///
+    /// styles.insert_space(7, 3);
+    ///
+    /// assert!(styles.into_iter().eq(vec![
+    ///     (Segment::from(0..5), "bold"),
+    ///     (Segment::from(5..13), "italic"),
+    ///     (Segment::from(23..33), "bold"),
+    /// ]));
+    /// ```
+    pub fn insert_space(&mut self, at: K, len: K)
+    where
+        K: Clone + Ord + CheckedAdd,
+    {
+        self.insert_space_with(at, len, Stickiness::Left)
+    }
+
+    /// Insert `len` empty keys at `at` like [`SegmentMap::insert_space`], or
+    /// return an error (leaving the map unchanged) if a bound after `at`
+    /// would be moved past the largest `K`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map: SegmentMap<u8, _> = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(200..250, "b");
+    ///
+    /// assert_eq!(map.try_insert_space(100, 60), Err(SpaceOverflow));
+    /// assert!(map.iter().eq(vec![
+    ///     (&Segment::from(0..10), &"a"),
+    ///     (&Segment::from(200..250), &"b"),
+    /// ]));
+    ///
+    /// assert_eq!(map.try_insert_space(100, 5), Ok(()));
+    /// assert_eq!(map.get(&252), Some(&"b"));
+    /// ```
+    pub fn try_insert_space(&mut self, at: K, len: K) -> Result<(), SpaceOverflow>
+    where
+        K: Clone + Ord + CheckedAdd,
+    {
+        self.try_insert_space_with(at, len, Stickiness::Left)
+    }
+
+    /// Insert `len` empty keys at `at`, moving everything after `at` to the
+    /// right, and using `stickiness` to decide which segment (if any) grows
+    /// when `at` is on a segment boundary.
+    ///
+    /// # Panics
+    ///
+    /// Like [`SegmentMap::insert_space`], if a bound after `at` would be
+    /// moved past the largest `K`. See
+    /// [`SegmentMap::try_insert_space_with`] to handle that instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..5, "a");
+    /// map.set(5..10, "b");
+    ///
+    /// let mut left = map.clone();
+    /// left.insert_space_with(5, 2, Stickiness::Left);
+    /// assert!(left.into_iter().eq(vec![
+    ///     (Segment::from(0..7), "a"),
+    ///     (Segment::from(7..12), "b"),
+    /// ]));
+    ///
+    /// let mut right = map.clone();
+    /// right.insert_space_with(5, 2, Stickiness::Right);
+    /// assert!(right.into_iter().eq(vec![
+    ///     (Segment::from(0..5), "a"),
+    ///     (Segment::from(5..12), "b"),
+    /// ]));
+    ///
+    /// let mut neither = map.clone();
+    /// neither.insert_space_with(5, 2, Stickiness::Neither);
+    /// assert!(neither.into_iter().eq(vec![
+    ///     (Segment::from(0..5), "a"),
+    ///     (Segment::from(7..12), "b"),
+    /// ]));
+    /// ```
+    pub fn insert_space_with(&mut self, at: K, len: K, stickiness: Stickiness)
+    where
+        K: Clone + Ord + CheckedAdd,
+    {
+        self.try_insert_space_with(at, len, stickiness)
+            .expect("inserted space overflows the key type")
+    }
+
+    /// Insert `len` empty keys at `at` like
+    /// [`SegmentMap::insert_space_with`], or return an error (leaving the map
+    /// unchanged) if a bound after `at` would be moved past the largest `K`.
+    pub fn try_insert_space_with(
+        &mut self,
+        at: K,
+        len: K,
+        stickiness: Stickiness,
+    ) -> Result<(), SpaceOverflow>
+    where
+        K: Clone + Ord + CheckedAdd,
+    {
+        let at_start = Start(Included(at.clone()));
+        let stretched = |end: &End<K>| checked_shift(&end.0, &len).map(End).ok_or(SpaceOverflow);
+
+        // Work out every moved bound before changing anything, so an overflow
+        // leaves the map as it was. The last range before `at` can only
+        // stretch.
+        let last_end = match self.map.range::<Start<K>, _>(..&at_start).next_back() {
+            Some((Key(last), _))
+                if last.contains(&at)
+                    || (stickiness == Stickiness::Left && last.end.0 == Excluded(at.clone())) =>
+            {
+                Some(stretched(&last.end)?)
+            }
+            _ => None,
+        };
+
+        // Everything starting at or after `at` needs to move (or stretch)
+        let moved = self
+            .map
+            .range::<Start<K>, _>(&at_start..)
+            .map(|(Key(range), _)| {
+                let stretch_only = stickiness == Stickiness::Right
+                    && matches!(&range.start.0, Included(start) if *start == at);
+                if stretch_only {
+                    let mut range = range.clone();
+                    range.end = stretched(&range.end)?;
+                    Ok(Key(range))
+                } else {
+                    range
+                        .checked_shift_right(&len)
+                        .map(Key)
+                        .ok_or(SpaceOverflow)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let tail = self.map.split_off(&at_start);
+        if let Some(end) = last_end {
+            let (mut last, value) = self.map.pop_last().expect("checked above");
+            last.0.end = end;
+            self.map.insert(last, value);
+        }
+        let mut shifted = moved
+            .into_iter()
+            .zip(tail.into_values())
+            .collect::<BTreeMap<_, _>>();
+        self.map.append(&mut shifted);
+        Ok(())
+    }
+
+    /// Remove the keys in `range`, moving everything after it to the left to
+    /// close the hole. Segments overlapping `range` shrink, and segments
+    /// that end up touching with the same value are coalesced.
+    ///
+    /// Since every segment after `range` needs to be rekeyed, this takes
+    /// linear time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut styles = SegmentMap::new();
+    /// styles.set(0..5, "bold");
+    /// styles.set(5..10, "italic");
+    /// styles.set(10..20, "bold");
+    ///
+    /// // Deleting all of the italic text joins the bold runs
+    /// styles.remove_space(4..12);
+    ///
+    /// assert!(styles.into_iter().eq(vec![(Segment::from(0..12), "bold")]));
+    /// ```
+    pub fn remove_space(&mut self, range: Range<K>)
+    where
+        K: Clone + Ord + Sub<Output = K> + SubAssign,
+        V: Clone + Eq,
+    {
+        let Range { start, end } = range;
+        if start >= end {
+            return;
+        }
+        let len = end.clone() - start.clone();
+
+        // Cut out everything in `range`, holding on to what comes after it
+        let tail = self.split_off(Bound::Included(end));
+        self.clear_range(start..);
+
+        let mut shifted = tail.map.into_iter().map(|(mut key, value)| {
+            key.0.shift_left(len.clone());
+            (key, value)
+        });
+
+        // Only the first shifted range could touch what's left before `start`
+        if let Some((first, value)) = shifted.next() {
+            self.insert_internal(first.0, value, &mut MaybeMap::Never);
+        }
+        let mut shifted = shifted.collect::<BTreeMap<Key<K>, V>>();
+        self.map.append(&mut shifted);
+    }
+}
@@ -1,4 +1,5 @@
 use super::*;
+use crate::SegmentSet;
 use alloc::{collections::BTreeMap, format, vec};
 
 // A simple but infeasibly slow and memory-hungry
@@ -614,6 +615,26 @@ fn remove_superset_of_stored() {
 /// impl Debug
 ///
 
+#[test]
+fn iter_complement_covers_ends() {
+    let mut range_map: SegmentMap<u32, bool> = SegmentMap::new();
+    assert!(range_map.iter_complement().eq(vec![Segment::from(..)]));
+
+    range_map.insert(5..10, true);
+    range_map.insert(20..30, false);
+    assert!(range_map.iter_complement().eq(vec![
+        Segment::from(&..&5),
+        Segment::from(&10..&20),
+        Segment::from(&30..),
+    ]));
+
+    // Nothing before a range starting at the lowest bound
+    range_map.insert(..10, true);
+    assert!(range_map
+        .iter_complement()
+        .eq(vec![Segment::from(&10..&20), Segment::from(&30..)]));
+}
+
 #[test]
 fn map_debug_repr_looks_right() {
     let mut map: SegmentMap<u32, ()> = SegmentMap::new();
@@ -651,3 +672,849 @@ fn map_debug_repr_looks_right() {
 //     // Equality
 //     assert_eq!(cloned, consumed);
 // }
+
+//
+// Space insertion/removal tests
+//
+
+#[test]
+fn insert_space_inside_inclusive_and_unbounded_ranges() {
+    let mut range_map: SegmentMap<u32, bool> = SegmentMap::new();
+    range_map.insert(..=5, false);
+    range_map.insert(10.., true);
+    range_map.insert_space(5, 3);
+    assert_eq!(
+        range_map.into_vec(),
+        vec![(Segment::from(..=8), false), (Segment::from(13..), true)]
+    );
+}
+
+#[test]
+fn insert_space_in_gap_only_moves_later_ranges() {
+    let mut range_map: SegmentMap<u32, bool> = SegmentMap::new();
+    range_map.insert(0..2, false);
+    range_map.insert(5..7, true);
+    range_map.insert_space_with(3, 10, Stickiness::Right);
+    assert_eq!(
+        range_map.into_vec(),
+        vec![(Segment::from(0..2), false), (Segment::from(15..17), true)]
+    );
+}
+
+#[test]
+#[should_panic(expected = "overflow")]
+fn insert_space_overflow_panics() {
+    let mut range_map: SegmentMap<u8, bool> = SegmentMap::new();
+    range_map.insert(200..250, true);
+    range_map.insert_space(100, 10);
+}
+
+#[test]
+fn try_insert_space_overflow_leaves_map_unchanged() {
+    let mut range_map: SegmentMap<u8, bool> = SegmentMap::new();
+    range_map.insert(0..10, false);
+    range_map.insert(200..250, true);
+    let expected = range_map.clone();
+
+    assert_eq!(range_map.try_insert_space(100, 60), Err(SpaceOverflow));
+    assert_eq!(range_map, expected);
+
+    // Stretching the range covering `at` can overflow too
+    assert_eq!(range_map.try_insert_space(220, 10), Err(SpaceOverflow));
+    assert_eq!(
+        range_map.try_insert_space_with(250, 10, Stickiness::Left),
+        Err(SpaceOverflow)
+    );
+    assert_eq!(range_map, expected);
+
+    assert_eq!(
+        range_map.try_insert_space_with(200, 5, Stickiness::Right),
+        Ok(())
+    );
+    assert_eq!(
+        range_map.into_vec(),
+        vec![
+            (Segment::from(0..10), false),
+            (Segment::from(200..255), true)
+        ]
+    );
+}
+
+#[test]
+fn remove_space_drops_covered_ranges() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.insert(0..4, 1);
+    range_map.insert(5..6, 2);
+    range_map.insert(7..10, 3);
+    range_map.insert(12..14, 1);
+    range_map.remove_space(2..8);
+    assert_eq!(
+        range_map.into_vec(),
+        vec![
+            (Segment::from(0..2), 1),
+            (Segment::from(2..4), 3),
+            (Segment::from(6..8), 1)
+        ]
+    );
+}
+
+//
+// Key transform tests
+//
+
+#[test]
+fn checked_key_transforms_coalesce_touching_ranges() {
+    let mut range_map: SegmentMap<u32, bool> = SegmentMap::new();
+    range_map.insert(0..4, true);
+    range_map.insert(5..8, true);
+    range_map.insert(8..10, false);
+
+    // Collapses the gap between the first two ranges
+    let halved = range_map
+        .clone()
+        .map_keys_monotonic_checked(|t| t / 2)
+        .unwrap();
+    assert_eq!(
+        halved.into_vec(),
+        vec![(Segment::from(0..4), true), (Segment::from(4..5), false)]
+    );
+
+    let mirrored = range_map
+        .clone()
+        .map_keys_decreasing_checked(|t| 10 - t)
+        .unwrap();
+    assert_eq!(
+        mirrored.into_vec(),
+        vec![
+            (Segment::new(Bound::Excluded(0), Bound::Included(2)), false),
+            (Segment::new(Bound::Excluded(2), Bound::Included(5)), true),
+            (Segment::new(Bound::Excluded(6), Bound::Included(10)), true),
+        ]
+    );
+
+    assert_eq!(
+        range_map.map_keys_decreasing_checked(|t| t),
+        Err(NotMonotonic)
+    );
+}
+
+#[test]
+fn value_transforms_coalesce_only_touching_ranges() {
+    let mut range_map: SegmentMap<u32, u32> = SegmentMap::new();
+    range_map.insert(0..5, 1);
+    range_map.insert(5..8, 2);
+    range_map.insert(10..12, 3);
+    range_map.insert(12..15, 4);
+
+    // The gap between 8 and 10 stays, even though the values match
+    assert_eq!(
+        range_map.clone().map_values(|_| ()).into_vec(),
+        vec![(Segment::from(0..8), ()), (Segment::from(10..15), ())]
+    );
+
+    // Dropping a range leaves a gap that nothing merges across
+    assert_eq!(
+        range_map
+            .clone()
+            .filter_map_values(|v| (v != 2).then_some(v % 2))
+            .into_vec(),
+        vec![
+            (Segment::from(0..5), 1),
+            (Segment::from(10..12), 1),
+            (Segment::from(12..15), 0),
+        ]
+    );
+
+    range_map.map_values_in_place(|v| *v = (*v > 2) as u32);
+    assert_eq!(
+        range_map.into_vec(),
+        vec![(Segment::from(0..8), 0), (Segment::from(10..15), 1)]
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn map_values_in_place_keeps_values_when_f_panics() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let mut range_map: SegmentMap<u32, u32> = SegmentMap::new();
+    range_map.insert(0..5, 1);
+    range_map.insert(5..10, 2);
+    range_map.insert(10..15, 3);
+
+    let mut calls = 0;
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        range_map.map_values_in_place(|v| {
+            calls += 1;
+            assert!(calls < 2, "second value");
+            *v = 2;
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(
+        range_map.into_vec(),
+        vec![(Segment::from(0..10), 2), (Segment::from(10..15), 3)]
+    );
+}
+
+#[test]
+fn remove_inside_range_returns_only_removed_part() {
+    let mut range_map: SegmentMap<u32, bool> = SegmentMap::new();
+    range_map.insert(10..20, true);
+    range_map.insert(20..30, false);
+
+    let removed = range_map.remove(12..14).unwrap();
+    assert_eq!(removed.into_vec(), vec![(Segment::from(12..14), true)]);
+
+    // A range starting right after the removed one isn't touched either
+    let removed = range_map.remove(15..20).unwrap();
+    assert_eq!(removed.into_vec(), vec![(Segment::from(15..20), true)]);
+    assert_eq!(
+        range_map.into_vec(),
+        vec![
+            (Segment::from(10..12), true),
+            (Segment::from(14..15), true),
+            (Segment::from(20..30), false),
+        ]
+    );
+}
+
+#[test]
+fn insert_in_gaps_stops_at_covering_range() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.insert(0..10, 1);
+    range_map.insert(20..40, 2);
+
+    // Ending exactly where a stored range ends leaves nothing to fill
+    range_map.insert_in_gaps(5..40, 3);
+    // As does ending inside one
+    range_map.insert_in_gaps(5..30, 4);
+    assert_eq!(
+        range_map.into_vec(),
+        vec![
+            (Segment::from(0..10), 1),
+            (Segment::from(10..20), 3),
+            (Segment::from(20..40), 2),
+        ]
+    );
+}
+
+#[test]
+fn restrict_and_exclude_match_clear_range() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.insert(0..10, 1);
+    range_map.insert(10..=15, 2);
+    range_map.insert(20..30, 1);
+    range_map.insert(40.., 3);
+
+    let sets: Vec<Vec<Segment<u32>>> = vec![
+        vec![],
+        vec![Segment::from(..)],
+        vec![Segment::from(5..12)],
+        vec![Segment::from(0..10), Segment::from(20..30)],
+        vec![
+            Segment::from(3..=4),
+            Segment::from(15..25),
+            Segment::from(50..60),
+        ],
+        vec![
+            Segment::from(..5),
+            Segment::from(10..11),
+            Segment::from(45..),
+        ],
+    ];
+
+    for ranges in sets {
+        let set: SegmentSet<u32> = ranges.iter().collect();
+
+        // Naive versions, removing ranges one by one
+        let mut excluded = range_map.clone();
+        for range in set.iter() {
+            excluded.clear_range(range);
+        }
+        let mut restricted = range_map.clone();
+        for (range, _) in excluded.iter() {
+            restricted.clear_range(range);
+        }
+
+        let mut map = range_map.clone();
+        map.exclude(&set);
+        assert_eq!(map, excluded, "excluding {:?}", set);
+        assert_eq!(range_map.excluding(&set).len(), excluded.len());
+
+        let mut map = range_map.clone();
+        map.restrict_to(&set);
+        assert_eq!(map, restricted, "restricting to {:?}", set);
+        assert_eq!(range_map.restricted_to(&set).len(), restricted.len());
+    }
+}
+
+#[test]
+fn partition_coalesces_each_side() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.insert(0..5, 1);
+    range_map.insert(5..10, 3);
+    range_map.insert(10..15, 2);
+    range_map.insert(20..25, 5);
+
+    let (odd, even) = range_map.partition(|v| v % 2 == 1);
+    assert_eq!(
+        odd.into_iter().collect::<Vec<_>>(),
+        vec![Segment::from(&0..&10), Segment::from(&20..&25)]
+    );
+    assert_eq!(
+        even.into_iter().collect::<Vec<_>>(),
+        vec![Segment::from(&10..&15)]
+    );
+}
+
+#[test]
+fn apply_patch_reproduces_new_version() {
+    let mut versions: Vec<SegmentMap<u32, u8>> = vec![SegmentMap::new()];
+    let edits: Vec<(u32, u32, Option<u8>)> = vec![
+        (0, 10, Some(1)),
+        (5, 15, Some(2)),
+        (20, 30, Some(1)),
+        (8, 22, None),
+        (0, 40, Some(1)),
+        (10, 12, Some(3)),
+        (10, 12, Some(1)),
+        (35, 50, Some(2)),
+        (0, 50, None),
+    ];
+    for (start, end, value) in edits {
+        let mut next = versions.last().unwrap().clone();
+        match value {
+            Some(value) => next.set(start..end, value),
+            None => next.clear_range(start..end),
+        }
+        versions.push(next);
+    }
+
+    // Patch between every pair of versions, in both directions
+    for old in &versions {
+        for new in &versions {
+            let patch = old.diff(new);
+            assert_eq!(patch.is_empty(), old == new);
+            for change in &patch {
+                assert_ne!(change.old_value(), change.new_value());
+            }
+
+            let mut patched = old.clone();
+            patched.apply_patch(&patch);
+            assert_eq!(&patched, new);
+        }
+    }
+}
+
+#[test]
+fn change_set_affected_matches_diff() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    let edits: Vec<(u32, u32, Option<u8>, bool)> = vec![
+        (0, 10, Some(1), false),
+        (5, 15, Some(2), false),
+        (20, 30, Some(1), true),
+        (8, 22, None, false),
+        (0, 40, Some(1), true),
+        (10, 12, Some(3), false),
+        (10, 12, Some(1), false),
+        (12, 50, Some(1), false),
+        (5, 7, None, false),
+        (0, 60, Some(2), true),
+        (0, 60, None, false),
+    ];
+    for (start, end, value, gaps_only) in edits {
+        let before = range_map.clone();
+        let report = match value {
+            Some(value) if gaps_only => range_map.insert_in_gaps_with_report(start..end, value),
+            Some(value) => range_map.insert_with_report(start..end, value),
+            None => range_map.remove_with_report(start..end),
+        };
+
+        let changed: SegmentSet<u32> = before
+            .iter_diff(&range_map)
+            .map(|change| change.range().cloned())
+            .collect();
+        assert_eq!(report.affected(), changed);
+
+        // Everything reported removed really was there before, and is gone
+        for (range, value) in &report.removed {
+            assert_eq!(
+                before.subset(range).values().collect::<Vec<_>>(),
+                vec![&value]
+            );
+            assert_ne!(range_map.subset(range).values().next(), Some(&value));
+        }
+    }
+}
+
+#[test]
+fn transaction_rollback_restores_snapshots() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.insert(0..10, 1);
+    range_map.insert(20..30, 2);
+    let original = range_map.clone();
+
+    let edits: Vec<(u32, u32, Option<u8>, bool)> = vec![
+        (5, 25, Some(3), false),
+        (0, 40, Some(1), true),
+        (8, 12, None, false),
+        (0, 5, Some(1), false),
+        (10, 35, Some(2), false),
+        (0, 100, None, false),
+        (50, 60, Some(4), true),
+    ];
+
+    let mut tx = range_map.transaction();
+    let mut snapshots = Vec::new();
+    for (start, end, value, gaps_only) in edits {
+        snapshots.push((tx.savepoint(), (*tx).clone()));
+        match value {
+            Some(value) if gaps_only => tx.insert_in_gaps(start..end, value),
+            Some(value) => tx.set(start..end, value),
+            None => tx.clear_range(start..end),
+        }
+    }
+
+    // Roll back one step at a time, checking every intermediate state
+    for (savepoint, snapshot) in snapshots.into_iter().rev() {
+        tx.rollback_to(savepoint);
+        assert_eq!(*tx, snapshot);
+    }
+    drop(tx);
+    assert_eq!(range_map, original);
+
+    // Committed changes stick, dropped ones don't
+    let mut tx = range_map.transaction();
+    assert_eq!(tx.remove(5..25).map(|r| r.len()), Some(2));
+    tx.commit();
+    let mut tx = range_map.transaction();
+    tx.insert(0..100, 9);
+    drop(tx);
+    assert_eq!(
+        range_map.into_vec(),
+        vec![(Segment::from(0..5), 1), (Segment::from(25..30), 2)]
+    );
+}
+
+#[test]
+#[should_panic(expected = "already been rolled back")]
+fn transaction_rejects_stale_savepoint() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    let mut tx = range_map.transaction();
+    tx.set(0..10, 1);
+    let early = tx.savepoint();
+    tx.set(20..30, 2);
+    let late = tx.savepoint();
+    tx.rollback_to(early);
+
+    // The log grows back past `late`, but its changes are gone
+    tx.set(40..50, 3);
+    tx.set(60..70, 4);
+    tx.rollback_to(late);
+}
+
+#[test]
+#[should_panic(expected = "different transaction")]
+fn transaction_rejects_foreign_savepoint() {
+    let mut first: SegmentMap<u32, u8> = SegmentMap::new();
+    let mut second = first.clone();
+
+    let mut tx = first.transaction();
+    tx.set(0..10, 1);
+    tx.set(20..30, 2);
+    let foreign = tx.savepoint();
+
+    let mut other = second.transaction();
+    other.set(0..10, 1);
+    other.rollback_to(foreign);
+}
+
+#[test]
+fn cursor_edits_match_range_operations() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.set(0..10, 1);
+    range_map.set(10..20, 2);
+    range_map.set(30..40, 1);
+    range_map.set(45..50, 3);
+    let mut expected = range_map.clone();
+
+    // Walking forwards then backwards visits every segment and gap
+    let mut cursor = range_map.cursor_at(&0);
+    let mut forwards = vec![(cursor.segment().map(|(r, _)| *r), cursor.gap().copied())];
+    while cursor.move_next() {
+        forwards.push((cursor.segment().map(|(r, _)| *r), cursor.gap().copied()));
+    }
+    let mut backwards = vec![(cursor.segment().map(|(r, _)| *r), cursor.gap().copied())];
+    while cursor.move_prev() {
+        backwards.push((cursor.segment().map(|(r, _)| *r), cursor.gap().copied()));
+    }
+    backwards.reverse();
+    assert_eq!(forwards.len(), 7);
+    assert_eq!(backwards.len(), 8); // Including the gap before 0
+    assert_eq!(&backwards[1..], &forwards[..]);
+
+    let mut cursor = range_map.cursor_mut_at(&15);
+    assert_eq!(cursor.replace_value(1), Some(2));
+    expected.set(10..20, 1);
+    assert_eq!(cursor.segment(), Some((&Segment::from(0..20), &1)));
+
+    assert!(cursor.split_at(Bound::Included(5), 4));
+    expected.set(5..20, 4);
+    assert!(cursor.move_next());
+    assert_eq!(cursor.remove_current(), Some((Segment::from(5..20), 4)));
+    expected.clear_range(5..20);
+    assert_eq!(cursor.gap(), Some(&Segment::from(5..30)));
+
+    assert!(cursor.insert_in_current_gap(1));
+    expected.set(5..30, 1);
+    assert_eq!(cursor.segment(), Some((&Segment::from(0..40), &1)));
+
+    assert_eq!(cursor.merge_with_next(), Some(3));
+    expected.set(40..50, 1);
+    assert_eq!(cursor.segment(), Some((&Segment::from(0..50), &1)));
+    assert!(!cursor.move_next_segment());
+    assert_eq!(range_map, expected);
+}
+
+#[test]
+fn cursor_moves_match_cursor_mut() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.set(..5, 1);
+    range_map.set(5..10, 2);
+    range_map.set(20..30, 1);
+    range_map.set(30..40, 3);
+    range_map.set(50.., 2);
+    let mut other = range_map.clone();
+
+    // Mix every kind of move, turning around often
+    let moves = [
+        0, 0, 2, 1, 1, 3, 0, 2, 2, 2, 1, 3, 3, 3, 0, 0, 0, 0, 0, 1, 2, 3,
+    ];
+    let mut cursor = range_map.cursor_at(&25);
+    let mut cursor_mut = other.cursor_mut_at(&25);
+    for step in moves.iter().cycle().take(100) {
+        let moved = match step {
+            0 => (cursor.move_next(), cursor_mut.move_next()),
+            1 => (cursor.move_prev(), cursor_mut.move_prev()),
+            2 => (cursor.move_next_segment(), cursor_mut.move_next_segment()),
+            _ => (cursor.move_prev_segment(), cursor_mut.move_prev_segment()),
+        };
+        assert_eq!(moved.0, moved.1);
+        assert_eq!(cursor.segment(), cursor_mut.segment());
+        assert_eq!(cursor.gap(), cursor_mut.gap());
+    }
+}
+
+#[test]
+fn view_mut_only_changes_window() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.set(0..10, 1);
+    range_map.set(10..30, 2);
+    range_map.set(40..50, 3);
+    let mut expected = range_map.clone();
+
+    let mut view = range_map.view_mut(5..45);
+    assert_eq!(
+        view.to_segment_map().into_vec(),
+        vec![
+            (Segment::from(5..10), 1),
+            (Segment::from(10..30), 2),
+            (Segment::from(40..45), 3),
+        ]
+    );
+    assert_eq!(view.get(&2), None);
+    assert_eq!(view.get_range_value(&42), Some((Segment::from(40..45), &3)));
+
+    // Writes are clamped to the window
+    let removed = view.insert(0..12, 4).unwrap();
+    expected.insert(5..12, 4);
+    assert_eq!(
+        removed.into_vec(),
+        vec![(Segment::from(5..10), 1), (Segment::from(10..12), 2)]
+    );
+    view.insert_in_gaps(20.., 3);
+    expected.insert_in_gaps(20..45, 3);
+    assert!(view.insert(50..60, 9).is_none());
+    assert_eq!(view.len(), 3);
+
+    view.clear();
+    expected.clear_range(5..45);
+    assert!(view.is_empty());
+    assert_eq!(range_map, expected);
+}
+
+#[test]
+fn view_mut_gaps_and_lookups_stay_in_window() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.set(0..10, 1);
+    range_map.set(10..30, 2);
+    range_map.set(40..50, 3);
+
+    // Gaps at the window's bounds are included, touching ranges leave none
+    let view = range_map.view_mut(5..60);
+    assert_eq!(
+        view.iter_gaps().collect::<Vec<_>>(),
+        vec![Segment::from(30..40), Segment::from(50..60)]
+    );
+    assert!(range_map.view_mut(12..20).iter_gaps().next().is_none());
+    assert_eq!(
+        range_map
+            .view_mut(..)
+            .gaps()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec![
+            Segment::from(..0),
+            Segment::from(30..40),
+            Segment::from(50..)
+        ]
+    );
+
+    let mut view = range_map.view_mut(5..45);
+    assert_eq!(view.first(), Some((Segment::from(5..10), &1)));
+    assert_eq!(view.last(), Some((Segment::from(40..45), &3)));
+    assert_eq!(
+        view.iter_in(9..11).collect::<Vec<_>>(),
+        vec![(Segment::from(5..10), &1), (Segment::from(10..30), &2)]
+    );
+    assert!(view.iter_in(50..).next().is_none());
+
+    // Only the part inside the window is checked (and inserted)
+    assert_eq!(view.insert_if_empty(35..50, 4), Some(4));
+    assert_eq!(view.insert_if_empty(60..70, 4), Some(4));
+    assert_eq!(view.insert_if_empty(30..40, 4), None);
+    assert_eq!(
+        range_map.get_range_value(&35),
+        Some((&Segment::from(30..40), &4))
+    );
+}
+
+#[test]
+fn gaps_skip_touching_ranges() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.set(0..10, 1);
+    range_map.set(10..20, 2);
+    range_map.set(20..30, 1);
+    range_map.set(40..50, 2);
+
+    let gaps = range_map.iter_gaps();
+    assert_eq!(gaps.size_hint(), (0, Some(3)));
+    assert_eq!(
+        gaps.clone().collect::<Vec<_>>(),
+        vec![Segment::from(&30..&40)]
+    );
+    assert_eq!(
+        gaps.rev().collect::<Vec<_>>(),
+        vec![Segment::from(&30..&40)]
+    );
+    assert_eq!(
+        range_map.iter_complement().collect::<Vec<_>>(),
+        vec![
+            Segment::from(..&0),
+            Segment::from(&30..&40),
+            Segment::from(&50..)
+        ]
+    );
+}
+
+#[test]
+fn iter_subset_clips_both_ends() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.set(0..100, 1);
+    range_map.set(200.., 2);
+
+    assert!(range_map
+        .iter_subset(10..20)
+        .eq(vec![(Segment::from(10..20), &1)]));
+    assert!(range_map.iter_subset(50..).eq(vec![
+        (Segment::from(50..100), &1),
+        (Segment::from(200..), &2)
+    ]));
+    assert!(range_map
+        .iter_subset(250..)
+        .eq(vec![(Segment::from(250..), &2)]));
+}
+
+#[test]
+fn drain_range_matches_remove() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    range_map.set(0..10, 1);
+    range_map.set(10..20, 2);
+    range_map.set(30..40, 3);
+    range_map.set(50.., 4);
+
+    for (start, end) in [(0, 60), (5, 15), (12, 18), (15, 35), (20, 30), (45, 55)] {
+        let mut drained = range_map.clone();
+        let mut removed = range_map.clone();
+        let expected = removed.remove(start..end).map_or(vec![], |m| m.into_vec());
+        assert_eq!(
+            drained.drain_range(start..end).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(drained, removed);
+
+        // Dropping the iterator early still removes the whole range
+        let mut dropped = range_map.clone();
+        dropped.drain_range(start..end).next();
+        assert_eq!(dropped, removed);
+    }
+
+    let mut drained = range_map.clone();
+    assert!(drained
+        .drain_range(35..)
+        .eq(vec![(Segment::from(35..40), 3), (Segment::from(50..), 4)]));
+    assert_eq!(drained.last(), Some((&Segment::from(30..35), &3)));
+}
+
+#[test]
+fn extract_if_keeps_unvisited_ranges() {
+    let mut range_map: SegmentMap<u32, u8> = SegmentMap::new();
+    for (i, start) in (0..50).step_by(10).enumerate() {
+        range_map.set(start..start + 5, i as u8);
+    }
+
+    {
+        // Stop after the first two matches
+        let mut extract = range_map.extract_if(|_, v| *v % 2 == 0);
+        assert_eq!(extract.next(), Some((Segment::from(0..5), 0)));
+        assert_eq!(extract.next(), Some((Segment::from(20..25), 2)));
+    }
+    assert_eq!(
+        range_map.into_vec(),
+        vec![
+            (Segment::from(10..15), 1),
+            (Segment::from(30..35), 3),
+            (Segment::from(40..45), 4),
+        ]
+    );
+}
+
+#[test]
+fn overlap_join_matches_nested_loops() {
+    let mut a: SegmentMap<i32, u8> = SegmentMap::new();
+    a.set(..0, 0);
+    a.set(2..5, 1);
+    a.set(5..=9, 2);
+    a.set(20..30, 3);
+    a.set(31..32, 4);
+    a.set(50.., 5);
+
+    let mut b: SegmentMap<i32, char> = SegmentMap::new();
+    b.set(-5..3, 'a');
+    b.set(9..10, 'b');
+    b.set(12..14, 'c');
+    b.set(25..40, 'd');
+    b.set(45..47, 'e');
+
+    let expected: Vec<_> = a
+        .iter()
+        .flat_map(|(ka, va)| {
+            b.iter()
+                .filter(move |(kb, _)| ka.overlaps(kb))
+                .map(move |(kb, vb)| (ka, va, kb, vb))
+        })
+        .collect();
+    assert_eq!(a.overlap_join(&b).collect::<Vec<_>>(), expected);
+    assert_eq!(b.overlap_join(&a).count(), expected.len());
+
+    let gap = |x: &Segment<i32>, y: &Segment<i32>| match (x.end.value(), y.start.value()) {
+        _ if x.overlaps(y) => Some(0),
+        (Some(end), Some(start)) if end <= start => Some(start - end),
+        _ => None,
+    };
+    for distance in [0, 1, 3, 5, 10, 100] {
+        let expected: Vec<_> = a
+            .iter()
+            .flat_map(|(ka, va)| {
+                b.iter()
+                    .filter(move |(kb, _)| {
+                        gap(ka, kb)
+                            .or_else(|| gap(kb, ka))
+                            .is_some_and(|gap| gap <= distance as i32)
+                    })
+                    .map(move |(kb, vb)| (ka, va, kb, vb))
+            })
+            .collect();
+        assert_eq!(a.within(&b, distance).collect::<Vec<_>>(), expected);
+    }
+}
+
+#[test]
+fn lookup_sorted_matches_get() {
+    let mut map: SegmentMap<u32, u8> = SegmentMap::new();
+    map.set(2..5, 1);
+    map.set(5..=9, 2);
+    map.set(20..30, 3);
+    map.set(40.., 4);
+
+    let points: Vec<u32> = vec![0, 1, 2, 2, 4, 5, 9, 10, 15, 20, 29, 30, 39, 40, 100];
+    let found: Vec<_> = map.lookup_sorted(points.clone()).collect();
+    let expected: Vec<_> = points
+        .iter()
+        .map(|p| (*p, map.get_range_value(p)))
+        .collect();
+    assert_eq!(found, expected);
+
+    let found: Vec<_> = map.lookup_sorted(points.clone()).as_of().collect();
+    let expected: Vec<_> = points
+        .iter()
+        .map(|p| {
+            let before = map
+                .iter()
+                .rev()
+                .find(|(range, _)| range.start_value() <= Some(p));
+            (*p, before)
+        })
+        .collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+#[should_panic(expected = "points must be sorted")]
+fn lookup_sorted_rejects_unsorted_points() {
+    let mut map: SegmentMap<u32, u8> = SegmentMap::new();
+    map.set(0..5, 1);
+    map.set(10..20, 2);
+    map.lookup_sorted(vec![12, 3]).for_each(drop);
+}
+
+#[test]
+fn set_many_matches_set() {
+    use permutator::Permutation;
+
+    let mut ranges_with_values = [
+        (0..2, 1),
+        (2..3, 1),
+        (3..5, 2),
+        (4..6, 1),
+        (1..4, 3),
+        (7..9, 2),
+        (5..7, 2),
+    ];
+
+    ranges_with_values.permutation().for_each(|permutation| {
+        for split in [0, 3, 5] {
+            let (before, batch) = permutation.split_at(split);
+            let mut expected: SegmentMap<u32, u8> = SegmentMap::new();
+            for (k, v) in permutation.iter().cloned() {
+                expected.set(k, v);
+            }
+
+            let mut range_map: SegmentMap<u32, u8> = before.iter().cloned().collect();
+            range_map.set_many(batch.iter().cloned());
+            assert_eq!(range_map, expected);
+        }
+    });
+
+    // Sorted input builds directly
+    let sorted = vec![(0..2, 1), (2..4, 1), (5..6, 2)];
+    assert_eq!(
+        SegmentMap::from_sorted_iter(sorted.clone()),
+        Ok(sorted.into_iter().collect())
+    );
+    assert_eq!(
+        SegmentMap::from_sorted_iter(vec![(2..4, 1), (0..2, 1)]),
+        Err(NotSorted)
+    );
+}
@@ -0,0 +1,305 @@
+use alloc::vec::Vec;
+use core::{
+    fmt::{self, Debug},
+    ops::{Deref, RangeBounds},
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+};
+
+use super::{Key, MaybeMap, Tracker};
+use crate::{Segment, SegmentMap};
+
+/// A position in a [`Transaction`]'s undo log, which can be rolled back to
+///
+/// A savepoint can only be used with the transaction that created it.
+///
+/// See [`Transaction::savepoint`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Savepoint {
+    /// Id of the transaction that created the savepoint
+    transaction: usize,
+
+    /// Length of the undo log when the savepoint was created
+    position: usize,
+
+    /// Number of rollbacks made before the savepoint was created
+    generation: usize,
+}
+
+/// A single change to the inner map, recorded as the operation that undoes it
+enum Undo<K, V> {
+    /// Undo an insertion by removing the key again
+    Remove(Key<K>),
+
+    /// Undo a removal by putting the entry back
+    Insert(Key<K>, V),
+}
+
+/// Records every change made to the inner map, while passing removed ranges
+/// through to `removed` (so transactions can return them like the map does)
+struct UndoTracker<'a, K, V> {
+    log: &'a mut Vec<Undo<K, V>>,
+    removed: MaybeMap<K, V>,
+}
+
+impl<K: Clone + Ord, V: Clone> Tracker<K, V> for UndoTracker<'_, K, V> {
+    fn removed(&mut self, key: Key<K>, value: V) {
+        self.removed.removed(key, value);
+    }
+    fn stored(&mut self, key: &Key<K>) {
+        self.log.push(Undo::Remove(key.clone()));
+    }
+    fn unstored(&mut self, key: &Key<K>, value: &V) {
+        self.log.push(Undo::Insert(key.clone(), value.clone()));
+    }
+}
+
+/// A guard for a batch of changes to a [`SegmentMap`], which can be rolled
+/// back
+///
+/// Created by [`SegmentMap::transaction`]. Changes are applied to the map
+/// immediately, and an undo log records the individual splits, merges and
+/// removals they caused. Unless [`Transaction::commit`] is called, dropping
+/// the transaction rolls every change back.
+///
+/// The map can be read through the transaction at any time (it dereferences
+/// to the underlying [`SegmentMap`]).
+///
+/// Undoing only touches the ranges that were changed, so the cost of a
+/// transaction is proportional to the size of the changes, not the map.
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// let mut map = SegmentMap::new();
+/// map.set(0..100, "free");
+///
+/// {
+///     let mut tx = map.transaction();
+///     tx.set(10..20, "used");
+///     tx.set(50..60, "used");
+///     assert_eq!(tx.len(), 5);
+///
+///     // Validation failed! Dropping `tx` without committing rolls back.
+/// }
+/// assert!(map.iter().eq(vec![(&Segment::from(0..100), &"free")]));
+///
+/// let mut tx = map.transaction();
+/// tx.set(10..20, "used");
+/// let sp = tx.savepoint();
+/// tx.clear_range(..);
+/// assert!(tx.is_empty());
+///
+/// // Undo just the last change, then keep the rest
+/// tx.rollback_to(sp);
+/// tx.commit();
+/// assert_eq!(map[&15], "used");
+/// ```
+pub struct Transaction<'a, K, V>
+where
+    K: Ord,
+{
+    map: &'a mut SegmentMap<K, V>,
+
+    /// Unique to this transaction, so savepoints from others are rejected
+    id: usize,
+    log: Vec<Undo<K, V>>,
+
+    /// The log position each rollback went back to, in order. A savepoint is
+    /// stale if a later rollback went back past it, even if the log has
+    /// since grown again.
+    rollbacks: Vec<usize>,
+    committed: bool,
+}
+
+impl<K, V> SegmentMap<K, V> {
+    /// Start a [`Transaction`], which will roll back any changes made through
+    /// it unless committed.
+    pub fn transaction(&mut self) -> Transaction<'_, K, V>
+    where
+        K: Ord,
+    {
+        // Ids only need to differ between transactions that are alive at the
+        // same time, so wrapping around is fine
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        Transaction {
+            map: self,
+            id: NEXT_ID.fetch_add(1, Relaxed),
+            log: Vec::new(),
+            rollbacks: Vec::new(),
+            committed: false,
+        }
+    }
+}
+
+impl<K: Ord, V> Transaction<'_, K, V> {
+    /// Get a [`Savepoint`] for the current state of the transaction, which can
+    /// later be restored with [`Transaction::rollback_to`].
+    pub fn savepoint(&self) -> Savepoint {
+        Savepoint {
+            transaction: self.id,
+            position: self.log.len(),
+            generation: self.rollbacks.len(),
+        }
+    }
+
+    /// A savepoint from before any changes were made
+    fn start(&self) -> Savepoint {
+        Savepoint {
+            transaction: self.id,
+            position: 0,
+            generation: 0,
+        }
+    }
+
+    /// Undo all changes made since `savepoint` was created.
+    ///
+    /// Savepoints created after `savepoint` are no longer valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `savepoint` was created by a different transaction, or if
+    /// changes made before `savepoint` have already been rolled back (for
+    /// example, by rolling back to an earlier savepoint), even if more changes
+    /// have been made since.
+    pub fn rollback_to(&mut self, savepoint: Savepoint) {
+        assert_eq!(
+            savepoint.transaction, self.id,
+            "savepoint belongs to a different transaction"
+        );
+
+        // A rollback past the savepoint is recorded in `rollbacks` (and is
+        // the only way the log shrinks), so this also keeps `position` in
+        // bounds
+        let stale = self.rollbacks[savepoint.generation..]
+            .iter()
+            .any(|&position| position < savepoint.position);
+        assert!(!stale, "savepoint has already been rolled back");
+        self.rollbacks.push(savepoint.position);
+        for undo in self.log.drain(savepoint.position..).rev() {
+            match undo {
+                Undo::Remove(key) => {
+                    self.map.map.remove(&key);
+                }
+                Undo::Insert(key, value) => {
+                    self.map.map.insert(key, value);
+                }
+            }
+        }
+    }
+
+    /// Undo all changes made in this transaction.
+    ///
+    /// This is the same as dropping the transaction without committing.
+    pub fn rollback(mut self) {
+        self.rollback_to(self.start());
+    }
+
+    /// Keep all changes made in this transaction.
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+
+    /// Insert a value for the specified range, like [`SegmentMap::insert`].
+    pub fn insert<R>(&mut self, range: R, value: V) -> Option<SegmentMap<K, V>>
+    where
+        R: RangeBounds<K>,
+        K: Clone,
+        V: Clone + Eq,
+    {
+        let mut tracker = UndoTracker {
+            log: &mut self.log,
+            removed: MaybeMap::Uninitialized,
+        };
+        self.map
+            .insert_internal(Segment::from(&range), value, &mut tracker);
+        tracker.removed.into()
+    }
+
+    /// Set a value for the specified range, like [`SegmentMap::set`].
+    pub fn set<R>(&mut self, range: R, value: V)
+    where
+        R: RangeBounds<K>,
+        K: Clone,
+        V: Clone + Eq,
+    {
+        let mut tracker = UndoTracker {
+            log: &mut self.log,
+            removed: MaybeMap::Never,
+        };
+        self.map
+            .insert_internal(Segment::from(&range), value, &mut tracker);
+    }
+
+    /// Insert a value for empty regions in the specified range, like
+    /// [`SegmentMap::insert_in_gaps`].
+    pub fn insert_in_gaps<R>(&mut self, range: R, value: V)
+    where
+        R: RangeBounds<K>,
+        K: Clone,
+        V: Clone + Eq,
+    {
+        let mut tracker = UndoTracker {
+            log: &mut self.log,
+            removed: MaybeMap::Never,
+        };
+        self.map
+            .insert_in_gaps_internal(Segment::from(&range), value, &mut tracker);
+    }
+
+    /// Remove all values in a given range, returning the removed values, like
+    /// [`SegmentMap::remove`].
+    pub fn remove<R>(&mut self, range: R) -> Option<SegmentMap<K, V>>
+    where
+        R: RangeBounds<K>,
+        K: Clone,
+        V: Clone,
+    {
+        let mut tracker = UndoTracker {
+            log: &mut self.log,
+            removed: MaybeMap::Uninitialized,
+        };
+        self.map
+            .remove_internal(Segment::from(&range), &mut tracker);
+        tracker.removed.into()
+    }
+
+    /// Remove all values in a given range, like [`SegmentMap::clear_range`].
+    pub fn clear_range<R>(&mut self, range: R)
+    where
+        R: RangeBounds<K>,
+        K: Clone,
+        V: Clone,
+    {
+        let mut tracker = UndoTracker {
+            log: &mut self.log,
+            removed: MaybeMap::Never,
+        };
+        self.map
+            .remove_internal(Segment::from(&range), &mut tracker);
+    }
+}
+
+impl<K: Ord, V> Deref for Transaction<'_, K, V> {
+    type Target = SegmentMap<K, V>;
+    fn deref(&self) -> &SegmentMap<K, V> {
+        self.map
+    }
+}
+
+impl<K: Ord, V> Drop for Transaction<'_, K, V> {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.rollback_to(self.start());
+        }
+    }
+}
+
+impl<K: Ord + Debug, V: Debug> Debug for Transaction<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("map", &self.map.map)
+            .field("changes", &self.log.len())
+            .finish()
+    }
+}
@@ -0,0 +1,395 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{fmt, ops::Bound::*};
+
+use super::Key;
+use crate::{
+    segment::{End, Start},
+    Segment, SegmentMap,
+};
+
+/// Error returned by the checked key transforms (like
+/// [`SegmentMap::map_keys_monotonic_checked`]) when the given function does
+/// not preserve (or exactly reverse) the order of the stored keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NotMonotonic;
+
+impl fmt::Display for NotMonotonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("key transform is not monotonic")
+    }
+}
+
+impl<K, V> SegmentMap<K, V> {
+    /// Convert every key in the map with an order-preserving function.
+    ///
+    /// Since the order of segments is unchanged, the internal tree is rebuilt
+    /// directly (in linear time) without going through insertion.
+    ///
+    /// `f` must be monotonically increasing on the stored bounds (that is,
+    /// `a < b` implies `f(a) < f(b)`). This is not checked! A function that
+    /// reorders keys will produce a map with overlapping or misplaced
+    /// segments, which is a logic error. Use
+    /// [`SegmentMap::map_keys_monotonic_checked`] if `f` can't be trusted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut micros = SegmentMap::new();
+    /// micros.set(0u64..10, "a");
+    /// micros.set(10..25, "b");
+    ///
+    /// let nanos = micros.map_keys_monotonic(|t| t * 1000);
+    /// assert!(nanos.into_iter().eq(vec![
+    ///     (Segment::from(0..10_000), "a"),
+    ///     (Segment::from(10_000..25_000), "b"),
+    /// ]));
+    /// ```
+    pub fn map_keys_monotonic<U, F>(self, mut f: F) -> SegmentMap<U, V>
+    where
+        U: Ord,
+        F: FnMut(K) -> U,
+    {
+        SegmentMap {
+            map: self
+                .map
+                .into_iter()
+                .map(|(key, value)| (Key(map_segment(key.0, &mut f)), value))
+                .collect(),
+            store: Vec::new(),
+        }
+    }
+
+    /// Convert every key in the map with an order-preserving function,
+    /// checking that the order of segments really is preserved.
+    ///
+    /// Segments that `f` causes to touch are coalesced if they have the same
+    /// value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NotMonotonic`] if any converted segment is backwards (or
+    /// empty), or overlaps the previous segment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// use segmap::map::NotMonotonic;
+    ///
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(20..30, "b");
+    ///
+    /// let shifted = map.clone().map_keys_monotonic_checked(|t| t - 5).unwrap();
+    /// assert_eq!(shifted.get(&-5), Some(&"a"));
+    ///
+    /// assert_eq!(
+    ///     map.map_keys_monotonic_checked(|t| t % 15),
+    ///     Err(NotMonotonic)
+    /// );
+    /// ```
+    pub fn map_keys_monotonic_checked<U, F>(
+        self,
+        mut f: F,
+    ) -> Result<SegmentMap<U, V>, NotMonotonic>
+    where
+        U: Ord,
+        V: Eq,
+        F: FnMut(K) -> U,
+    {
+        collect_checked(
+            self.map
+                .into_iter()
+                .map(|(key, value)| (map_segment(key.0, &mut f), value)),
+        )
+        .ok_or(NotMonotonic)
+    }
+
+    /// Convert every key in the map with an order-reversing function.
+    ///
+    /// Segments are mirrored (so the start of a segment becomes the end of
+    /// the converted one) and stored in reverse order. Like
+    /// [`SegmentMap::map_keys_monotonic`], the internal tree is rebuilt
+    /// directly in linear time.
+    ///
+    /// `f` must be monotonically decreasing on the stored bounds (that is,
+    /// `a < b` implies `f(a) > f(b)`). This is not checked! Use
+    /// [`SegmentMap::map_keys_decreasing_checked`] if `f` can't be trusted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(10.., "b");
+    ///
+    /// let negated = map.map_keys_decreasing(|t: i32| -t);
+    /// assert!(negated.into_iter().eq(vec![
+    ///     (Segment::from(..=-10), "b"),
+    ///     (Segment::new(Bound::Excluded(-10), Bound::Included(0)), "a"),
+    /// ]));
+    /// ```
+    pub fn map_keys_decreasing<U, F>(self, mut f: F) -> SegmentMap<U, V>
+    where
+        U: Ord,
+        F: FnMut(K) -> U,
+    {
+        SegmentMap {
+            map: self
+                .map
+                .into_iter()
+                .rev()
+                .map(|(key, value)| (Key(mirror_segment(key.0, &mut f)), value))
+                .collect(),
+            store: Vec::new(),
+        }
+    }
+
+    /// Convert every key in the map with an order-reversing function,
+    /// checking that the order of segments really is reversed.
+    ///
+    /// Segments that `f` causes to touch are coalesced if they have the same
+    /// value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NotMonotonic`] if any converted segment is backwards (or
+    /// empty), or overlaps its neighbour.
+    pub fn map_keys_decreasing_checked<U, F>(
+        self,
+        mut f: F,
+    ) -> Result<SegmentMap<U, V>, NotMonotonic>
+    where
+        U: Ord,
+        V: Eq,
+        F: FnMut(K) -> U,
+    {
+        collect_checked(
+            self.map
+                .into_iter()
+                .rev()
+                .map(|(key, value)| (mirror_segment(key.0, &mut f), value)),
+        )
+        .ok_or(NotMonotonic)
+    }
+}
+
+impl<K, V> SegmentMap<K, V> {
+    /// Convert every value in the map, coalescing neighbouring segments whose
+    /// converted values are equal.
+    ///
+    /// This takes a single linear pass over the map (without going through
+    /// insertion), and moves keys rather than cloning them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut status = SegmentMap::new();
+    /// status.set(0..10, "running");
+    /// status.set(10..20, "degraded");
+    /// status.set(20..30, "stopped");
+    ///
+    /// let is_up = status.map_values(|s| s != "stopped");
+    /// assert!(is_up.into_iter().eq(vec![
+    ///     (Segment::from(0..20), true),
+    ///     (Segment::from(20..30), false),
+    /// ]));
+    /// ```
+    pub fn map_values<U, F>(self, mut f: F) -> SegmentMap<K, U>
+    where
+        K: Ord,
+        U: Eq,
+        F: FnMut(V) -> U,
+    {
+        collect_coalesced(self.map.into_iter().map(|(key, value)| (key.0, f(value))))
+    }
+
+    /// Convert every value in the map, dropping segments for which `f`
+    /// returns `None` and coalescing neighbouring segments whose converted
+    /// values are equal.
+    ///
+    /// Like [`SegmentMap::map_values`], this takes a single linear pass.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, 1);
+    /// map.set(10..20, -2);
+    /// map.set(20..30, 3);
+    /// map.set(30..40, 4);
+    ///
+    /// let positive = map.filter_map_values(|v| if v > 0 { Some(v > 2) } else { None });
+    /// assert!(positive.into_iter().eq(vec![
+    ///     (Segment::from(0..10), false),
+    ///     (Segment::from(20..40), true),
+    /// ]));
+    /// ```
+    pub fn filter_map_values<U, F>(self, mut f: F) -> SegmentMap<K, U>
+    where
+        K: Ord,
+        U: Eq,
+        F: FnMut(V) -> Option<U>,
+    {
+        collect_coalesced(
+            self.map
+                .into_iter()
+                .filter_map(|(key, value)| f(value).map(|value| (key.0, value))),
+        )
+    }
+
+    /// Modify every value in the map in place, then coalesce neighbouring
+    /// segments whose values have become equal.
+    ///
+    /// Unlike modifying values through [`SegmentMap::iter_mut`] or
+    /// [`SegmentMap::values_mut`], this keeps the map fully coalesced.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the panic is passed on. Values it already modified keep
+    /// their new values, the rest are left as they were, and the map is still
+    /// coalesced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, 1);
+    /// map.set(10..20, 2);
+    /// map.set(20..30, 3);
+    ///
+    /// map.map_values_in_place(|v| *v = (*v).min(2));
+    /// assert!(map.into_iter().eq(vec![
+    ///     (Segment::from(0..10), 1),
+    ///     (Segment::from(10..30), 2),
+    /// ]));
+    /// ```
+    pub fn map_values_in_place<F>(&mut self, f: F)
+    where
+        K: Ord,
+        V: Eq,
+        F: FnMut(&mut V),
+    {
+        // Coalesce afterwards even if `f` panics part way through
+        let guard = CoalesceOnDrop(self);
+        guard.0.map.values_mut().for_each(f);
+    }
+}
+
+/// Coalesces touching neighbours with equal values in a map when dropped,
+/// including while unwinding
+struct CoalesceOnDrop<'a, K: Ord, V: Eq>(&'a mut SegmentMap<K, V>);
+
+impl<K: Ord, V: Eq> Drop for CoalesceOnDrop<'_, K, V> {
+    fn drop(&mut self) {
+        let map = &mut self.0.map;
+        let mut pairs = map.iter().zip(map.iter().skip(1));
+        if pairs.any(|((before, a), (after, b))| a == b && before.0.touches(&after.0)) {
+            let segments = core::mem::take(map)
+                .into_iter()
+                .map(|(key, value)| (key.0, value));
+            *map = collect_coalesced(segments).map;
+        }
+    }
+}
+
+/// Apply `f` to each bound of `segment`, keeping bound types and directions
+fn map_segment<K, U, F: FnMut(K) -> U>(segment: Segment<K>, f: &mut F) -> Segment<U> {
+    Segment {
+        start: Start(map_bound(segment.start.0, f)),
+        end: End(map_bound(segment.end.0, f)),
+    }
+}
+
+/// Apply `f` to each bound of `segment`, swapping the start and end
+fn mirror_segment<K, U, F: FnMut(K) -> U>(segment: Segment<K>, f: &mut F) -> Segment<U> {
+    // Call `f` in order of the new segment's bounds
+    let start = map_bound(segment.end.0, f);
+    let end = map_bound(segment.start.0, f);
+    Segment {
+        start: Start(start),
+        end: End(end),
+    }
+}
+
+fn map_bound<K, U, F: FnMut(K) -> U>(bound: core::ops::Bound<K>, f: &mut F) -> core::ops::Bound<U> {
+    match bound {
+        Included(k) => Included(f(k)),
+        Excluded(k) => Excluded(f(k)),
+        Unbounded => Unbounded,
+    }
+}
+
+/// Collect segments (which should already be in order) into a map, checking
+/// that they are well-formed and coalescing touching neighbours.
+///
+/// Returns `None` if any segment is backwards, or overlaps the one before it.
+pub(crate) fn collect_checked<K, V, I>(iter: I) -> Option<SegmentMap<K, V>>
+where
+    K: Ord,
+    V: Eq,
+    I: Iterator<Item = (Segment<K>, V)>,
+{
+    let mut segments: Vec<(Segment<K>, V)> = Vec::new();
+    for (segment, value) in iter {
+        // Individual segments must still be increasing
+        if segment.start.cmp_end(&segment.end).is_gt() {
+            return None;
+        }
+
+        // And must come strictly after the previous one
+        if let Some((prev, _)) = segments.last() {
+            if !prev.end.cmp_start(&segment.start).is_lt() {
+                return None;
+            }
+        }
+        push_coalesced(&mut segments, segment, value);
+    }
+    Some(into_map(segments))
+}
+
+/// Collect in-order, non-overlapping segments into a map, coalescing touching
+/// neighbours with equal values.
+pub(crate) fn collect_coalesced<K, V, I>(iter: I) -> SegmentMap<K, V>
+where
+    K: Ord,
+    V: Eq,
+    I: Iterator<Item = (Segment<K>, V)>,
+{
+    let mut segments = Vec::with_capacity(iter.size_hint().0);
+    for (segment, value) in iter {
+        push_coalesced(&mut segments, segment, value);
+    }
+    into_map(segments)
+}
+
+/// Push a segment that comes after all of `segments`, merging it into the
+/// last one if they touch and have the same value.
+fn push_coalesced<K: Ord, V: Eq>(
+    segments: &mut Vec<(Segment<K>, V)>,
+    segment: Segment<K>,
+    value: V,
+) {
+    if let Some((prev, prev_value)) = segments.last_mut() {
+        if *prev_value == value && prev.touches(&segment) {
+            prev.end = segment.end;
+            return;
+        }
+    }
+    segments.push((segment, value));
+}
+
+fn into_map<K: Ord, V>(segments: Vec<(Segment<K>, V)>) -> SegmentMap<K, V> {
+    SegmentMap {
+        map: segments
+            .into_iter()
+            .map(|(segment, value)| (Key(segment), value))
+            .collect::<BTreeMap<_, _>>(),
+        store: Vec::new(),
+    }
+}
@@ -0,0 +1,366 @@
+use core::{
+    fmt::{self, Debug},
+    iter::FusedIterator,
+    ops::RangeBounds,
+};
+
+use super::{iterators::IterSubset, Key};
+use crate::{
+    segment::{End, Start},
+    Segment, SegmentMap, SegmentSet,
+};
+
+/// A mutable view of the part of a [`SegmentMap`] inside a window, which acts
+/// like a map of its own
+///
+/// Created by [`SegmentMap::view_mut`]. Reads only see the window (with
+/// segments clipped to it, like [`SegmentMap::iter_subset`]), and writes are
+/// clamped to it, so nothing outside the window is changed. Writes still
+/// follow the usual coalescing rules, so a segment inside the window can merge
+/// with a touching segment outside of it.
+///
+/// A view supports the lookups, iterators and range writes of a
+/// [`SegmentMap`] (reads return owned, clipped ranges rather than references
+/// to stored ones). Operations that rekey or restructure the whole map, like
+/// [`SegmentMap::split_off`], [`SegmentMap::append`] or
+/// [`SegmentMap::insert_space`], aren't available through a view.
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// let mut map = SegmentMap::new();
+/// map.set(0..100, "a");
+///
+/// let mut view = map.view_mut(10..20);
+/// assert!(view.iter().eq(vec![(Segment::from(10..20), &"a")]));
+///
+/// // Clamped to 10..20
+/// view.set(15..30, "b");
+/// view.clear_range(..12);
+/// assert_eq!(view.len(), 2);
+///
+/// assert!(map.iter().eq(vec![
+///     (&Segment::from(0..10), &"a"),
+///     (&Segment::from(12..15), &"a"),
+///     (&Segment::from(15..20), &"b"),
+///     (&Segment::from(20..100), &"a"),
+/// ]));
+/// ```
+pub struct ViewMut<'a, K, V> {
+    map: &'a mut SegmentMap<K, V>,
+    window: Segment<K>,
+}
+
+impl<K, V> SegmentMap<K, V> {
+    /// Get a [`ViewMut`] of the part of the map inside `window`.
+    pub fn view_mut<R>(&mut self, window: R) -> ViewMut<'_, K, V>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+    {
+        ViewMut {
+            map: self,
+            window: Segment::from(&window),
+        }
+    }
+}
+
+impl<K: Clone + Ord, V> ViewMut<'_, K, V> {
+    /// The window this view covers
+    pub fn window(&self) -> &Segment<K> {
+        &self.window
+    }
+
+    /// Returns a reference to the value corresponding to the given point,
+    /// if the point is inside the window and covered by a range in the map.
+    pub fn get(&self, at: &K) -> Option<&V> {
+        self.get_range_value(at).map(|(_range, value)| value)
+    }
+
+    /// Returns the range (clipped to the window) and value covering the given
+    /// point, if any.
+    pub fn get_range_value(&self, at: &K) -> Option<(Segment<K>, &V)> {
+        if !self.window.contains(at) {
+            return None;
+        }
+        self.map
+            .get_range_value(at)
+            .map(|(range, value)| (range.intersection(&self.window).unwrap(), value))
+    }
+
+    /// Returns `true` if the point is inside the window and covered by a
+    /// range in the map.
+    pub fn contains(&self, at: &K) -> bool {
+        self.get_range_value(at).is_some()
+    }
+
+    /// Returns the first range (clipped to the window) in the view and its
+    /// value, if any.
+    pub fn first(&self) -> Option<(Segment<K>, &V)> {
+        self.iter().next()
+    }
+
+    /// Returns the last range (clipped to the window) in the view and its
+    /// value, if any.
+    pub fn last(&self) -> Option<(Segment<K>, &V)> {
+        self.iter().next_back()
+    }
+
+    /// Gets an iterator over the ranges (clipped to the window) and values in
+    /// the view.
+    pub fn iter(&self) -> IterSubset<'_, K, V> {
+        self.map.iter_subset(&self.window)
+    }
+
+    /// Gets an iterator over the ranges in the view that overlap `range`,
+    /// like [`SegmentMap::iter_in`].
+    ///
+    /// Ranges are clipped to the window, but not to `range`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(10..20, "b");
+    /// map.set(20..30, "c");
+    ///
+    /// let view = map.view_mut(5..25);
+    /// assert!(view.iter_in(12..).eq(vec![
+    ///     (Segment::from(10..20), &"b"),
+    ///     (Segment::from(20..25), &"c"),
+    /// ]));
+    /// assert!(view.iter_in(30..).next().is_none());
+    /// ```
+    pub fn iter_in<R>(&self, range: R) -> IterSubset<'_, K, V>
+    where
+        R: RangeBounds<K>,
+    {
+        let range = self.clamp(&range);
+        self.map.iter_subset_in(range.as_ref(), self.window.clone())
+    }
+
+    /// Gets an iterator over the parts of the window not covered by any
+    /// range.
+    ///
+    /// Unlike [`SegmentMap::iter_gaps`], this includes the space between the
+    /// ends of the window and the first and last ranges in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = SegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(20..30, "b");
+    ///
+    /// let view = map.view_mut(5..40);
+    /// assert!(view.iter_gaps().eq(vec![Segment::from(10..20), Segment::from(30..40)]));
+    /// ```
+    pub fn iter_gaps(&self) -> ViewGaps<'_, K, V> {
+        ViewGaps {
+            iter: self.iter(),
+            start: Some(self.window.start.clone()),
+            end: self.window.end.clone(),
+        }
+    }
+
+    /// Get the parts of the window not covered by any range, as a
+    /// [`SegmentSet`].
+    ///
+    /// Since gaps may end at the window's bounds, they are owned (unlike
+    /// [`SegmentMap::gaps`]).
+    pub fn gaps(&self) -> SegmentSet<K> {
+        SegmentSet {
+            map: SegmentMap {
+                map: self.iter_gaps().map(|gap| (Key(gap), ())).collect(),
+                store: alloc::vec::Vec::new(),
+            },
+        }
+    }
+
+    /// Returns the number of (clipped) ranges in the view.
+    ///
+    /// Unlike [`SegmentMap::len`], this takes linear time in the number of
+    /// ranges in the window.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if no ranges overlap the window.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Insert a value for the specified range (clamped to the window),
+    /// returning any values that were overwritten, like
+    /// [`SegmentMap::insert`].
+    pub fn insert<R>(&mut self, range: R, value: V) -> Option<SegmentMap<K, V>>
+    where
+        R: RangeBounds<K>,
+        V: Clone + Eq,
+    {
+        let range = self.clamp(&range)?;
+        self.map.insert(range, value)
+    }
+
+    /// Set a value for the specified range (clamped to the window), like
+    /// [`SegmentMap::set`].
+    pub fn set<R>(&mut self, range: R, value: V)
+    where
+        R: RangeBounds<K>,
+        V: Clone + Eq,
+    {
+        if let Some(range) = self.clamp(&range) {
+            self.map.set(range, value);
+        }
+    }
+
+    /// Insert a value into the empty regions of the specified range (clamped
+    /// to the window), like [`SegmentMap::insert_in_gaps`].
+    pub fn insert_in_gaps<R>(&mut self, range: R, value: V)
+    where
+        R: RangeBounds<K>,
+        V: Clone + Eq,
+    {
+        if let Some(range) = self.clamp(&range) {
+            self.map.insert_in_gaps(range, value);
+        }
+    }
+
+    /// Insert a value for the specified range (clamped to the window), only
+    /// if there are no existing overlapping ranges, like
+    /// [`SegmentMap::insert_if_empty`]. Returns the given value if it wasn't
+    /// inserted (including when `range` is entirely outside the window).
+    pub fn insert_if_empty<R>(&mut self, range: R, value: V) -> Option<V>
+    where
+        R: RangeBounds<K>,
+        V: Clone + Eq,
+    {
+        match self.clamp(&range) {
+            Some(range) => self.map.insert_if_empty(range, value),
+            None => Some(value),
+        }
+    }
+
+    /// Remove all values in a given range (clamped to the window), returning
+    /// the removed values, like [`SegmentMap::remove`].
+    pub fn remove<R>(&mut self, range: R) -> Option<SegmentMap<K, V>>
+    where
+        R: RangeBounds<K>,
+        V: Clone,
+    {
+        let range = self.clamp(&range)?;
+        self.map.remove(range)
+    }
+
+    /// Remove all values in a given range (clamped to the window), like
+    /// [`SegmentMap::clear_range`].
+    pub fn clear_range<R>(&mut self, range: R)
+    where
+        R: RangeBounds<K>,
+        V: Clone,
+    {
+        if let Some(range) = self.clamp(&range) {
+            self.map.clear_range(range);
+        }
+    }
+
+    /// Remove everything inside the window, leaving the rest of the map
+    /// untouched.
+    pub fn clear(&mut self)
+    where
+        V: Clone,
+    {
+        self.map.clear_range(&self.window);
+    }
+
+    /// Copy the contents of the view into a new `SegmentMap`
+    pub fn to_segment_map(&self) -> SegmentMap<K, V>
+    where
+        V: Clone,
+    {
+        SegmentMap {
+            map: self
+                .iter()
+                .map(|(range, value)| (Key(range), value.clone()))
+                .collect(),
+            store: alloc::vec::Vec::new(),
+        }
+    }
+
+    fn clamp<R: RangeBounds<K>>(&self, range: &R) -> Option<Segment<K>> {
+        self.window.intersection(&Segment::from(range))
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for ViewMut<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViewMut")
+            .field("window", &self.window)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An iterator over the uncovered parts of a [`ViewMut`]'s window
+///
+/// This `struct` is created by the [`iter_gaps`] method on [`ViewMut`].
+/// See its documentation for more.
+///
+/// [`iter_gaps`]: ViewMut::iter_gaps
+pub struct ViewGaps<'a, K, V> {
+    iter: IterSubset<'a, K, V>,
+
+    /// Where the next gap could start, or `None` once the window is covered
+    /// to its end
+    start: Option<Start<K>>,
+    end: End<K>,
+}
+
+impl<K: Clone + Ord, V> Iterator for ViewGaps<'_, K, V> {
+    type Item = Segment<K>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.start.take()?;
+            let gap = match self.iter.next() {
+                Some((range, _)) => {
+                    self.start = range.bound_after().map(|after| after.cloned());
+                    match range.bound_before() {
+                        Some(before) => Segment {
+                            start,
+                            end: before.cloned(),
+                        },
+                        None => continue,
+                    }
+                }
+                None => Segment {
+                    start,
+                    end: self.end.clone(),
+                },
+            };
+
+            // Ranges touching each other (or the window's bounds) leave no
+            // gap between them
+            if !gap.end.cmp_start(&gap.start).is_lt() {
+                return Some(gap);
+            }
+        }
+    }
+}
+impl<K: Clone + Ord, V> FusedIterator for ViewGaps<'_, K, V> {}
+impl<K: Clone, V> Clone for ViewGaps<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            iter: self.iter.clone(),
+            start: self.start.clone(),
+            end: self.end.clone(),
+        }
+    }
+}
+impl<K: Clone + Ord + Debug, V> Debug for ViewGaps<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
@@ -0,0 +1,880 @@
+//! An immutable [`SegmentMap`] with cheap snapshots
+//!
+//! [`PersistentSegmentMap`] stores its segments in a balanced (AVL) tree of
+//! reference counted nodes. Updates copy only the path to the changed nodes,
+//! sharing everything else with the previous version, so every version can be
+//! kept (and handed to other threads) at the cost of `O(log n)` new nodes per
+//! update.
+
+use alloc::{sync::Arc, vec::Vec};
+use core::{
+    fmt::{self, Debug},
+    iter::FusedIterator,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::{
+    map::{collect_coalesced, Key},
+    segment::Start,
+    Segment, SegmentMap, SegmentSet,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A persistent map of non-overlapping ranges to values, with the same
+/// insertion and coalescing rules as [`SegmentMap`]
+///
+/// Updating methods take `&self` and return a new map, leaving the original
+/// untouched. Both share any nodes that weren't changed, so cloning is `O(1)`
+/// and updates are `O(log n)` (plus the number of removed segments, when they
+/// are returned).
+///
+/// Iterators borrow from the map: [`PersistentSegmentMap::iter`],
+/// [`PersistentSegmentMap::iter_in`] and [`PersistentSegmentMap::iter_gaps`]
+/// work like their [`SegmentMap`] counterparts. Since nodes may be shared
+/// with other versions, there's no owning iterator (convert to a
+/// [`SegmentMap`] to take the values).
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// let v1 = PersistentSegmentMap::new().set(0..100, "a");
+/// let v2 = v1.set(40..60, "b");
+/// let v3 = v2.clear_range(50..);
+///
+/// assert_eq!(v1.get(&50), Some(&"a"));
+/// assert_eq!(v2.get(&50), Some(&"b"));
+/// assert_eq!(v3.get(&50), None);
+///
+/// assert!(v3.iter().eq(vec![
+///     (&Segment::from(0..40), &"a"),
+///     (&Segment::from(40..50), &"b"),
+/// ]));
+/// ```
+pub struct PersistentSegmentMap<K, V> {
+    root: Tree<K, V>,
+}
+
+type Tree<K, V> = Option<Arc<Node<K, V>>>;
+
+#[derive(Clone)]
+struct Node<K, V> {
+    range: Segment<K>,
+    value: V,
+    left: Tree<K, V>,
+    right: Tree<K, V>,
+    height: u8,
+    len: usize,
+}
+
+impl<K, V> PersistentSegmentMap<K, V> {
+    /// Makes a new, empty `PersistentSegmentMap`.
+    pub fn new() -> Self {
+        PersistentSegmentMap { root: None }
+    }
+
+    /// Returns the number of ranges in the map.
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    /// Returns `true` if the map contains no ranges.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns a reference to the value corresponding to the given point,
+    /// if the point is covered by any range in the map.
+    pub fn get(&self, at: &K) -> Option<&V>
+    where
+        K: Clone + Ord,
+    {
+        self.get_range_value(at).map(|(_range, value)| value)
+    }
+
+    /// Returns the range-value pair (as a pair of references) corresponding
+    /// to the given point, if the point is covered by any range in the map.
+    pub fn get_range_value(&self, at: &K) -> Option<(&Segment<K>, &V)>
+    where
+        K: Clone + Ord,
+    {
+        // Find the last range starting at or before `at`
+        let start = Start(Bound::Included(at.clone()));
+        let mut candidate = None;
+        let mut tree = &self.root;
+        while let Some(node) = tree {
+            if node.range.start <= start {
+                candidate = Some(node);
+                tree = &node.right;
+            } else {
+                tree = &node.left;
+            }
+        }
+        candidate
+            .filter(|node| node.range.contains(at))
+            .map(|node| (&node.range, &node.value))
+    }
+
+    /// Returns `true` if any range in the map covers the specified point.
+    pub fn contains(&self, at: &K) -> bool
+    where
+        K: Clone + Ord,
+    {
+        self.get_range_value(at).is_some()
+    }
+
+    /// Gets an iterator over all pairs of key range and value, ordered by key
+    /// range.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter::over(&self.root)
+    }
+
+    /// Gets an iterator over all pairs of key range and value, where the key
+    /// range overlaps the given range, like [`SegmentMap::iter_in`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let map = PersistentSegmentMap::new()
+    ///     .set(0..10, "a")
+    ///     .set(10..20, "b")
+    ///     .set(30..40, "c");
+    ///
+    /// assert!(map.iter_in(5..25).eq(vec![
+    ///     (&Segment::from(0..10), &"a"),
+    ///     (&Segment::from(10..20), &"b"),
+    /// ]));
+    /// assert!(map.iter_in(35..).rev().eq(vec![(&Segment::from(30..40), &"c")]));
+    /// assert_eq!(map.iter_in(22..28).next(), None);
+    /// ```
+    pub fn iter_in<R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+    {
+        let range = Segment::from(&range);
+        Iter::between(
+            &self.root,
+            |r| r.start < range.start && !r.overlaps(&range),
+            |r| !range.end.cmp_start(&r.start).is_lt(),
+        )
+    }
+
+    /// Gets an iterator over all maximally-sized gaps between ranges in the
+    /// map, like [`SegmentMap::iter_gaps`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let map = PersistentSegmentMap::new()
+    ///     .set(0..10, "a")
+    ///     .set(10..20, "b")
+    ///     .set(30..40, "c");
+    ///
+    /// assert!(map.iter_gaps().eq(vec![Segment::from(&20..&30)]));
+    /// ```
+    pub fn iter_gaps(&self) -> Gaps<'_, K, V> {
+        let mut iter = self.iter();
+        Gaps {
+            front: iter.next().map(|(range, _)| range),
+            back: iter.next_back().map(|(range, _)| range),
+            iter,
+        }
+    }
+
+    /// Get all gaps between ranges in the map, as a set, like
+    /// [`SegmentMap::gaps`].
+    pub fn gaps(&self) -> SegmentSet<&K>
+    where
+        K: Ord,
+    {
+        SegmentSet {
+            map: SegmentMap {
+                map: self.iter_gaps().map(|r| (Key(r), ())).collect(),
+                store: Vec::new(),
+            },
+        }
+    }
+
+    /// Returns a new map with `value` set for the specified range, like
+    /// [`SegmentMap::set`].
+    pub fn set<R>(&self, range: R, value: V) -> Self
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.update(Segment::from(&range), Some(value), None)
+    }
+
+    /// Returns a new map with `value` set for the specified range, along with
+    /// the values that were overwritten, like [`SegmentMap::insert`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let old = PersistentSegmentMap::new().set(0..10, "a");
+    /// let (new, removed) = old.insert(5..15, "b");
+    ///
+    /// assert!(removed.unwrap().iter().eq(vec![(&Segment::from(5..10), &"a")]));
+    /// assert_eq!(new.len(), 2);
+    /// assert_eq!(old.len(), 1);
+    /// ```
+    pub fn insert<R>(&self, range: R, value: V) -> (Self, Option<SegmentMap<K, V>>)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let mut removed = Vec::new();
+        let map = self.update(Segment::from(&range), Some(value), Some(&mut removed));
+        (map, into_removed(removed))
+    }
+
+    /// Returns a new map with all values in a given range removed, along with
+    /// the removed values, like [`SegmentMap::remove`].
+    pub fn remove<R>(&self, range: R) -> (Self, Option<SegmentMap<K, V>>)
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let mut removed = Vec::new();
+        let map = self.update(Segment::from(&range), None, Some(&mut removed));
+        (map, into_removed(removed))
+    }
+
+    /// Returns a new map with all values in a given range removed, like
+    /// [`SegmentMap::clear_range`].
+    pub fn clear_range<R>(&self, range: R) -> Self
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.update(Segment::from(&range), None, None)
+    }
+
+    /// Shared implementation of the updating methods: clear `range`, then
+    /// store `value` over it (if any), coalescing with neighbours.
+    ///
+    /// The tree is split into the ranges starting before `range`, those
+    /// starting inside it, and those after it. Only the ranges on either edge
+    /// of those splits need adjusting; everything in the middle is dropped
+    /// (after being reported to `removed`, if needed).
+    fn update(
+        &self,
+        range: Segment<K>,
+        value: Option<V>,
+        mut removed: Option<&mut Vec<(Segment<K>, V)>>,
+    ) -> Self
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let (left, rest) = split_by(self.root.clone(), &|r| r.start < range.start);
+        let (inside, right) = split_by(rest, &|r| !range.end.cmp_start(&r.start).is_lt());
+        let (mut left, before) = pop_last(left);
+        let (inside, last) = pop_last(inside);
+        let (mut right, after) = pop_first(right);
+
+        // The part of an overwritten range that extends past the new one
+        let mut tail = None;
+        let mut new = range.clone();
+        let tracking = removed.is_some();
+        let mut report = |piece: &Segment<K>, piece_value: &V| {
+            if let Some(removed) = removed.as_mut() {
+                if value.as_ref() != Some(piece_value) {
+                    let start = core::cmp::max(&piece.start, &range.start).clone();
+                    let end = core::cmp::min(&piece.end, &range.end).clone();
+                    removed.push((Segment { start, end }, piece_value.clone()));
+                }
+            }
+        };
+
+        // A range starting before the new one may need merging or trimming
+        if let Some((before, before_value)) = before {
+            if value.as_ref() == Some(&before_value) && before.touches(&range) {
+                new.start = before.start;
+                if before.end > new.end {
+                    new.end = before.end;
+                }
+            } else if before.overlaps(&range) {
+                report(&before, &before_value);
+                if before.end > range.end {
+                    // Neither bound is unbounded, since `range` is inside
+                    // `before`
+                    tail = Some((
+                        Segment {
+                            start: range.end.after().unwrap().cloned(),
+                            end: before.end.clone(),
+                        },
+                        before_value.clone(),
+                    ));
+                }
+                let trimmed = Segment {
+                    start: before.start,
+                    end: range.start.before().unwrap().cloned(),
+                };
+                left = push_back(left, trimmed, before_value);
+            } else {
+                left = push_back(left, before, before_value);
+            }
+        }
+
+        // Ranges starting inside the new one are removed, except for the part
+        // of the last one that extends past it
+        if tracking {
+            for (piece, piece_value) in Iter::over(&inside) {
+                report(piece, piece_value);
+            }
+        }
+        if let Some((last, last_value)) = last {
+            report(&last, &last_value);
+            if last.end > range.end {
+                if value.as_ref() == Some(&last_value) {
+                    new.end = last.end;
+                } else {
+                    // Not unbounded, since less than `last.end`
+                    let start = range.end.after().unwrap().cloned();
+                    tail = Some((
+                        Segment {
+                            start,
+                            end: last.end,
+                        },
+                        last_value,
+                    ));
+                }
+            }
+        }
+
+        // The next range may need merging
+        if let Some((after, after_value)) = after {
+            if value.as_ref() == Some(&after_value) && after.touches(&new) {
+                new.end = after.end;
+            } else {
+                right = push_front(after, after_value, right);
+            }
+        }
+
+        if let Some(value) = value {
+            left = push_back(left, new, value);
+        }
+        if let Some((piece, piece_value)) = tail {
+            right = push_front(piece, piece_value, right);
+        }
+        PersistentSegmentMap {
+            root: concat(left, right),
+        }
+    }
+}
+
+/// Collect removed pieces into the map returned by `insert` and `remove`
+fn into_removed<K: Ord, V: Eq>(removed: Vec<(Segment<K>, V)>) -> Option<SegmentMap<K, V>> {
+    if removed.is_empty() {
+        None
+    } else {
+        Some(collect_coalesced(removed.into_iter()))
+    }
+}
+
+// Tree operations
+//
+// These follow the "join-based" formulation of balanced trees: everything is
+// built from `join` (concatenate two trees around a middle node, rebalancing
+// along one spine) and `split_by` (cut a tree where a monotone predicate
+// changes), both `O(log n)`.
+
+fn height<K, V>(tree: &Tree<K, V>) -> u8 {
+    tree.as_ref().map_or(0, |node| node.height)
+}
+
+fn len<K, V>(tree: &Tree<K, V>) -> usize {
+    tree.as_ref().map_or(0, |node| node.len)
+}
+
+fn node<K, V>(left: Tree<K, V>, range: Segment<K>, value: V, right: Tree<K, V>) -> Tree<K, V> {
+    Some(Arc::new(Node {
+        height: core::cmp::max(height(&left), height(&right)) + 1,
+        len: len(&left) + len(&right) + 1,
+        range,
+        value,
+        left,
+        right,
+    }))
+}
+
+/// Take apart a node, only copying it if it's shared with another version
+#[allow(clippy::type_complexity)]
+fn expose<K: Clone, V: Clone>(node: Arc<Node<K, V>>) -> (Tree<K, V>, Segment<K>, V, Tree<K, V>) {
+    let node = Arc::try_unwrap(node).unwrap_or_else(|node| (*node).clone());
+    (node.left, node.range, node.value, node.right)
+}
+
+fn rotate_left<K: Clone, V: Clone>(tree: Tree<K, V>) -> Tree<K, V> {
+    let (left, range, value, right) = expose(tree.unwrap());
+    let (right_left, right_range, right_value, right_right) = expose(right.unwrap());
+    node(
+        node(left, range, value, right_left),
+        right_range,
+        right_value,
+        right_right,
+    )
+}
+
+fn rotate_right<K: Clone, V: Clone>(tree: Tree<K, V>) -> Tree<K, V> {
+    let (left, range, value, right) = expose(tree.unwrap());
+    let (left_left, left_range, left_value, left_right) = expose(left.unwrap());
+    node(
+        left_left,
+        left_range,
+        left_value,
+        node(left_right, range, value, right),
+    )
+}
+
+/// Join `left` and `right` around a middle node, where every range in `left`
+/// is before `range` and every range in `right` is after it
+fn join<K: Clone, V: Clone>(
+    left: Tree<K, V>,
+    range: Segment<K>,
+    value: V,
+    right: Tree<K, V>,
+) -> Tree<K, V> {
+    if height(&left) > height(&right) + 1 {
+        join_right(left, range, value, right)
+    } else if height(&right) > height(&left) + 1 {
+        join_left(left, range, value, right)
+    } else {
+        node(left, range, value, right)
+    }
+}
+
+/// Join when `left` is taller: walk down its right spine
+fn join_right<K: Clone, V: Clone>(
+    left: Tree<K, V>,
+    range: Segment<K>,
+    value: V,
+    right: Tree<K, V>,
+) -> Tree<K, V> {
+    let (left_left, left_range, left_value, left_right) = expose(left.unwrap());
+    if height(&left_right) <= height(&right) + 1 {
+        let joined = node(left_right, range, value, right);
+        if height(&joined) <= height(&left_left) + 1 {
+            node(left_left, left_range, left_value, joined)
+        } else {
+            rotate_left(node(
+                left_left,
+                left_range,
+                left_value,
+                rotate_right(joined),
+            ))
+        }
+    } else {
+        let joined = join_right(left_right, range, value, right);
+        let balanced = height(&joined) <= height(&left_left) + 1;
+        let joined = node(left_left, left_range, left_value, joined);
+        if balanced {
+            joined
+        } else {
+            rotate_left(joined)
+        }
+    }
+}
+
+/// Join when `right` is taller: walk down its left spine
+fn join_left<K: Clone, V: Clone>(
+    left: Tree<K, V>,
+    range: Segment<K>,
+    value: V,
+    right: Tree<K, V>,
+) -> Tree<K, V> {
+    let (right_left, right_range, right_value, right_right) = expose(right.unwrap());
+    if height(&right_left) <= height(&left) + 1 {
+        let joined = node(left, range, value, right_left);
+        if height(&joined) <= height(&right_right) + 1 {
+            node(joined, right_range, right_value, right_right)
+        } else {
+            rotate_right(node(
+                rotate_left(joined),
+                right_range,
+                right_value,
+                right_right,
+            ))
+        }
+    } else {
+        let joined = join_left(left, range, value, right_left);
+        let balanced = height(&joined) <= height(&right_right) + 1;
+        let joined = node(joined, right_range, right_value, right_right);
+        if balanced {
+            joined
+        } else {
+            rotate_right(joined)
+        }
+    }
+}
+
+/// Split a tree into the ranges for which `in_left` is true, and the rest.
+/// `in_left` must be true for a prefix of the ranges.
+fn split_by<K: Clone, V: Clone>(
+    tree: Tree<K, V>,
+    in_left: &impl Fn(&Segment<K>) -> bool,
+) -> (Tree<K, V>, Tree<K, V>) {
+    match tree {
+        None => (None, None),
+        Some(tree) => {
+            let (left, range, value, right) = expose(tree);
+            if in_left(&range) {
+                let (right_left, right_right) = split_by(right, in_left);
+                (join(left, range, value, right_left), right_right)
+            } else {
+                let (left_left, left_right) = split_by(left, in_left);
+                (left_left, join(left_right, range, value, right))
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn pop_last<K: Clone, V: Clone>(tree: Tree<K, V>) -> (Tree<K, V>, Option<(Segment<K>, V)>) {
+    match tree {
+        None => (None, None),
+        Some(tree) => {
+            let (left, range, value, right) = expose(tree);
+            if right.is_none() {
+                (left, Some((range, value)))
+            } else {
+                let (right, last) = pop_last(right);
+                (join(left, range, value, right), last)
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn pop_first<K: Clone, V: Clone>(tree: Tree<K, V>) -> (Tree<K, V>, Option<(Segment<K>, V)>) {
+    match tree {
+        None => (None, None),
+        Some(tree) => {
+            let (left, range, value, right) = expose(tree);
+            if left.is_none() {
+                (right, Some((range, value)))
+            } else {
+                let (left, first) = pop_first(left);
+                (join(left, range, value, right), first)
+            }
+        }
+    }
+}
+
+fn push_back<K: Clone, V: Clone>(tree: Tree<K, V>, range: Segment<K>, value: V) -> Tree<K, V> {
+    join(tree, range, value, None)
+}
+
+fn push_front<K: Clone, V: Clone>(range: Segment<K>, value: V, tree: Tree<K, V>) -> Tree<K, V> {
+    join(None, range, value, tree)
+}
+
+/// Concatenate two trees, where every range in `left` is before `right`
+fn concat<K: Clone, V: Clone>(left: Tree<K, V>, right: Tree<K, V>) -> Tree<K, V> {
+    match pop_last(left) {
+        (left, Some((range, value))) => join(left, range, value, right),
+        (_, None) => right,
+    }
+}
+
+/// Build a perfectly balanced tree from `len` sorted items
+fn build<K, V>(items: &mut impl Iterator<Item = (Segment<K>, V)>, len: usize) -> Tree<K, V> {
+    if len == 0 {
+        return None;
+    }
+    let left = build(items, len / 2);
+    let (range, value) = items.next().unwrap();
+    let right = build(items, len - len / 2 - 1);
+    node(left, range, value, right)
+}
+
+/// An iterator over the entries of a [`PersistentSegmentMap`], ordered by
+/// key range
+///
+/// See [`PersistentSegmentMap::iter`] and [`PersistentSegmentMap::iter_in`]
+pub struct Iter<'a, K, V> {
+    /// Nodes whose left subtree has been visited, but not the node itself
+    front: Vec<&'a Node<K, V>>,
+
+    /// Nodes whose right subtree has been visited from the back, but not the
+    /// node itself
+    back: Vec<&'a Node<K, V>>,
+
+    /// Both ends walk independently, so this is what stops them crossing
+    remaining: usize,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn over(tree: &'a Tree<K, V>) -> Self {
+        let mut iter = Iter {
+            front: Vec::new(),
+            back: Vec::new(),
+            remaining: len(tree),
+        };
+        iter.push_left(tree);
+        iter.push_right(tree);
+        iter
+    }
+
+    /// Iterate from the first range for which `before` is false to the last
+    /// range for which `until` is true. Both must hold for a prefix of the
+    /// ranges.
+    fn between(
+        tree: &'a Tree<K, V>,
+        before: impl Fn(&Segment<K>) -> bool,
+        until: impl Fn(&Segment<K>) -> bool,
+    ) -> Self {
+        let mut front = Vec::new();
+        let mut skipped = 0;
+        let mut node = tree;
+        while let Some(n) = node {
+            if before(&n.range) {
+                skipped += len(&n.left) + 1;
+                node = &n.right;
+            } else {
+                front.push(&**n);
+                node = &n.left;
+            }
+        }
+
+        let mut back = Vec::new();
+        let mut taken = 0;
+        let mut node = tree;
+        while let Some(n) = node {
+            if until(&n.range) {
+                taken += len(&n.left) + 1;
+                back.push(&**n);
+                node = &n.right;
+            } else {
+                node = &n.left;
+            }
+        }
+
+        Iter {
+            front,
+            back,
+            remaining: taken.saturating_sub(skipped),
+        }
+    }
+
+    fn push_left(&mut self, mut tree: &'a Tree<K, V>) {
+        while let Some(node) = tree {
+            self.front.push(node);
+            tree = &node.left;
+        }
+    }
+
+    fn push_right(&mut self, mut tree: &'a Tree<K, V>) {
+        while let Some(node) = tree {
+            self.back.push(node);
+            tree = &node.right;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a Segment<K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.front.pop()?;
+        self.push_left(&node.right);
+        self.remaining -= 1;
+        Some((&node.range, &node.value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.back.pop()?;
+        self.push_right(&node.left);
+        self.remaining -= 1;
+        Some((&node.range, &node.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            front: self.front.clone(),
+            back: self.back.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Debug for Iter<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// An iterator over the gaps between ranges of a [`PersistentSegmentMap`]
+///
+/// See [`PersistentSegmentMap::iter_gaps`]
+pub struct Gaps<'a, K, V> {
+    // The ranges on either side of the gaps left to iterate. If there's only
+    // one range left, it's always in `front`.
+    front: Option<&'a Segment<K>>,
+    iter: Iter<'a, K, V>,
+    back: Option<&'a Segment<K>>,
+}
+
+impl<'a, K: Ord, V> Gaps<'a, K, V> {
+    /// The gap between two ranges, or `None` if they touch
+    fn gap(before: &'a Segment<K>, after: &'a Segment<K>) -> Option<Segment<&'a K>> {
+        let gap = Segment {
+            start: before
+                .bound_after()
+                .expect("Unbounded internal range in PersistentSegmentMap"),
+            end: after
+                .bound_before()
+                .expect("Unbounded internal range in PersistentSegmentMap"),
+        };
+        if gap.end.cmp_start(&gap.start).is_lt() {
+            None
+        } else {
+            Some(gap)
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for Gaps<'a, K, V> {
+    type Item = Segment<&'a K>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let before = self.front?;
+            let after = match self.iter.next() {
+                Some((after, _)) => after,
+                None => self.back.take()?,
+            };
+            self.front = Some(after);
+            if let Some(gap) = Self::gap(before, after) {
+                return Some(gap);
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // At most one gap between each pair of ranges, but touching ranges
+        // have none
+        match (self.front, self.back) {
+            (Some(_), Some(_)) => (0, Some(self.iter.len() + 1)),
+            _ => (0, Some(0)),
+        }
+    }
+}
+impl<K: Ord, V> DoubleEndedIterator for Gaps<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            let after = self.back?;
+            self.back = self.iter.next_back().map(|(before, _)| before);
+            let before = self.back.or(self.front)?;
+            if let Some(gap) = Self::gap(before, after) {
+                return Some(gap);
+            }
+        }
+    }
+}
+impl<K: Ord, V> FusedIterator for Gaps<'_, K, V> {}
+
+impl<K, V> Clone for Gaps<'_, K, V> {
+    fn clone(&self) -> Self {
+        Gaps {
+            front: self.front,
+            iter: self.iter.clone(),
+            back: self.back,
+        }
+    }
+}
+impl<K: Debug + Ord, V> Debug for Gaps<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a PersistentSegmentMap<K, V> {
+    type Item = (&'a Segment<K>, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<K, V> Clone for PersistentSegmentMap<K, V> {
+    fn clone(&self) -> Self {
+        PersistentSegmentMap {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<K, V> Default for PersistentSegmentMap<K, V> {
+    fn default() -> Self {
+        PersistentSegmentMap::new()
+    }
+}
+
+impl<K, V> From<SegmentMap<K, V>> for PersistentSegmentMap<K, V> {
+    fn from(map: SegmentMap<K, V>) -> Self {
+        let len = map.len();
+        PersistentSegmentMap {
+            root: build(&mut map.into_iter(), len),
+        }
+    }
+}
+
+impl<K: Clone + Ord, V: Clone> From<&PersistentSegmentMap<K, V>> for SegmentMap<K, V> {
+    fn from(map: &PersistentSegmentMap<K, V>) -> Self {
+        SegmentMap {
+            map: map
+                .iter()
+                .map(|(range, value)| (Key(range.clone()), value.clone()))
+                .collect(),
+            store: Vec::new(),
+        }
+    }
+}
+
+impl<K: Clone + Ord, V: Clone> From<PersistentSegmentMap<K, V>> for SegmentMap<K, V> {
+    fn from(map: PersistentSegmentMap<K, V>) -> Self {
+        SegmentMap::from(&map)
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for PersistentSegmentMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for PersistentSegmentMap<K, V> {}
+
+impl<K: Debug, V: Debug> Debug for PersistentSegmentMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
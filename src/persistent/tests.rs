@@ -0,0 +1,119 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// Check heights, lengths and AVL balance of every node
+fn assert_balanced<K, V>(tree: &Tree<K, V>) -> (u8, usize) {
+    match tree {
+        None => (0, 0),
+        Some(node) => {
+            let (left_height, left_len) = assert_balanced(&node.left);
+            let (right_height, right_len) = assert_balanced(&node.right);
+            assert!(left_height.abs_diff(right_height) <= 1);
+            assert_eq!(node.height, left_height.max(right_height) + 1);
+            assert_eq!(node.len, left_len + right_len + 1);
+            (node.height, node.len)
+        }
+    }
+}
+
+#[test]
+fn updates_match_segment_map() {
+    let mut expected: SegmentMap<u32, u8> = SegmentMap::new();
+    let mut map = PersistentSegmentMap::new();
+    let mut versions = Vec::new();
+
+    // Deterministic mix of overlapping, touching and disjoint updates
+    let mut seed = 7u32;
+    for i in 0..400 {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        let start = (seed >> 8) % 200;
+        let end = start + 1 + (seed >> 20) % 30;
+        let value = (seed % 4) as u8;
+
+        let (next, removed) = match i % 5 {
+            0 => map.remove(start..end),
+            1 => (map.clear_range(start..=end), None),
+            2 => (map.set(start.., value), None),
+            _ => map.insert(start..end, value),
+        };
+        let expected_removed = match i % 5 {
+            0 => expected.remove(start..end),
+            1 => {
+                expected.clear_range(start..=end);
+                None
+            }
+            2 => {
+                expected.set(start.., value);
+                None
+            }
+            _ => expected.insert(start..end, value),
+        };
+        assert_eq!(removed, expected_removed);
+        assert_eq!(SegmentMap::from(&next), expected);
+        assert_balanced(&next.root);
+
+        versions.push((next.clone(), expected.clone()));
+        map = next;
+    }
+
+    // Earlier versions are untouched by later updates
+    for (version, contents) in &versions {
+        assert_eq!(&SegmentMap::from(version), contents);
+    }
+    for k in 0..250 {
+        assert_eq!(map.get(&k), expected.get(&k));
+    }
+}
+
+#[test]
+fn conversion_round_trips() {
+    let mut map: SegmentMap<u32, u8> = SegmentMap::new();
+    for i in 0..100 {
+        map.set(i * 3..i * 3 + 2, (i % 3) as u8);
+    }
+    let persistent = PersistentSegmentMap::from(map.clone());
+    assert_balanced(&persistent.root);
+    assert_eq!(persistent.len(), map.len());
+    assert!(persistent.iter().eq(map.iter()));
+    assert_eq!(SegmentMap::from(persistent), map);
+}
+
+#[test]
+fn iterators_match_segment_map() {
+    let mut map: SegmentMap<u32, u8> = SegmentMap::new();
+    for i in 0..60 {
+        // Some ranges touch, some are apart
+        let start = i * 4 + i % 3;
+        map.set(start..start + 3, (i % 4) as u8);
+    }
+    let persistent = PersistentSegmentMap::from(map.clone());
+
+    assert!(persistent.iter().rev().eq(map.iter().rev()));
+    assert!(persistent.iter_gaps().eq(map.iter_gaps()));
+    assert!(persistent.iter_gaps().rev().eq(map.iter_gaps().rev()));
+    assert_eq!(persistent.gaps(), map.gaps());
+
+    // Meeting in the middle yields everything once
+    let mut iter = persistent.iter();
+    let (mut fronts, mut backs) = (Vec::new(), Vec::new());
+    while let Some(front) = iter.next() {
+        fronts.push(front);
+        backs.extend(iter.next_back());
+    }
+    fronts.extend(backs.into_iter().rev());
+    assert!(fronts.into_iter().eq(map.iter()));
+
+    for start in 0..250 {
+        for end in (start..250).step_by(7) {
+            assert!(persistent.iter_in(start..end).eq(map.iter_in(start..end)));
+            assert!(persistent
+                .iter_in(start..=end)
+                .rev()
+                .eq(map.iter_in(start..=end).rev()));
+            assert_eq!(
+                persistent.iter_in(start..end).len(),
+                map.iter_in(start..end).count()
+            );
+        }
+    }
+}
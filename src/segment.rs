@@ -8,6 +8,8 @@ mod bounds;
 
 pub(crate) use bounds::{End, Start};
 
+use crate::map::{space::checked_shift, CheckedAdd};
+
 /// Monotonically increasing segment, for use as a concrete range type in
 /// [`SegmentMap`].
 #[derive(Clone, Copy, Hash, Eq)]
@@ -58,7 +60,7 @@ impl<T> Segment<T> {
     ///
     /// Oddly constructed ranges will be coerced to "sane" structure:
     /// - Point ranges (where both start and end are bounded to the same value
-    /// and at least one is included) will be coerced to (Included, Included)
+    ///   and at least one is included) will be coerced to (Included, Included)
     ///
     /// # Panics
     ///
@@ -153,6 +155,29 @@ impl<T> Segment<T> {
         }
     }
 
+    /// Construct a segment from its bounds as given, in a `const` context
+    ///
+    /// Unlike [`Segment::new`], the bounds aren't compared, so `start` must
+    /// not be after `end` (and a point range should include both ends).
+    /// This is meant for building tables like a [`FrozenSegmentSlice`] at
+    /// compile time.
+    ///
+    /// [`FrozenSegmentSlice`]: crate::frozen::FrozenSegmentSlice
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// const DIGITS: Segment<char> = Segment::from_bounds(Bound::Included('0'), Bound::Included('9'));
+    /// assert_eq!(DIGITS, Segment::from('0'..='9'));
+    /// ```
+    pub const fn from_bounds(start: Bound<T>, end: Bound<T>) -> Self {
+        Self {
+            start: Start(start),
+            end: End(end),
+        }
+    }
+
     /// Get the start value of the range (if it is bounded)
     ///
     /// # Examples
@@ -294,46 +319,87 @@ impl<T> Segment<T> {
         }
     }
 
-    // TODO: Range convenience methods
+    /// Shift the entire segment to the right (increasing) by some value.
+    /// Unbounded sides are left unbounded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut a = Segment::from(0..10);
+    /// a.shift_right(5);
+    /// assert_eq!(a, Segment::from(5..15));
+    ///
+    /// let mut b = Segment::from(..10);
+    /// b.shift_right(5);
+    /// assert_eq!(b, Segment::from(..15));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Bounds are moved with `+=`, so for integer keys this panics in debug
+    /// builds (and wraps in release builds) if a bound would overflow. See
+    /// [`Segment::checked_shift_right`] to check for that instead.
+    pub fn shift_right(&mut self, by: T)
+    where
+        T: Clone + core::ops::AddAssign,
+    {
+        if let Some(value) = self.start.value_mut() {
+            *value += by.clone();
+        }
+        if let Some(value) = self.end.value_mut() {
+            *value += by;
+        }
+    }
 
-    // /// Shift the entire range by some value
-    // pub fn shift(&mut self, by: T)
-    // where
-    //     T: Clone + core::ops::AddAssign,
-    // {
-    //     if let Some(value) = self.start.value_mut() {
-    //         *value += by.clone();
-    //     }
-    //     if let Some(value) = self.end.value_mut() {
-    //         *value += by;
-    //     }
-    // }
+    /// Returns the segment shifted to the right by `by`, or `None` if either
+    /// bound would overflow. Unbounded sides are left unbounded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let a = Segment::<u8>::from(0..10);
+    /// assert_eq!(a.checked_shift_right(&5), Some(Segment::from(5..15)));
+    /// assert_eq!(a.checked_shift_right(&250), None);
+    /// ```
+    pub fn checked_shift_right(&self, by: &T) -> Option<Self>
+    where
+        T: CheckedAdd,
+    {
+        Some(Segment {
+            start: Start(checked_shift(&self.start.0, by)?),
+            end: End(checked_shift(&self.end.0, by)?),
+        })
+    }
 
-    // /// Shift the entire range to the left by some value (useful if using an
-    // /// unsigned type, where [`shift`] isn't useful)
-    // pub fn shift_left(&mut self, by: T)
-    // where
-    //     T: Clone + core::ops::SubAssign,
-    // {
-    //     if let Some(value) = self.start.value_mut() {
-    //         *value -= by.clone();
-    //     }
-    //     if let Some(value) = self.end.value_mut() {
-    //         *value -= by;
-    //     }
-    // }
+    /// Shift the entire segment to the left (decreasing) by some value.
+    /// Unbounded sides are left unbounded.
+    ///
+    /// This is useful for unsigned types, where [`Segment::shift_right`]
+    /// can't be given a negative value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut a = Segment::from(5u32..15);
+    /// a.shift_left(5);
+    /// assert_eq!(a, Segment::from(0..10));
+    /// ```
+    pub fn shift_left(&mut self, by: T)
+    where
+        T: Clone + core::ops::SubAssign,
+    {
+        if let Some(value) = self.start.value_mut() {
+            *value -= by.clone();
+        }
+        if let Some(value) = self.end.value_mut() {
+            *value -= by;
+        }
+    }
 
-    // pub fn shift_right(&mut self, by: T)
-    // where
-    //     T: Clone + core::ops::AddAssign,
-    // {
-    //     if let Some(value) = self.start.value_mut() {
-    //         *value += by.clone();
-    //     }
-    //     if let Some(value) = self.end.value_mut() {
-    //         *value += by;
-    //     }
-    // }
+    // TODO: Range convenience methods
 
     // /// Adjust the start of a range to a new lower bound.
     // pub fn adjust_left(&mut self, _new_start: Bound<T>) -> Option<Self> {
@@ -366,14 +432,20 @@ impl<T> Segment<T> {
     pub(crate) fn bound_after(&self) -> Option<Start<&T>> {
         self.end.after()
     }
-}
 
-impl<'a, T> Segment<&'a T> {
-    pub(crate) fn borrow_bound_before(&self) -> Option<End<&'a T>> {
-        self.start.borrow_before()
-    }
-    pub(crate) fn borrow_bound_after(&self) -> Option<Start<&'a T>> {
-        self.end.borrow_after()
+    /// The part of this range that is also covered by `other`, if they
+    /// overlap
+    pub(crate) fn intersection(&self, other: &Self) -> Option<Segment<T>>
+    where
+        T: Clone + Ord,
+    {
+        if !self.overlaps(other) {
+            return None;
+        }
+        Some(Segment {
+            start: core::cmp::max(&self.start, &other.start).clone(),
+            end: core::cmp::min(&self.end, &other.end).clone(),
+        })
     }
 }
 
@@ -32,12 +32,12 @@ impl<T> Start<T> {
             Unbounded => None,
         }
     }
-    // pub fn value_mut(&mut self) -> Option<&mut T> {
-    //     match &mut self.0 {
-    //         Included(x) | Excluded(x) => Some(x),
-    //         Unbounded => None,
-    //     }
-    // }
+    pub fn value_mut(&mut self) -> Option<&mut T> {
+        match &mut self.0 {
+            Included(x) | Excluded(x) => Some(x),
+            Unbounded => None,
+        }
+    }
     pub fn before(&self) -> Option<End<&T>> {
         match &self.0 {
             Excluded(t) => Some(End(Included(t))),
@@ -158,12 +158,12 @@ impl<T> End<T> {
             Unbounded => None,
         }
     }
-    // pub fn value_mut(&mut self) -> Option<&mut T> {
-    //     match &mut self.0 {
-    //         Included(x) | Excluded(x) => Some(x),
-    //         Unbounded => None,
-    //     }
-    // }
+    pub fn value_mut(&mut self) -> Option<&mut T> {
+        match &mut self.0 {
+            Included(x) | Excluded(x) => Some(x),
+            Unbounded => None,
+        }
+    }
     pub fn after(&self) -> Option<Start<&T>> {
         match &self.0 {
             Excluded(t) => Some(Start(Included(t))),
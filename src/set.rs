@@ -1,7 +1,14 @@
-use core::fmt::{self, Debug};
+use alloc::vec::Vec;
+use core::{
+    cmp::Ordering,
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+    ops::{Range, Sub, SubAssign},
+};
 
 use crate::{
-    map::{Key, MaybeMap},
+    map::{CheckedAdd, Key, MaybeMap, NotMonotonic, SpaceOverflow, Stickiness},
+    segment::Start,
     Bound::{self, *},
     RangeBounds, Segment, SegmentMap,
 };
@@ -144,7 +151,114 @@ impl<T> SegmentSet<T> {
         self.map.get_range_value(value).map(|(range, _)| range)
     }
 
-    /// Insert a range into the set.
+    /// Get the widest bounds covered by the ranges in this set
+    ///
+    /// **NOTE**: This is not necessarily (or likely) a contiguous range!
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(0..9);
+    /// set.insert(15..30);
+    ///
+    /// assert_eq!(set.bounds(), Some(Segment::from(0..30).as_ref()));
+    /// assert_eq!(set.lower_bound(), Some(&Bound::Included(0)));
+    /// assert_eq!(set.upper_bound(), Some(&Bound::Excluded(30)));
+    /// ```
+    pub fn bounds(&self) -> Option<Segment<&T>> {
+        self.map.bounds()
+    }
+
+    /// Get the lowest bound covered by the ranges in this set
+    pub fn lower_bound(&self) -> Option<&Bound<T>> {
+        self.map.lower_bound()
+    }
+
+    /// Get the highest bound covered by the ranges in this set
+    pub fn upper_bound(&self) -> Option<&Bound<T>> {
+        self.map.upper_bound()
+    }
+
+    /// Returns the first range in the set, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// assert_eq!(set.first(), None);
+    /// set.insert(5..9);
+    /// set.insert(0..2);
+    ///
+    /// assert_eq!(set.first(), Some(&Segment::from(0..2)));
+    /// ```
+    pub fn first(&self) -> Option<&Segment<T>> {
+        self.map.first().map(|(range, _)| range)
+    }
+
+    /// Returns the last range in the set, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// assert_eq!(set.last(), None);
+    /// set.insert(5..9);
+    /// set.insert(0..2);
+    ///
+    /// assert_eq!(set.last(), Some(&Segment::from(5..9)));
+    /// ```
+    pub fn last(&self) -> Option<&Segment<T>> {
+        self.map.last().map(|(range, _)| range)
+    }
+
+    /// Removes and returns the first range in the set, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(0..2);
+    /// set.insert(5..9);
+    ///
+    /// assert_eq!(set.pop_first(), Some(Segment::from(0..2)));
+    /// assert_eq!(set.pop_first(), Some(Segment::from(5..9)));
+    /// assert_eq!(set.pop_first(), None);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<Segment<T>>
+    where
+        T: Ord,
+    {
+        self.map.pop_first().map(|(range, _)| range)
+    }
+
+    /// Removes and returns the last range in the set, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(0..2);
+    /// set.insert(5..9);
+    ///
+    /// assert_eq!(set.pop_last(), Some(Segment::from(5..9)));
+    /// assert_eq!(set.pop_last(), Some(Segment::from(0..2)));
+    /// assert_eq!(set.pop_last(), None);
+    /// ```
+    pub fn pop_last(&mut self) -> Option<Segment<T>>
+    where
+        T: Ord,
+    {
+        self.map.pop_last().map(|(range, _)| range)
+    }
+
+    /// Insert a range into the set, returning `true` if it added any new
+    /// coverage (that is, if the range wasn't already entirely covered).
     ///
     /// If the inserted range either overlaps or is immediately adjacent
     /// any existing range, then the ranges will be coalesced into
@@ -155,22 +269,37 @@ impl<T> SegmentSet<T> {
     /// ```
     /// # use segmap::*;
     /// let mut set = SegmentSet::new();
-    /// set.insert(0..5);
-    /// assert!(!set.is_empty())
+    /// assert!(set.insert(0..5));
+    /// assert!(!set.insert(1..3));
+    /// assert!(set.insert(3..7));
+    /// assert!(set.into_iter().eq(vec![Segment::from(0..7)]));
     /// ```
     ///
     /// # See Also
     ///
     /// - [`SegmentMap::insert`] and [`SegmentMap::set`] for the internal map's
-    /// insertion semantics. Because values are always `()` and returning
-    /// overwritten values is not necessary, this method uses `set`.
+    ///   insertion semantics. Because values are always `()` and returning
+    ///   overwritten values is not necessary, this method uses `set`.
     ///
-    pub fn insert<R>(&mut self, range: R)
+    pub fn insert<R>(&mut self, range: R) -> bool
     where
         R: RangeBounds<T>,
         T: Clone + Ord,
     {
-        self.map.set(range, ())
+        let range = Segment::from(&range);
+
+        // Ranges are coalesced, so anything already covered must be inside
+        // the last range starting at or before this one
+        let covered = self
+            .map
+            .map
+            .range::<Start<T>, _>(..=&range.start)
+            .next_back()
+            .is_some_and(|(existing, _)| existing.0.end >= range.end);
+        if !covered {
+            self.map.set(range, ());
+        }
+        !covered
     }
 
     /// Removes a range from the set returning if all or any of it was present.
@@ -187,8 +316,8 @@ impl<T> SegmentSet<T> {
     /// # See Also
     ///
     /// - [`SegmentMap::remove`] and [`SegmentMap::clear_range`] for the internal map's
-    /// removal semantics. However, this method will not allocate anything to
-    /// return.
+    ///   removal semantics. However, this method will not allocate anything to
+    ///   return.
     /// - [`SegmentSet::take`] if you want the removed elements
     ///
     pub fn remove<R>(&mut self, range: R) -> bool
@@ -213,14 +342,13 @@ impl<T> SegmentSet<T> {
     /// set.insert(0..5);
     /// let removed = set.take(0..2);
     ///
-    ///
+    /// assert!(removed.into_iter().eq(vec![Segment::from(0..2)]));
+    /// assert!(set.into_iter().eq(vec![Segment::from(2..5)]));
     /// ```
     ///
     /// # See Also
     ///
-    /// - [`SegmentMap::remove`] and [`SegmentMap::clear_range`] for the internal map's
-    /// removal semantics. However, this method will not allocate anything to
-    /// return.
+    /// - [`SegmentSet::split_off_range`], which this is an alias for
     /// - [`SegmentSet::remove`] if you don't want the removed elements
     ///
     pub fn take<R>(&mut self, range: R) -> Self
@@ -228,9 +356,7 @@ impl<T> SegmentSet<T> {
         R: RangeBounds<T>,
         T: Clone + Ord,
     {
-        Self {
-            map: self.map.remove(range).unwrap_or_default(),
-        }
+        self.split_off_range(range)
     }
 
     /// Retains only the elements specified by the predicate.
@@ -360,14 +486,271 @@ impl<T> SegmentSet<T> {
         }
     }
 
-    // TODO: split_off_range
+    /// Split the set into two, removing and returning everything inside
+    /// `range`. Ranges overlapping the bounds of `range` are split.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut a = SegmentSet::new();
+    /// a.insert(0..5);
+    /// a.insert(10..15);
+    ///
+    /// let b = a.split_off_range(3..12);
+    ///
+    /// assert!(a.into_iter().eq(vec![
+    ///     Segment::from(0..3),
+    ///     Segment::from(12..15),
+    /// ]));
+    /// assert!(b.into_iter().eq(vec![
+    ///     Segment::from(3..5),
+    ///     Segment::from(10..12),
+    /// ]));
+    /// ```
+    pub fn split_off_range<R>(&mut self, range: R) -> Self
+    where
+        R: RangeBounds<T>,
+        T: Clone + Ord,
+    {
+        // Drained ranges are sorted and apart, so they can go directly into
+        // the new set
+        Self {
+            map: SegmentMap {
+                map: self
+                    .map
+                    .drain_range(range)
+                    .map(|(range, _)| (Key(range), ()))
+                    .collect(),
+                store: Vec::new(),
+            },
+        }
+    }
+
+    /// Insert `len` empty values at `at`, moving everything after `at` to the
+    /// right. A range containing `at` is stretched to cover the inserted
+    /// space.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(0..5);
+    /// set.insert(10..15);
+    /// set.insert_space(3, 10);
+    ///
+    /// assert!(set.into_iter().eq(vec![
+    ///     Segment::from(0..15),
+    ///     Segment::from(20..25),
+    /// ]));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If a bound after `at` would be moved past the largest `T`, as for
+    /// [`SegmentMap::insert_space`]. Nothing is moved in that case.
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::insert_space`], which is called internally
+    /// - [`SegmentSet::try_insert_space`] to handle overflow without
+    ///   panicking
+    ///
+    pub fn insert_space(&mut self, at: T, len: T)
+    where
+        T: Clone + Ord + CheckedAdd,
+    {
+        self.map.insert_space(at, len)
+    }
+
+    /// Insert `len` empty values at `at`, moving everything after `at` to the
+    /// right, and using `stickiness` to decide which range (if any) grows when
+    /// `at` is on a range boundary.
+    ///
+    /// # Panics
+    ///
+    /// If a bound after `at` would be moved past the largest `T`, as for
+    /// [`SegmentSet::insert_space`].
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::insert_space_with`], which is called internally
+    ///
+    pub fn insert_space_with(&mut self, at: T, len: T, stickiness: Stickiness)
+    where
+        T: Clone + Ord + CheckedAdd,
+    {
+        self.map.insert_space_with(at, len, stickiness)
+    }
+
+    /// Insert `len` empty values at `at` like [`SegmentSet::insert_space`],
+    /// or return an error (leaving the set unchanged) if a bound after `at`
+    /// would be moved past the largest `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set: SegmentSet<u8> = SegmentSet::new();
+    /// set.insert(200..250);
+    ///
+    /// assert_eq!(set.try_insert_space(100, 60), Err(SpaceOverflow));
+    /// assert!(set.iter().eq(&[Segment::from(200..250)]));
+    /// ```
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::try_insert_space`], which is called internally
+    ///
+    pub fn try_insert_space(&mut self, at: T, len: T) -> Result<(), SpaceOverflow>
+    where
+        T: Clone + Ord + CheckedAdd,
+    {
+        self.map.try_insert_space(at, len)
+    }
+
+    /// Insert `len` empty values at `at` like
+    /// [`SegmentSet::insert_space_with`], or return an error (leaving the set
+    /// unchanged) if a bound after `at` would be moved past the largest `T`.
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::try_insert_space_with`], which is called internally
+    ///
+    pub fn try_insert_space_with(
+        &mut self,
+        at: T,
+        len: T,
+        stickiness: Stickiness,
+    ) -> Result<(), SpaceOverflow>
+    where
+        T: Clone + Ord + CheckedAdd,
+    {
+        self.map.try_insert_space_with(at, len, stickiness)
+    }
+
+    /// Remove the values in `range`, moving everything after it to the left
+    /// to close the hole.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(0..5);
+    /// set.insert(10..15);
+    /// set.remove_space(3..12);
+    ///
+    /// assert!(set.into_iter().eq(vec![Segment::from(0..6)]));
+    /// ```
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::remove_space`], which is called internally
+    ///
+    pub fn remove_space(&mut self, range: Range<T>)
+    where
+        T: Clone + Ord + Sub<Output = T> + SubAssign,
+    {
+        self.map.remove_space(range)
+    }
+
+    /// Convert every value in the set with an order-preserving function,
+    /// rebuilding the set in linear time.
+    ///
+    /// `f` must be monotonically increasing, which is not checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(1u32..3);
+    /// set.insert(5..8);
+    ///
+    /// let doubled = set.map_keys_monotonic(|t| t as u64 * 2);
+    /// assert!(doubled.into_iter().eq(vec![
+    ///     Segment::from(2..6),
+    ///     Segment::from(10..16),
+    /// ]));
+    /// ```
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::map_keys_monotonic`], which is called internally
+    ///
+    pub fn map_keys_monotonic<U, F>(self, f: F) -> SegmentSet<U>
+    where
+        U: Ord,
+        F: FnMut(T) -> U,
+    {
+        SegmentSet {
+            map: self.map.map_keys_monotonic(f),
+        }
+    }
+
+    /// Convert every value in the set with an order-preserving function,
+    /// checking that the order of ranges really is preserved.
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::map_keys_monotonic_checked`], which is called
+    ///   internally
+    ///
+    pub fn map_keys_monotonic_checked<U, F>(self, f: F) -> Result<SegmentSet<U>, NotMonotonic>
+    where
+        U: Ord,
+        F: FnMut(T) -> U,
+    {
+        self.map
+            .map_keys_monotonic_checked(f)
+            .map(|map| SegmentSet { map })
+    }
+
+    /// Convert every value in the set with an order-reversing function,
+    /// rebuilding the set in linear time.
+    ///
+    /// `f` must be monotonically decreasing, which is not checked.
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::map_keys_decreasing`], which is called internally
+    ///
+    pub fn map_keys_decreasing<U, F>(self, f: F) -> SegmentSet<U>
+    where
+        U: Ord,
+        F: FnMut(T) -> U,
+    {
+        SegmentSet {
+            map: self.map.map_keys_decreasing(f),
+        }
+    }
+
+    /// Convert every value in the set with an order-reversing function,
+    /// checking that the order of ranges really is reversed.
+    ///
+    /// # See Also
+    ///
+    /// - [`SegmentMap::map_keys_decreasing_checked`], which is called
+    ///   internally
+    ///
+    pub fn map_keys_decreasing_checked<U, F>(self, f: F) -> Result<SegmentSet<U>, NotMonotonic>
+    where
+        U: Ord,
+        F: FnMut(T) -> U,
+    {
+        self.map
+            .map_keys_decreasing_checked(f)
+            .map(|map| SegmentSet { map })
+    }
 }
 
 impl<T: Clone + Ord> SegmentSet<&T> {
     pub fn cloned(&self) -> SegmentSet<T> {
         SegmentSet {
             map: SegmentMap {
-                map: self.map.map.iter().map(|(k, _)| (k.cloned(), ())).collect(),
+                map: self.map.map.keys().map(|k| (k.cloned(), ())).collect(),
                 store: alloc::vec::Vec::with_capacity(self.map.store.len()),
             },
         }
@@ -391,7 +774,7 @@ where
         let SegmentMap { map, store } = map;
         SegmentSet {
             map: SegmentMap {
-                map: map.into_iter().map(|(k, _)| (k, ())).collect(),
+                map: map.into_keys().map(|k| (k, ())).collect(),
                 store,
             },
         }
@@ -410,8 +793,37 @@ where
     }
 }
 
+impl<T: Hash> Hash for SegmentSet<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Prefix with the length (like the std collections), so that sets
+        // hashed one after another can't run together
+        state.write_usize(self.len());
+        for range in self.iter() {
+            range.hash(state);
+        }
+    }
+}
+
 impl<T: PartialEq> PartialEq for SegmentSet<T> {
     fn eq(&self, other: &Self) -> bool {
         self.map == other.map
     }
 }
+impl<T: Eq> Eq for SegmentSet<T> {}
+
+// Ranges in a set never overlap, so comparing them by start then end is
+// consistent with equality
+impl<T: Ord> PartialOrd for SegmentSet<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T: Ord> Ord for SegmentSet<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter()
+            .map(|range| (&range.start, &range.end))
+            .cmp(other.iter().map(|range| (&range.start, &range.end)))
+    }
+}
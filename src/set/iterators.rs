@@ -6,7 +6,7 @@ use core::{
 
 use alloc::vec::Vec;
 
-use crate::{Segment, SegmentSet};
+use crate::{map::iterators as map_iterators, Segment, SegmentSet};
 // TODO: all doctests
 
 // /// See [`alloc::collections::btree_set::ITER_PERFORMANCE_TIPPING_SIZE_DIFF`]
@@ -36,32 +36,143 @@ impl<T> SegmentSet<T> {
         self.into_iter().collect()
     }
 
-    // range?
+    /// Gets an iterator over the sorted ranges in the set that overlap
+    /// `range`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(0..5);
+    /// set.insert(10..15);
+    /// set.insert(20..25);
+    ///
+    /// assert!(set.iter_in(3..12).eq(vec![
+    ///     &Segment::from(0..5),
+    ///     &Segment::from(10..15),
+    /// ]));
+    /// ```
+    pub fn iter_in<R>(&self, range: R) -> IterIn<'_, T>
+    where
+        R: RangeBounds<T>,
+        T: Clone + Ord,
+    {
+        IterIn(self.map.iter_in(range))
+    }
 
-    // pub fn iter_complement(&self) -> impl Iterator<Item = Range<T>> {
-    //     self.map.iter_complement()
-    // }
-    // pub fn gaps(&self) -> impl Iterator<Item = Range<T>>
-    // where
-    //     T: Ord + Clone,
-    // {
-    //     self.map.gaps()
-    // }
+    /// Gets an iterator over the parts of the ranges in the set that are
+    /// inside `range`, truncating any that overlap its bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(0..5);
+    /// set.insert(10..15);
+    /// set.insert(20..25);
+    ///
+    /// assert!(set.iter_subset(3..12).eq(vec![
+    ///     Segment::from(3..5),
+    ///     Segment::from(10..12),
+    /// ]));
+    /// ```
+    pub fn iter_subset<R>(&self, range: R) -> IterSubset<'_, T>
+    where
+        R: RangeBounds<T>,
+        T: Clone + Ord,
+    {
+        IterSubset(self.map.iter_subset(range))
+    }
 
-    // /// Gets an iterator over all the maximally-sized ranges
-    // /// contained in `outer_range` that are not covered by
-    // /// any range stored in the set.
-    // ///
-    // /// The iterator element type is `Range<T>`.
-    // ///
-    // /// NOTE: Calling `gaps` eagerly finds the first gap,
-    // /// even if the iterator is never consumed.
-    // pub fn gaps_in<R: RangeBounds<T>>(&self, outer_range: R) -> impl Iterator<Item = Range<T>> {
-    //     self.map.gaps_in(outer_range).map(|(k, v)| k)
-    // }
+    /// Gets an iterator over all maximally-sized gaps between ranges in the
+    /// set.
+    ///
+    /// Like [`SegmentMap::iter_gaps`], this doesn't include the regions
+    /// before the first range or after the last range. Use
+    /// [`SegmentSet::iter_complement`] to include them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(0..5);
+    /// set.insert(10..15);
+    /// set.insert(20..25);
+    ///
+    /// assert!(set.iter_gaps().eq(vec![
+    ///     Segment::from(&5..&10),
+    ///     Segment::from(&15..&20),
+    /// ]));
+    /// ```
+    pub fn iter_gaps(&self) -> Gaps<'_, T> {
+        Gaps(self.map.iter_gaps())
+    }
+
+    /// Create a `SegmentSet` of all the gaps between ranges in the set.
+    ///
+    /// See [`SegmentSet::iter_gaps`].
+    pub fn gaps(&self) -> SegmentSet<&T>
+    where
+        T: Ord,
+    {
+        self.map.gaps()
+    }
+
+    /// Gets an iterator over everything not covered by the set, including
+    /// the regions before the first range and after the last range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(0..5);
+    /// set.insert(10..15);
+    ///
+    /// assert!(set.iter_complement().eq(vec![
+    ///     Segment::from(..&0),
+    ///     Segment::from(&5..&10),
+    ///     Segment::from(&15..),
+    /// ]));
+    /// assert!(SegmentSet::<u32>::new().iter_complement().eq(vec![Segment::from(..)]));
+    /// ```
+    pub fn iter_complement(&self) -> IterComplement<'_, T> {
+        IterComplement(self.map.iter_complement())
+    }
 
-    // TODO
-    // pub fn extend_into_gaps(&mut self)
+    /// Insert all the ranges from `iter` that aren't already covered by the
+    /// set.
+    ///
+    /// The resulting set is the same as with [`Extend::extend`], but only the
+    /// gaps between existing ranges are filled, so ranges that are already
+    /// covered are skipped without changing anything.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut set = SegmentSet::new();
+    /// set.insert(0..10);
+    /// set.extend_into_gaps(vec![2..4, 8..12, 20..25]);
+    ///
+    /// assert!(set.into_iter().eq(vec![
+    ///     Segment::from(0..12),
+    ///     Segment::from(20..25),
+    /// ]));
+    /// ```
+    pub fn extend_into_gaps<I, R>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = R>,
+        R: RangeBounds<T>,
+        T: Clone + Ord,
+    {
+        for range in iter {
+            self.map.insert_in_gaps(range, ());
+        }
+    }
 }
 
 // impl<T: Clone + Ord> FromIterator<Range<T>> for SegmentSet<T> {
@@ -85,9 +196,9 @@ impl<Item: RangeBounds<T>, T: Clone + Ord> FromIterator<Item> for SegmentSet<T>
 impl<T: Clone + Ord> Extend<Segment<T>> for SegmentSet<T> {
     /// Insert all the items from `iter` into `self`.
     ///
-    /// **NOTE**: Inserted items will overwrite existing ranges in `self` if
-    /// they overlap. If you don't want to overwrite existing ranges, use
-    /// [`extend_into_gaps`].
+    /// **NOTE**: Inserted items will replace existing ranges in `self` if
+    /// they overlap. If you only want to fill the gaps between existing
+    /// ranges, use [`SegmentSet::extend_into_gaps`].
     ///
     /// Clone is required for insertion, since we can't guarantee elements in `iter`
     /// are ordered or non-overlapping, so ranges may need to be split.
@@ -116,9 +227,14 @@ impl<'a, T: 'a + Ord + Copy> Extend<&'a Segment<T>> for SegmentSet<T> {
     // }
 }
 
-#[derive(Clone)]
 pub struct Iter<'a, T: 'a>(crate::map::iterators::Ranges<'a, T, ()>);
 
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter(self.0.clone())
+    }
+}
+
 impl<T: fmt::Debug> fmt::Debug for Iter<'_, T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("Iter").field(&self.0.clone()).finish()
@@ -192,3 +308,161 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
     }
 }
 impl<T> FusedIterator for IntoIter<T> {}
+
+/// An iterator over the ranges of a `SegmentSet` that overlap a given range.
+///
+/// This `struct` is created by the [`iter_in`] method on [`SegmentSet`]. See
+/// its documentation for more.
+///
+/// [`iter_in`]: SegmentSet::iter_in
+pub struct IterIn<'a, T>(map_iterators::IterIn<'a, T, ()>);
+
+impl<T: Clone + Ord + fmt::Debug> fmt::Debug for IterIn<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+impl<'a, T: Ord> Iterator for IterIn<'a, T> {
+    type Item = &'a Segment<T>;
+    fn next(&mut self) -> Option<&'a Segment<T>> {
+        self.0.next().map(|(range, _)| range)
+    }
+}
+impl<'a, T: Ord> DoubleEndedIterator for IterIn<'a, T> {
+    fn next_back(&mut self) -> Option<&'a Segment<T>> {
+        self.0.next_back().map(|(range, _)| range)
+    }
+}
+impl<T: Ord> FusedIterator for IterIn<'_, T> {}
+impl<T: Clone> Clone for IterIn<'_, T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// An iterator over the parts of the ranges of a `SegmentSet` inside a given
+/// range.
+///
+/// This `struct` is created by the [`iter_subset`] method on [`SegmentSet`].
+/// See its documentation for more.
+///
+/// [`iter_subset`]: SegmentSet::iter_subset
+pub struct IterSubset<'a, T>(map_iterators::IterSubset<'a, T, ()>);
+
+impl<T: Clone + Ord + fmt::Debug> fmt::Debug for IterSubset<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+impl<T: Clone + Ord> Iterator for IterSubset<'_, T> {
+    type Item = Segment<T>;
+    fn next(&mut self) -> Option<Segment<T>> {
+        self.0.next().map(|(range, _)| range)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+    fn last(mut self) -> Option<Segment<T>> {
+        self.next_back()
+    }
+}
+impl<T: Clone + Ord> DoubleEndedIterator for IterSubset<'_, T> {
+    fn next_back(&mut self) -> Option<Segment<T>> {
+        self.0.next_back().map(|(range, _)| range)
+    }
+}
+impl<T: Clone + Ord> FusedIterator for IterSubset<'_, T> {}
+impl<T: Clone> Clone for IterSubset<'_, T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// An iterator over the gaps between the ranges of a `SegmentSet`.
+///
+/// This `struct` is created by the [`iter_gaps`] method on [`SegmentSet`].
+/// See its documentation for more.
+///
+/// [`iter_gaps`]: SegmentSet::iter_gaps
+pub struct Gaps<'a, T>(map_iterators::Gaps<'a, T, ()>);
+
+impl<T: fmt::Debug + Ord> fmt::Debug for Gaps<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+impl<'a, T: Ord> Iterator for Gaps<'a, T> {
+    type Item = Segment<&'a T>;
+    fn next(&mut self) -> Option<Segment<&'a T>> {
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+    fn last(mut self) -> Option<Segment<&'a T>> {
+        self.next_back()
+    }
+}
+impl<T: Ord> DoubleEndedIterator for Gaps<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+impl<T: Ord> ExactSizeIterator for Gaps<'_, T> {
+    fn len(&self) -> usize {
+        // Touching ranges in a set are coalesced, so every pair of ranges
+        // has a gap between them
+        self.0.pairs()
+    }
+}
+impl<T: Ord> FusedIterator for Gaps<'_, T> {}
+impl<T> Clone for Gaps<'_, T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// An iterator over everything not covered by a `SegmentSet`.
+///
+/// This `struct` is created by the [`iter_complement`] method on
+/// [`SegmentSet`]. See its documentation for more.
+///
+/// [`iter_complement`]: SegmentSet::iter_complement
+pub struct IterComplement<'a, T>(map_iterators::IterComplement<'a, T, ()>);
+
+impl<T: fmt::Debug + Ord> fmt::Debug for IterComplement<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+impl<'a, T: Ord> Iterator for IterComplement<'a, T> {
+    type Item = Segment<&'a T>;
+    fn next(&mut self) -> Option<Segment<&'a T>> {
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+    fn last(mut self) -> Option<Segment<&'a T>> {
+        self.next_back()
+    }
+}
+impl<T: Ord> DoubleEndedIterator for IterComplement<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+impl<T: Ord> ExactSizeIterator for IterComplement<'_, T> {
+    fn len(&self) -> usize {
+        // Exact for the same reason as `Gaps::len`
+        self.0.max_len()
+    }
+}
+impl<T: Ord> FusedIterator for IterComplement<'_, T> {}
+impl<T> Clone for IterComplement<'_, T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
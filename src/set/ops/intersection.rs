@@ -1,17 +1,22 @@
-use core::cmp::Ordering::*;
+use core::{
+    fmt::{self, Debug},
+    iter::FusedIterator,
+};
 
-use crate::{map::Key, set::iterators::Iter, Segment, SegmentMap, SegmentSet};
+use super::{
+    sweep::{SegmentIterExt, Stitch},
+    Refs,
+};
+use crate::{map::Key, Segment, SegmentMap, SegmentSet};
 
 impl<T> SegmentSet<T> {
     // TODO: into_intersection_iter
 
-    pub fn iter_intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T> {
-        Intersection {
-            iter_a: self.iter(),
-            prev_a: None,
-            iter_b: other.iter(),
-            prev_b: None,
-        }
+    pub fn iter_intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T>
+    where
+        T: Ord,
+    {
+        Intersection(self.refs().intersection(other.refs()))
     }
 
     // TODO: into_intersection
@@ -60,14 +65,14 @@ impl<'a, T: Ord + Clone> core::ops::BitAnd<&'a SegmentSet<T>> for &'a SegmentSet
 }
 
 // TODO: into_intersection
-impl<'a, T: Ord + Clone> core::ops::BitAnd<SegmentSet<T>> for SegmentSet<T> {
+impl<T: Ord + Clone> core::ops::BitAnd<SegmentSet<T>> for SegmentSet<T> {
     type Output = SegmentSet<T>;
     fn bitand(self, rhs: SegmentSet<T>) -> SegmentSet<T> {
         self.intersection(&rhs).cloned()
     }
 }
 
-/// Set in-place intersection
+// Set in-place intersection
 // impl<T: Ord + Clone> core::ops::BitAndAssign<&SegmentSet<T>> for SegmentSet<T> {
 //     fn bitxor_assign(&mut self, rhs: &SegmentSet<T>) {}
 // }
@@ -79,106 +84,47 @@ impl<'a, T: Ord + Clone> core::ops::BitAnd<SegmentSet<T>> for SegmentSet<T> {
 //     }
 // }
 
-pub struct Intersection<'a, T> {
-    iter_a: Iter<'a, T>,
-    prev_a: Option<Segment<&'a T>>,
+/// An iterator over the intersection of two [`SegmentSet`]s
+///
+/// This `struct` is created by [`SegmentSet::iter_intersection`]. See its
+/// documentation for more.
+pub struct Intersection<'a, T>(Stitch<Refs<'a, T>, Refs<'a, T>, &'a T>);
 
-    iter_b: Iter<'a, T>,
-    prev_b: Option<Segment<&'a T>>,
+impl<T> Clone for Intersection<'_, T> {
+    fn clone(&self) -> Self {
+        Intersection(self.0.clone())
+    }
+}
+
+impl<T: Ord + Debug> Debug for Intersection<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
 }
 
 impl<'a, T: Ord> Iterator for Intersection<'a, T> {
     type Item = Segment<&'a T>;
-    fn next(&mut self) -> Option<Self::Item> {
-        // Get the next values. If either ran out, we're done
-        let mut next_a = self
-            .prev_a
-            .take()
-            .or_else(|| self.iter_a.next().map(|x| x.as_ref()))?;
-
-        let mut next_b = self
-            .prev_b
-            .take()
-            .or_else(|| self.iter_b.next().map(|x| x.as_ref()))?;
-
-        // Otherwise, find the next common item
-        loop {
-            // If `next_a` is fully before `next_b`, grab another and loop
-            if next_a.end.cmp_start(&next_b.start).is_gt() {
-                next_a = self.iter_a.next()?.as_ref();
-                continue;
-            }
-
-            // Likewise the other way around
-            if next_a.start.cmp_end(&next_b.end).is_gt() {
-                next_b = self.iter_b.next()?.as_ref();
-                continue;
-            }
-
-            // Otherwise, we have some overlap
-            match (next_a.start.cmp(&next_b.start), next_a.end.cmp(&next_b.end)) {
-                // Partial overlap, but `a` doesn't extend beyond `b`.
-                // Use the overlapped part of `a` and remember to remove it from
-                // `b` for the next iteration.
-                (Less, Less) => {
-                    next_a.start =
-                        core::mem::replace(&mut next_b.start, next_a.borrow_bound_after().unwrap());
-                    self.prev_b.insert(next_b);
-                    return Some(next_a);
-                }
-
-                // Partial overlap where `a` extends just to the
-                // end of `b` (just use `b`)
-                (Less, Equal) => return Some(next_b),
-
-                // `a` extends beyond `b` in both directions.
-                // Return `b` but keep the last part of `a`
-                (Less, Greater) => {
-                    next_a.start = next_b.borrow_bound_after().unwrap();
-                    self.prev_a.insert(next_a);
-                    return Some(next_b);
-                }
-
-                // Partial overlap where `a` extends just to the
-                // end of `b`. Use `a` and hold on to the end of `b`
-                (Equal, Less) => {
-                    next_b.start = next_a.borrow_bound_after().unwrap();
-                    self.prev_b.insert(next_b);
-                    return Some(next_a);
-                }
-
-                // Both exactly overlap each other
-                (Equal, Equal) => return Some(next_a),
-
-                // Partial overlap, but some `b` past `a`
-                // Keep part of `a` and look for a new `b`
-                (Equal, Greater) => {
-                    next_a.start = next_b.borrow_bound_after().unwrap();
-                    self.prev_a.insert(next_a);
-                    return Some(next_b);
-                }
-
-                // `b` extends beyond `a` in both directions.
-                // Use `a` and keep the end of `b`
-                (Greater, Less) => {
-                    next_b.start = next_a.borrow_bound_after().unwrap();
-                    self.prev_b.insert(next_b);
-                    return Some(next_a);
-                }
-
-                // Partial overlap, where `b` extends before `a`, but they
-                // end together. Just return `a`
-                (Greater, Equal) => return Some(next_a),
-
-                // Partial overlap, but `b` doesn't extend beyond `a`.
-                // Use the overlapped part of `b` and keep `a` for the next iteration.
-                (Greater, Greater) => {
-                    next_b.start =
-                        core::mem::replace(&mut next_a.start, next_b.borrow_bound_after().unwrap());
-                    self.prev_a.insert(next_a);
-                    return Some(next_b);
-                }
-            }
-        }
+    fn next(&mut self) -> Option<Segment<&'a T>> {
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.coalesced_size_hint()
+    }
+    fn min(mut self) -> Option<Segment<&'a T>> {
+        self.next()
+    }
+    fn max(mut self) -> Option<Segment<&'a T>> {
+        self.next_back()
+    }
+    fn last(mut self) -> Option<Segment<&'a T>> {
+        self.next_back()
     }
 }
+
+impl<'a, T: Ord> DoubleEndedIterator for Intersection<'a, T> {
+    fn next_back(&mut self) -> Option<Segment<&'a T>> {
+        self.0.next_back()
+    }
+}
+
+impl<T: Ord> FusedIterator for Intersection<'_, T> {}
@@ -1,12 +1,25 @@
 //! Common Set operations for SegmentSet
 
-use crate::{map::Key, RangeBounds, SegmentMap, SegmentSet};
+use core::iter::Map;
+
+use crate::{map::Key, set::iterators::Iter, RangeBounds, Segment, SegmentMap, SegmentSet};
 
 pub mod difference;
 pub mod intersection;
+pub mod sweep;
 pub mod symmetric_difference;
 pub mod union;
 
+/// The ranges of a set, as the borrowed segments the set operation iterators
+/// work with
+type Refs<'a, T> = Map<Iter<'a, T>, fn(&'a Segment<T>) -> Segment<&'a T>>;
+
+impl<T> SegmentSet<T> {
+    fn refs(&self) -> Refs<'_, T> {
+        self.iter().map(Segment::as_ref)
+    }
+}
+
 impl<T: Ord> SegmentSet<T> {
     /// Check whether `self` and `other` are disjoint sets
     ///
@@ -46,6 +59,9 @@ impl<T: Ord> SegmentSet<T> {
     }
 
     // as_complement / into_complement?
+    /// Create a `SegmentSet` of everything not covered by `self`.
+    ///
+    /// See [`SegmentSet::iter_complement`].
     pub fn complement(&self) -> SegmentSet<&T>
     where
         T: Ord,
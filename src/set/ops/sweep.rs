@@ -0,0 +1,754 @@
+//! Lazy set algebra over sorted sequences of segments
+//!
+//! [`SegmentIterExt`] adds [`union`], [`intersection`], [`difference`],
+//! [`symmetric_difference`] and [`coalesce`] adapters to any iterator of
+//! [`Segment`]s, so sorted streams (from a file or a database, say) can be
+//! combined without collecting them into a [`SegmentSet`] first. The set
+//! operations on [`SegmentSet`] are built on the same adapters.
+//!
+//! Internally, a `Sweep` walks two sorted sequences of non-overlapping
+//! segments at once, cutting them into pieces that are either in one sequence
+//! or both, and keeps the pieces an `Op` asks for. Pieces come out sorted,
+//! but may touch each other, so they're joined back up by a `Join`.
+//!
+//! Both work from the back as well as the front. Each sequence is held as a
+//! `Side`, which remembers the partly consumed segment at either end, so
+//! the two ends can meet in the middle of a segment.
+//!
+//! [`union`]: SegmentIterExt::union
+//! [`intersection`]: SegmentIterExt::intersection
+//! [`difference`]: SegmentIterExt::difference
+//! [`symmetric_difference`]: SegmentIterExt::symmetric_difference
+//! [`coalesce`]: SegmentIterExt::coalesce
+//! [`SegmentSet`]: crate::SegmentSet
+
+use core::{
+    cmp::{max, min, Ordering::*},
+    fmt::{self, Debug},
+    iter::{Fuse, FusedIterator},
+};
+
+use crate::{
+    segment::{End, Start},
+    Segment,
+};
+
+/// Which pieces of two sequences to keep
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Op {
+    Union,
+    Intersection,
+    Difference,
+    SymmetricDifference,
+}
+
+impl Op {
+    /// Whether to keep a piece, given whether it's in `a` and/or `b`
+    fn keep(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            Op::Union => in_a || in_b,
+            Op::Intersection => in_a && in_b,
+            Op::Difference => in_a && !in_b,
+            Op::SymmetricDifference => in_a != in_b,
+        }
+    }
+}
+
+/// The segments of a sequence that haven't been consumed yet
+#[derive(Clone)]
+pub(crate) struct Side<I, T> {
+    iter: I,
+
+    // Segments (or what's left of them) taken from either end of `iter`
+    front: Option<Segment<T>>,
+    back: Option<Segment<T>>,
+}
+
+impl<I, T> Side<I, T> {
+    pub(crate) fn new(iter: I) -> Self {
+        Side {
+            iter,
+            front: None,
+            back: None,
+        }
+    }
+
+    /// The number of held (partly consumed) segments
+    fn held(&self) -> usize {
+        self.front.is_some() as usize + self.back.is_some() as usize
+    }
+
+    /// Bounds on the number of segments left, like [`Iterator::size_hint`]
+    pub(crate) fn size_hint(&self) -> (usize, Option<usize>)
+    where
+        I: Iterator<Item = Segment<T>>,
+    {
+        let (lower, upper) = self.iter.size_hint();
+        let held = self.held();
+        (
+            lower.saturating_add(held),
+            upper.and_then(|upper| upper.checked_add(held)),
+        )
+    }
+}
+
+impl<I, T> Side<I, T>
+where
+    I: FusedIterator<Item = Segment<T>>,
+{
+    fn peek_front(&mut self) -> Option<&Segment<T>> {
+        if self.front.is_none() {
+            // Once `iter` runs out, the front meets the back
+            self.front = self.iter.next().or_else(|| self.back.take());
+        }
+        self.front.as_ref()
+    }
+
+    fn pop_front(&mut self) -> Option<Segment<T>> {
+        self.peek_front();
+        self.front.take()
+    }
+
+    fn peek_back(&mut self) -> Option<&Segment<T>>
+    where
+        I: DoubleEndedIterator,
+    {
+        if self.back.is_none() {
+            self.back = self.iter.next_back().or_else(|| self.front.take());
+        }
+        self.back.as_ref()
+    }
+
+    fn pop_back(&mut self) -> Option<Segment<T>>
+    where
+        I: DoubleEndedIterator,
+    {
+        self.peek_back();
+        self.back.take()
+    }
+}
+
+impl<I, T: Clone> Side<I, T> {
+    /// Consume the front segment up to (and including) `end`
+    fn cut_front(&mut self, end: &End<T>)
+    where
+        T: Ord,
+    {
+        if let Some(front) = &mut self.front {
+            if &front.end == end {
+                self.front = None;
+            } else {
+                front.start = end.after().unwrap().cloned();
+            }
+        }
+    }
+
+    /// Consume the back segment down to (and including) `start`
+    fn cut_back(&mut self, start: &Start<T>)
+    where
+        T: Ord,
+    {
+        if let Some(back) = &mut self.back {
+            if &back.start == start {
+                self.back = None;
+            } else {
+                back.end = start.before().unwrap().cloned();
+            }
+        }
+    }
+}
+
+impl<I: Debug, T: Debug> Debug for Side<I, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Side")
+            .field("front", &self.front)
+            .field("iter", &self.iter)
+            .field("back", &self.back)
+            .finish()
+    }
+}
+
+/// The pieces of two sorted sequences of non-overlapping segments that an
+/// [`Op`] keeps, in order (but not coalesced)
+#[derive(Clone, Debug)]
+pub(crate) struct Sweep<I, J, T> {
+    a: Side<Fuse<Checked<I, T>>, T>,
+    b: Side<Fuse<Checked<J, T>>, T>,
+    op: Op,
+}
+
+impl<I, J, T> Sweep<I, J, T>
+where
+    I: Iterator<Item = Segment<T>>,
+    J: Iterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+    pub(crate) fn new(a: I, b: J, op: Op) -> Self {
+        Sweep {
+            a: Side::new(Checked::new(a, false).fuse()),
+            b: Side::new(Checked::new(b, false).fuse()),
+            op,
+        }
+    }
+
+    /// Upper bounds on the number of segments left in `a` and `b`
+    pub(crate) fn remaining(&self) -> (usize, usize) {
+        let upper = |(_, upper): (usize, Option<usize>)| upper.unwrap_or(usize::MAX);
+        (upper(self.a.size_hint()), upper(self.b.size_hint()))
+    }
+
+    /// Lower bounds on the number of segments left in `a` and `b`
+    pub(crate) fn remaining_at_least(&self) -> (usize, usize) {
+        (self.a.size_hint().0, self.b.size_hint().0)
+    }
+}
+
+impl<I, J, T> Iterator for Sweep<I, J, T>
+where
+    I: Iterator<Item = Segment<T>>,
+    J: Iterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+    type Item = Segment<T>;
+    fn next(&mut self) -> Option<Segment<T>> {
+        loop {
+            // Find the next piece that's entirely in `a`, `b` or both
+            let (start, end, in_a, in_b) = match (self.a.peek_front(), self.b.peek_front()) {
+                (None, None) => return None,
+                (Some(a), None) => {
+                    if !self.op.keep(true, false) {
+                        return None;
+                    }
+                    (a.start.clone(), a.end.clone(), true, false)
+                }
+                (None, Some(b)) => {
+                    if !self.op.keep(false, true) {
+                        return None;
+                    }
+                    (b.start.clone(), b.end.clone(), false, true)
+                }
+                (Some(a), Some(b)) => match a.start.cmp(&b.start) {
+                    Less => {
+                        let end = min(a.end.as_ref(), b.start.before().unwrap());
+                        (a.start.clone(), end.cloned(), true, false)
+                    }
+                    Greater => {
+                        let end = min(b.end.as_ref(), a.start.before().unwrap());
+                        (b.start.clone(), end.cloned(), false, true)
+                    }
+                    Equal => {
+                        let end = min(a.end.as_ref(), b.end.as_ref());
+                        (a.start.clone(), end.cloned(), true, true)
+                    }
+                },
+            };
+
+            if in_a {
+                self.a.cut_front(&end);
+            }
+            if in_b {
+                self.b.cut_front(&end);
+            }
+            if self.op.keep(in_a, in_b) {
+                return Some(Segment { start, end });
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Every kept piece starts at one of the bounds left in `a` or `b`
+        let (a, b) = self.remaining();
+        let upper = a.saturating_add(b).saturating_mul(2);
+
+        // Only segments that are sure to be there guarantee any output
+        let (a_min, b_min) = self.remaining_at_least();
+        let lower = match self.op {
+            Op::Union => (a_min > 0 || b_min > 0) as usize,
+            _ => 0,
+        };
+        (lower, Some(upper))
+    }
+}
+
+impl<I, J, T> DoubleEndedIterator for Sweep<I, J, T>
+where
+    I: DoubleEndedIterator<Item = Segment<T>>,
+    J: DoubleEndedIterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+    fn next_back(&mut self) -> Option<Segment<T>> {
+        loop {
+            // Find the last piece that's entirely in `a`, `b` or both
+            let (start, end, in_a, in_b) = match (self.a.peek_back(), self.b.peek_back()) {
+                (None, None) => return None,
+                (Some(a), None) => {
+                    if !self.op.keep(true, false) {
+                        return None;
+                    }
+                    (a.start.clone(), a.end.clone(), true, false)
+                }
+                (None, Some(b)) => {
+                    if !self.op.keep(false, true) {
+                        return None;
+                    }
+                    (b.start.clone(), b.end.clone(), false, true)
+                }
+                (Some(a), Some(b)) => match a.end.cmp(&b.end) {
+                    Greater => {
+                        let start = max(a.start.as_ref(), b.end.after().unwrap());
+                        (start.cloned(), a.end.clone(), true, false)
+                    }
+                    Less => {
+                        let start = max(b.start.as_ref(), a.end.after().unwrap());
+                        (start.cloned(), b.end.clone(), false, true)
+                    }
+                    Equal => {
+                        let start = max(a.start.as_ref(), b.start.as_ref());
+                        (start.cloned(), a.end.clone(), true, true)
+                    }
+                },
+            };
+
+            if in_a {
+                self.a.cut_back(&start);
+            }
+            if in_b {
+                self.b.cut_back(&start);
+            }
+            if self.op.keep(in_a, in_b) {
+                return Some(Segment { start, end });
+            }
+        }
+    }
+}
+
+impl<I, J, T> FusedIterator for Sweep<I, J, T>
+where
+    I: Iterator<Item = Segment<T>>,
+    J: Iterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+}
+
+/// Joins touching (or overlapping) segments from a sorted iterator
+#[derive(Clone, Debug)]
+pub(crate) struct Join<I, T> {
+    side: Side<I, T>,
+}
+
+impl<I, T> Join<I, T> {
+    pub(crate) fn new(iter: I) -> Self {
+        Join {
+            side: Side::new(iter),
+        }
+    }
+
+    /// The wrapped iterator
+    pub(crate) fn get_ref(&self) -> &I {
+        &self.side.iter
+    }
+
+    /// The number of segments taken from the wrapped iterator, but not yet
+    /// joined up and returned
+    pub(crate) fn held(&self) -> usize {
+        self.side.held()
+    }
+}
+
+impl<I, T> Iterator for Join<I, T>
+where
+    I: FusedIterator<Item = Segment<T>>,
+    T: Ord,
+{
+    type Item = Segment<T>;
+    fn next(&mut self) -> Option<Segment<T>> {
+        let mut joined = self.side.pop_front()?;
+        while let Some(next) = self.side.peek_front() {
+            if !joined.touches(next) {
+                break;
+            }
+            let next = self.side.front.take().unwrap();
+            joined.end = max(joined.end, next.end);
+        }
+        Some(joined)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.side.size_hint();
+        (min(lower, 1), upper)
+    }
+}
+
+impl<I, T> DoubleEndedIterator for Join<I, T>
+where
+    I: DoubleEndedIterator + FusedIterator<Item = Segment<T>>,
+    T: Ord,
+{
+    fn next_back(&mut self) -> Option<Segment<T>> {
+        let mut joined = self.side.pop_back()?;
+        while let Some(prev) = self.side.peek_back() {
+            if !joined.touches(prev) {
+                break;
+            }
+            let prev = self.side.back.take().unwrap();
+            joined.start = min(joined.start, prev.start);
+        }
+        Some(joined)
+    }
+}
+
+impl<I, T> FusedIterator for Join<I, T>
+where
+    I: FusedIterator<Item = Segment<T>>,
+    T: Ord,
+{
+}
+
+/// Checks (in debug builds) that segments come out of an iterator in order
+#[derive(Clone, Debug)]
+pub(crate) struct Checked<I, T> {
+    iter: I,
+
+    // Whether segments may overlap, rather than just touch
+    overlapping: bool,
+
+    // The last segments taken from either end. Only kept in debug builds.
+    front: Option<Segment<T>>,
+    back: Option<Segment<T>>,
+}
+
+impl<I, T> Checked<I, T> {
+    pub(crate) fn new(iter: I, overlapping: bool) -> Self {
+        Checked {
+            iter,
+            overlapping,
+            front: None,
+            back: None,
+        }
+    }
+}
+
+impl<I, T> Iterator for Checked<I, T>
+where
+    I: Iterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+    type Item = Segment<T>;
+    fn next(&mut self) -> Option<Segment<T>> {
+        let next = self.iter.next()?;
+        if cfg!(debug_assertions) {
+            if let Some(prev) = &self.front {
+                if self.overlapping {
+                    assert!(prev.start <= next.start, "segments aren't sorted");
+                } else {
+                    assert!(
+                        prev.end.cmp_start(&next.start).is_lt(),
+                        "segments aren't sorted, or overlap"
+                    );
+                }
+            }
+            self.front = Some(next.clone());
+        }
+        Some(next)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I, T> DoubleEndedIterator for Checked<I, T>
+where
+    I: DoubleEndedIterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+    fn next_back(&mut self) -> Option<Segment<T>> {
+        let next = self.iter.next_back()?;
+        if cfg!(debug_assertions) {
+            if let Some(prev) = &self.back {
+                if self.overlapping {
+                    assert!(next.end <= prev.end, "segment ends aren't sorted");
+                } else {
+                    assert!(
+                        next.end.cmp_start(&prev.start).is_lt(),
+                        "segments aren't sorted, or overlap"
+                    );
+                }
+            }
+            self.back = Some(next.clone());
+        }
+        Some(next)
+    }
+}
+
+/// Set algebra adapters for iterators over sorted [`Segment`]s
+///
+/// The set operations ([`union`], [`intersection`], [`difference`] and
+/// [`symmetric_difference`]) expect both iterators to yield segments in
+/// order, without overlapping each other (touching is fine). Their results
+/// are in the same form, and are coalesced, so they can be combined further.
+///
+/// [`coalesce`] joins up touching or overlapping segments, and only expects
+/// segments to be sorted by their start (and by their end, if used from the
+/// back). It can tidy up a stream before passing it to a set operation.
+///
+/// Everything is lazy, and works from either end if the underlying iterators
+/// do. In debug builds, the adapters panic if their input is out of order.
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// let a = vec![Segment::from(0..10), Segment::from(20..30)];
+/// let b = vec![Segment::from(5..25)];
+/// let c = vec![Segment::from(8..9), Segment::from(9..22)];
+///
+/// let result: Vec<_> = a.into_iter().intersection(b).difference(c).collect();
+/// assert_eq!(result, vec![Segment::from(5..8), Segment::from(22..25)]);
+/// ```
+///
+/// [`union`]: SegmentIterExt::union
+/// [`intersection`]: SegmentIterExt::intersection
+/// [`difference`]: SegmentIterExt::difference
+/// [`symmetric_difference`]: SegmentIterExt::symmetric_difference
+/// [`coalesce`]: SegmentIterExt::coalesce
+pub trait SegmentIterExt<T>: Iterator<Item = Segment<T>> {
+    /// Lazily yield everything in either `self` or `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let a = vec![Segment::from(0..5), Segment::from(10..15)];
+    /// let b = vec![Segment::from(3..10)];
+    /// assert!(a.into_iter().union(b).eq(vec![Segment::from(0..15)]));
+    /// ```
+    fn union<J>(self, other: J) -> Stitch<Self, J::IntoIter, T>
+    where
+        Self: Sized,
+        J: IntoIterator<Item = Segment<T>>,
+        T: Clone + Ord,
+    {
+        Stitch::new(self, other.into_iter(), Op::Union)
+    }
+
+    /// Lazily yield everything in both `self` and `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let a = vec![Segment::from(0..5), Segment::from(10..15)];
+    /// let b = vec![Segment::from(3..12)];
+    /// assert!(a.into_iter().intersection(b).rev().eq(vec![
+    ///     Segment::from(10..12),
+    ///     Segment::from(3..5),
+    /// ]));
+    /// ```
+    fn intersection<J>(self, other: J) -> Stitch<Self, J::IntoIter, T>
+    where
+        Self: Sized,
+        J: IntoIterator<Item = Segment<T>>,
+        T: Clone + Ord,
+    {
+        Stitch::new(self, other.into_iter(), Op::Intersection)
+    }
+
+    /// Lazily yield everything in `self` but not in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let a = vec![Segment::from(0..10)];
+    /// let b = vec![Segment::from(3..5), Segment::from(8..)];
+    /// assert!(a.into_iter().difference(b).eq(vec![
+    ///     Segment::from(0..3),
+    ///     Segment::from(5..8),
+    /// ]));
+    /// ```
+    fn difference<J>(self, other: J) -> Stitch<Self, J::IntoIter, T>
+    where
+        Self: Sized,
+        J: IntoIterator<Item = Segment<T>>,
+        T: Clone + Ord,
+    {
+        Stitch::new(self, other.into_iter(), Op::Difference)
+    }
+
+    /// Lazily yield everything in exactly one of `self` and `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let a = vec![Segment::from(0..10)];
+    /// let b = vec![Segment::from(5..15)];
+    /// assert!(a.into_iter().symmetric_difference(b).eq(vec![
+    ///     Segment::from(0..5),
+    ///     Segment::from(10..15),
+    /// ]));
+    /// ```
+    fn symmetric_difference<J>(self, other: J) -> Stitch<Self, J::IntoIter, T>
+    where
+        Self: Sized,
+        J: IntoIterator<Item = Segment<T>>,
+        T: Clone + Ord,
+    {
+        Stitch::new(self, other.into_iter(), Op::SymmetricDifference)
+    }
+
+    /// Lazily join touching or overlapping segments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let segments = vec![
+    ///     Segment::from(0..5),
+    ///     Segment::from(2..4),
+    ///     Segment::from(5..8),
+    ///     Segment::from(10..12),
+    /// ];
+    /// assert!(segments.into_iter().coalesce().eq(vec![
+    ///     Segment::from(0..8),
+    ///     Segment::from(10..12),
+    /// ]));
+    /// ```
+    fn coalesce(self) -> Coalesce<Self, T>
+    where
+        Self: Sized,
+        T: Clone + Ord,
+    {
+        Coalesce(Join::new(Checked::new(self, true).fuse()))
+    }
+}
+
+impl<I, T> SegmentIterExt<T> for I where I: Iterator<Item = Segment<T>> {}
+
+/// A lazy set operation on two sorted iterators of segments
+///
+/// This `struct` is created by the set operations on [`SegmentIterExt`]. See
+/// its documentation for more.
+#[derive(Clone, Debug)]
+pub struct Stitch<I, J, T>(Join<Sweep<I, J, T>, T>);
+
+impl<I, J, T> Stitch<I, J, T>
+where
+    I: Iterator<Item = Segment<T>>,
+    J: Iterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+    pub(crate) fn new(a: I, b: J, op: Op) -> Self {
+        Stitch(Join::new(Sweep::new(a, b, op)))
+    }
+
+    /// Bounds on the number of segments left, like [`Iterator::size_hint`],
+    /// if `a` and `b` are both already coalesced
+    pub(crate) fn coalesced_size_hint(&self) -> (usize, Option<usize>) {
+        let (a, b) = self.0.get_ref().remaining();
+        let (a_min, b_min) = self.0.get_ref().remaining_at_least();
+        let held = self.0.held();
+        let op = self.0.get_ref().op;
+
+        // Each segment in the result has its bounds from segments in `a` or
+        // `b`, and coalesced segments have two bounds each
+        let total = a.saturating_add(b);
+        let upper = match op {
+            Op::Intersection if a == 0 || b == 0 => 0,
+            Op::Intersection => total - 1,
+            Op::Difference if a == 0 => 0,
+            _ => total,
+        };
+
+        // Without anything to stitch against, segments pass straight through.
+        // Lower bounds can only come from the inputs' lower bounds, since an
+        // input may yield fewer segments than its upper bound.
+        let lower = match op {
+            _ if held > 0 => 1,
+            Op::Union | Op::SymmetricDifference if b == 0 => a_min,
+            Op::Union | Op::SymmetricDifference if a == 0 => b_min,
+            Op::Difference if b == 0 => a_min,
+            Op::Union => (a_min > 0 || b_min > 0) as usize,
+            _ => 0,
+        };
+        (lower, Some(upper.saturating_add(held)))
+    }
+}
+
+impl<I, J, T> Iterator for Stitch<I, J, T>
+where
+    I: Iterator<Item = Segment<T>>,
+    J: Iterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+    type Item = Segment<T>;
+    fn next(&mut self) -> Option<Segment<T>> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Touching segments in the input can join up, so only the upper bound
+        // holds in general
+        let (lower, upper) = self.coalesced_size_hint();
+        (min(lower, 1), upper)
+    }
+}
+
+impl<I, J, T> DoubleEndedIterator for Stitch<I, J, T>
+where
+    I: DoubleEndedIterator<Item = Segment<T>>,
+    J: DoubleEndedIterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+    fn next_back(&mut self) -> Option<Segment<T>> {
+        self.0.next_back()
+    }
+}
+
+impl<I, J, T> FusedIterator for Stitch<I, J, T>
+where
+    I: Iterator<Item = Segment<T>>,
+    J: Iterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+}
+
+/// Joins touching or overlapping segments from a sorted iterator
+///
+/// This `struct` is created by [`SegmentIterExt::coalesce`]. See its
+/// documentation for more.
+#[derive(Clone, Debug)]
+pub struct Coalesce<I, T>(Join<Fuse<Checked<I, T>>, T>);
+
+impl<I, T> Iterator for Coalesce<I, T>
+where
+    I: Iterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+    type Item = Segment<T>;
+    fn next(&mut self) -> Option<Segment<T>> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<I, T> DoubleEndedIterator for Coalesce<I, T>
+where
+    I: DoubleEndedIterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+    fn next_back(&mut self) -> Option<Segment<T>> {
+        self.0.next_back()
+    }
+}
+
+impl<I, T> FusedIterator for Coalesce<I, T>
+where
+    I: Iterator<Item = Segment<T>>,
+    T: Clone + Ord,
+{
+}
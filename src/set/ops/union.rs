@@ -1,15 +1,22 @@
-use crate::{map::Key, set::iterators::Iter, Segment, SegmentMap, SegmentSet};
+use core::{
+    fmt::{self, Debug},
+    iter::FusedIterator,
+};
+
+use super::{
+    sweep::{SegmentIterExt, Stitch},
+    Refs,
+};
+use crate::{map::Key, Segment, SegmentMap, SegmentSet};
 
 impl<T> SegmentSet<T> {
     // TODO: into_union_iter
 
-    pub fn union_iter<'a>(&'a self, other: &'a Self) -> Union<'a, T> {
-        Union {
-            iter_a: self.iter(),
-            prev_a: None,
-            iter_b: other.iter(),
-            prev_b: None,
-        }
+    pub fn union_iter<'a>(&'a self, other: &'a Self) -> Union<'a, T>
+    where
+        T: Ord,
+    {
+        Union(self.refs().union(other.refs()))
     }
 
     // TODO: into_union
@@ -62,100 +69,47 @@ impl<T: Ord + Clone> core::ops::Add<&SegmentSet<T>> for &SegmentSet<T> {
 
 // TODO: Set in-place union (AddAssign and BitOrAssign)
 
-pub struct Union<'a, T> {
-    iter_a: Iter<'a, T>,
-    prev_a: Option<Segment<&'a T>>,
+/// An iterator over the union of two [`SegmentSet`]s
+///
+/// This `struct` is created by [`SegmentSet::union_iter`]. See its
+/// documentation for more.
+pub struct Union<'a, T>(Stitch<Refs<'a, T>, Refs<'a, T>, &'a T>);
+
+impl<T> Clone for Union<'_, T> {
+    fn clone(&self) -> Self {
+        Union(self.0.clone())
+    }
+}
 
-    iter_b: Iter<'a, T>,
-    prev_b: Option<Segment<&'a T>>,
+impl<T: Ord + Debug> Debug for Union<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
 }
 
 impl<'a, T: Ord> Iterator for Union<'a, T> {
     type Item = Segment<&'a T>;
-    fn next(&mut self) -> Option<Self::Item> {
-        let next_a = self
-            .prev_a
-            .take()
-            .or_else(|| self.iter_a.next().map(|x| x.as_ref()));
-
-        let next_b = self
-            .prev_b
-            .take()
-            .or_else(|| self.iter_b.next().map(|x| x.as_ref()));
-
-        // If one ran out, use the other
-        let next_a = match next_a {
-            Some(a) => a,
-            None => return next_b,
-        };
-        let next_b = match next_b {
-            Some(b) => b,
-            None => return Some(next_a),
-        };
-
-        // If `a` is fully before `b` return it and hold on to `b`
-        if next_a.end.cmp_start(&next_b.start).is_gt() {
-            self.prev_b.insert(next_b);
-            return Some(next_a);
-        }
-
-        // Likewise the other way around
-        if next_a.start.cmp_end(&next_b.end).is_gt() {
-            self.prev_a.insert(next_a);
-            return Some(next_b);
-        }
-
-        // Otherwise, `a` must overlap `b`. Store the outer bounds
-        let mut outer = Segment {
-            start: core::cmp::min(next_a.start, next_b.start),
-            end: core::cmp::max(next_a.end, next_b.end),
-        };
-
-        // Increase the outer end bound until we have no overlap
-        loop {
-            // Get the next end_bound for a touching `a` (holding on to it if
-            // not touching)
-            let next_a_end = if let Some(r) = self
-                .prev_a
-                .take()
-                .or_else(|| self.iter_a.next().map(|r| r.as_ref()))
-            {
-                if outer.touches(&r) {
-                    Some(r.end)
-                } else {
-                    self.prev_a.insert(r);
-                    None
-                }
-            } else {
-                None
-            };
-
-            // likewise for `b`
-            let next_b_end = if let Some(r) = self
-                .prev_b
-                .take()
-                .or_else(|| self.iter_b.next().map(|r| r.as_ref()))
-            {
-                if outer.touches(&r) {
-                    Some(r.end)
-                } else {
-                    self.prev_b.insert(r);
-                    None
-                }
-            } else {
-                None
-            };
-
-            match (next_a_end, next_b_end) {
-                // If no extensions to make, return
-                (None, None) => return Some(outer),
-
-                // If we only have one, apply it and loop
-                (Some(end), None) | (None, Some(end)) => outer.end = end,
+    fn next(&mut self) -> Option<Segment<&'a T>> {
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.coalesced_size_hint()
+    }
+    fn min(mut self) -> Option<Segment<&'a T>> {
+        self.next()
+    }
+    fn max(mut self) -> Option<Segment<&'a T>> {
+        self.next_back()
+    }
+    fn last(mut self) -> Option<Segment<&'a T>> {
+        self.next_back()
+    }
+}
 
-                // If we have both, use the greater (and loop)
-                (Some(a), Some(b)) => outer.end = core::cmp::max(a, b),
-            }
-        }
+impl<'a, T: Ord> DoubleEndedIterator for Union<'a, T> {
+    fn next_back(&mut self) -> Option<Segment<&'a T>> {
+        self.0.next_back()
     }
 }
+
+impl<T: Ord> FusedIterator for Union<'_, T> {}
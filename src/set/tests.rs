@@ -1,4 +1,5 @@
 use super::*;
+use crate::SegmentIterExt;
 use alloc::{format, vec, vec::Vec};
 
 #[test]
@@ -66,3 +67,314 @@ fn set_debug_repr_looks_right() {
     set.insert(10..11);
     assert_eq!(format!("{:?}", set), "{[2, 5), [7, 8], [10, 11)}");
 }
+
+#[test]
+fn insert_reports_new_coverage() {
+    let mut range_set: SegmentSet<u32> = SegmentSet::new();
+    assert!(range_set.insert(10..20));
+    assert!(!range_set.insert(10..20));
+    assert!(!range_set.insert(12..=15));
+    assert!(range_set.insert(5..10));
+    assert!(range_set.insert(15..25));
+    assert!(range_set.insert(30..));
+    assert!(!range_set.insert(40..));
+    assert_eq!(
+        range_set.into_vec(),
+        vec![Segment::from(5..25), Segment::from(30..)]
+    );
+}
+
+#[test]
+fn complement_round_trips() {
+    let sets: Vec<SegmentSet<u32>> = vec![
+        SegmentSet::new(),
+        SegmentSet::full(),
+        core::iter::once(0..5).collect(),
+        vec![5..10, 20..30].into_iter().collect(),
+    ];
+    for set in sets {
+        let complement: SegmentSet<u32> = set.iter_complement().map(|r| r.cloned()).collect();
+        assert_eq!(complement, set.complement().cloned());
+        assert!(set.is_disjoint(&complement));
+
+        let mut full = set.clone();
+        full.extend(complement.iter().cloned());
+        assert_eq!(full, SegmentSet::full());
+    }
+}
+
+#[test]
+fn ordering_matches_equality() {
+    let a: SegmentSet<u32> = vec![0..5, 10..15].into_iter().collect();
+    let b: SegmentSet<u32> = vec![0..5, 10..20].into_iter().collect();
+    let c: SegmentSet<u32> = core::iter::once(0..10).collect();
+    assert!(a < b);
+    assert!(b < c);
+    assert_eq!(a.cmp(&a.clone()), core::cmp::Ordering::Equal);
+
+    let mut split = b.clone();
+    let middle = split.split_off_range(3..12);
+    assert_eq!(
+        middle.into_vec(),
+        vec![Segment::from(3..5), Segment::from(10..12)]
+    );
+    assert_eq!(
+        split.into_vec(),
+        vec![Segment::from(0..3), Segment::from(12..20)]
+    );
+}
+
+#[test]
+fn set_ops_run_both_ways() {
+    use core::ops::Bound::*;
+    let sets: Vec<SegmentSet<i32>> = vec![
+        SegmentSet::new(),
+        SegmentSet::full(),
+        vec![Segment::from(0..5), Segment::from(10..=20)]
+            .into_iter()
+            .collect(),
+        vec![
+            Segment::from(..3),
+            Segment::from(5..12),
+            Segment::from(20..),
+        ]
+        .into_iter()
+        .collect(),
+        vec![
+            Segment::from(&(Excluded(4), Included(10))),
+            Segment::from(15..16),
+            Segment::from(30..40),
+        ]
+        .into_iter()
+        .collect(),
+    ];
+
+    for a in &sets {
+        for b in &sets {
+            let check = |forward: Vec<Segment<&i32>>,
+                         backward: Vec<Segment<&i32>>,
+                         hint: (usize, Option<usize>),
+                         keep: fn(bool, bool) -> bool| {
+                assert!(hint.0 <= forward.len());
+                assert!(hint.1.map_or(true, |hi| forward.len() <= hi));
+                assert!(forward.iter().rev().eq(&backward));
+
+                // No two results should touch
+                let result: SegmentSet<i32> = forward.iter().map(|r| r.cloned()).collect();
+                assert_eq!(result.len(), forward.len());
+                for x in -5..45 {
+                    assert_eq!(
+                        result.contains(&x),
+                        keep(a.contains(&x), b.contains(&x)),
+                        "{:?} {:?} at {}",
+                        a,
+                        b,
+                        x
+                    );
+                }
+            };
+
+            let union = a.union_iter(b);
+            check(
+                union.clone().collect(),
+                union.clone().rev().collect(),
+                union.size_hint(),
+                |a, b| a || b,
+            );
+            let intersection = a.iter_intersection(b);
+            check(
+                intersection.clone().collect(),
+                intersection.clone().rev().collect(),
+                intersection.size_hint(),
+                |a, b| a && b,
+            );
+            let difference = a.iter_difference(b);
+            check(
+                difference.clone().collect(),
+                difference.clone().rev().collect(),
+                difference.size_hint(),
+                |a, b| a && !b,
+            );
+            let symmetric_difference = a.symmetric_difference_iter(b);
+            check(
+                symmetric_difference.clone().collect(),
+                symmetric_difference.clone().rev().collect(),
+                symmetric_difference.size_hint(),
+                |a, b| a != b,
+            );
+
+            // Meeting in the middle shouldn't lose or repeat anything
+            let mut union = a.union_iter(b);
+            let mut mixed = Vec::new();
+            let mut back = Vec::new();
+            while let Some(r) = union.next() {
+                mixed.push(r);
+                match union.next_back() {
+                    Some(r) => back.push(r),
+                    None => break,
+                }
+            }
+            mixed.extend(back.into_iter().rev());
+            assert_eq!(mixed, a.union_iter(b).collect::<Vec<_>>());
+        }
+    }
+}
+
+#[test]
+fn gaps_and_complement_run_both_ways() {
+    let set: SegmentSet<u32> = vec![2..4, 6..8, 10..12].into_iter().collect();
+
+    let gaps = set.iter_gaps();
+    assert_eq!(gaps.len(), 2);
+    assert!(gaps
+        .clone()
+        .rev()
+        .eq(vec![Segment::from(&8..&10), Segment::from(&4..&6)]));
+    assert_eq!(format!("{:?}", gaps), "[[4, 6), [8, 10)]");
+
+    let mut complement = set.iter_complement();
+    assert_eq!(complement.len(), 4);
+    assert_eq!(complement.next_back(), Some(Segment::from(&12..)));
+    assert_eq!(complement.next(), Some(Segment::from(..&2)));
+    assert_eq!(complement.next_back(), Some(Segment::from(&8..&10)));
+    assert_eq!(complement.len(), 1);
+    assert_eq!(complement.next(), Some(Segment::from(&4..&6)));
+    assert_eq!(complement.next_back(), None);
+
+    assert!(set.iter_subset(3..11).rev().eq(vec![
+        Segment::from(10..11),
+        Segment::from(6..8),
+        Segment::from(3..4)
+    ]));
+}
+
+#[test]
+fn segment_streams_match_set_ops() {
+    let a: SegmentSet<u32> = vec![0..5, 10..20, 30..40].into_iter().collect();
+    let b: SegmentSet<u32> = vec![
+        Segment::from(3..12),
+        Segment::from(15..17),
+        Segment::from(35..),
+    ]
+    .into_iter()
+    .collect();
+
+    // Touching segments are fine as input, and come out joined up
+    let split = |set: &SegmentSet<u32>| -> Vec<Segment<u32>> {
+        set.iter()
+            .flat_map(|range| {
+                let (start, end) = (*range.start_value().unwrap(), range.end_value().copied());
+                let mid = start + 1;
+                vec![
+                    Segment::from(start..mid),
+                    match end {
+                        Some(end) => Segment::from(mid..end),
+                        None => Segment::from(mid..),
+                    },
+                ]
+            })
+            .collect()
+    };
+
+    let union: Vec<_> = split(&a).into_iter().union(split(&b)).collect();
+    assert_eq!(union, a.union(&b).cloned().into_vec());
+    let intersection: Vec<_> = split(&a).into_iter().intersection(split(&b)).collect();
+    assert_eq!(intersection, a.intersection(&b).cloned().into_vec());
+    let difference: Vec<_> = split(&a).into_iter().difference(split(&b)).rev().collect();
+    assert!(difference.into_iter().rev().eq(a.difference(&b).cloned()));
+    let symmetric_difference: Vec<_> = split(&a)
+        .into_iter()
+        .symmetric_difference(split(&b))
+        .collect();
+    assert_eq!(
+        symmetric_difference,
+        a.symmetric_difference(&b).cloned().into_vec()
+    );
+
+    // (a - b) | (b - a) == (a | b) - (a & b)
+    let lhs = a
+        .iter()
+        .cloned()
+        .difference(b.iter().cloned())
+        .union(b.iter().cloned().difference(a.iter().cloned()));
+    let rhs = a
+        .iter()
+        .cloned()
+        .union(b.iter().cloned())
+        .difference(a.iter().cloned().intersection(b.iter().cloned()));
+    assert!(lhs.eq(rhs));
+
+    let overlapping = vec![
+        Segment::from(0..10),
+        Segment::from(2..4),
+        Segment::from(10..12),
+        Segment::from(20..30),
+    ];
+    assert!(overlapping
+        .into_iter()
+        .coalesce()
+        .eq(vec![Segment::from(0..12), Segment::from(20..30)]));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "segments aren't sorted")]
+fn segment_streams_check_order() {
+    let a = vec![Segment::from(10..20), Segment::from(0..5)];
+    a.into_iter()
+        .union(vec![Segment::from(0..1)])
+        .for_each(drop);
+}
+
+#[test]
+fn hash_is_prefixed_with_len() {
+    // Records everything written, so hashes can be compared exactly
+    #[derive(Default)]
+    struct Recorder(Vec<u8>);
+    impl Hasher for Recorder {
+        fn finish(&self) -> u64 {
+            0
+        }
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes);
+        }
+    }
+    fn hashed<T: Hash>(value: &T) -> Vec<u8> {
+        let mut recorder = Recorder::default();
+        value.hash(&mut recorder);
+        recorder.0
+    }
+
+    let mut both = SegmentSet::new();
+    both.insert(0..1);
+    both.insert(2..3);
+    let mut first = SegmentSet::new();
+    first.insert(0..1);
+    let mut second = SegmentSet::new();
+    second.insert(2..3);
+
+    // The same ranges, split differently between two sets
+    assert_ne!(
+        hashed(&(both, SegmentSet::<i32>::new())),
+        hashed(&(first, second))
+    );
+}
+
+#[test]
+fn size_hints_only_promise_certain_segments() {
+    // `Filter` can yield nothing, whatever its upper bound
+    let nothing = || vec![Segment::from(0..5)].into_iter().filter(|_| false);
+    let empty = Vec::<Segment<i32>>::new;
+
+    let union = nothing().union(empty());
+    assert_eq!(union.size_hint(), (0, Some(1)));
+    assert_eq!(union.count(), 0);
+    assert_eq!(empty().into_iter().union(nothing()).size_hint().0, 0);
+    assert_eq!(nothing().difference(empty()).size_hint().0, 0);
+    assert_eq!(nothing().symmetric_difference(empty()).size_hint().0, 0);
+    assert_eq!(
+        nothing().union(vec![Segment::from(10..20)]).size_hint().0,
+        1
+    );
+    assert_eq!(nothing().coalesce().size_hint().0, 0);
+}
@@ -0,0 +1,416 @@
+//! A [`SegmentMap`] that remembers every revision
+//!
+//! [`VersionedSegmentMap`] applies mutations to a regular [`SegmentMap`]
+//! (so insertion and coalescing work exactly the same way), and records each
+//! change in a history keyed by the same segments. Every range of keys has a
+//! timeline of `(revision, value)` pairs, so point-in-time lookups only need a
+//! tree search and a binary search.
+
+use alloc::vec::Vec;
+use core::{fmt, ops::RangeBounds};
+
+use crate::{
+    map::{collect_coalesced, Change},
+    Segment, SegmentMap,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A revision number of a [`VersionedSegmentMap`]
+///
+/// An empty map starts at revision `0`, and each mutation adds one.
+pub type Revision = u64;
+
+/// Error returned when querying a revision of a [`VersionedSegmentMap`] that
+/// has been discarded by [`VersionedSegmentMap::compact`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Compacted {
+    /// The oldest revision that can still be queried
+    pub oldest: Revision,
+}
+
+impl fmt::Display for Compacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "revision has been compacted (oldest available is {})",
+            self.oldest
+        )
+    }
+}
+
+/// The values a range of keys has held, as `(revision, value)` pairs in
+/// increasing order of revision. `None` means the range was empty.
+type Timeline<V> = Vec<(Revision, Option<V>)>;
+
+/// A [`SegmentMap`] that keeps the history of every mutation, allowing
+/// queries against past revisions
+///
+/// # Examples
+///
+/// ```
+/// # use segmap::*;
+/// let mut config = VersionedSegmentMap::new();
+/// let r1 = config.set(0..100, "v1");
+/// let r2 = config.set(50..100, "v2");
+/// let r3 = config.clear_range(90..);
+///
+/// assert_eq!(config.get_at(&95, r1), Ok(Some(&"v1")));
+/// assert_eq!(config.get_at(&95, r2), Ok(Some(&"v2")));
+/// assert_eq!(config.get_at(&95, r3), Ok(None));
+/// assert_eq!(config.get(&95), None);
+///
+/// let old = config.snapshot(r2).unwrap();
+/// assert!(old.iter().eq(vec![
+///     (&Segment::from(0..50), &"v1"),
+///     (&Segment::from(50..100), &"v2"),
+/// ]));
+/// ```
+#[derive(Clone)]
+pub struct VersionedSegmentMap<K, V> {
+    current: SegmentMap<K, V>,
+    history: SegmentMap<K, Timeline<V>>,
+    revision: Revision,
+    oldest: Revision,
+}
+
+impl<K, V> VersionedSegmentMap<K, V> {
+    /// Makes a new, empty `VersionedSegmentMap` at revision `0`.
+    pub fn new() -> Self
+    where
+        K: Ord,
+    {
+        VersionedSegmentMap {
+            current: SegmentMap::new(),
+            history: SegmentMap::new(),
+            revision: 0,
+            oldest: 0,
+        }
+    }
+
+    /// The current (latest) revision
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    /// The oldest revision that can still be queried (see
+    /// [`VersionedSegmentMap::compact`])
+    pub fn oldest_revision(&self) -> Revision {
+        self.oldest
+    }
+
+    /// Get the map as of the current revision
+    pub fn as_map(&self) -> &SegmentMap<K, V> {
+        &self.current
+    }
+
+    /// Discard the history, returning the map as of the current revision
+    pub fn into_map(self) -> SegmentMap<K, V> {
+        self.current
+    }
+
+    /// Returns a reference to the current value covering `at`, if any.
+    pub fn get(&self, at: &K) -> Option<&V>
+    where
+        K: Clone + Ord,
+    {
+        self.current.get(at)
+    }
+
+    /// Returns a reference to the value covering `at` as of `revision`, if
+    /// any.
+    ///
+    /// Revisions after the current one see the current value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Compacted`] if `revision` is older than
+    /// [`VersionedSegmentMap::oldest_revision`].
+    pub fn get_at(&self, at: &K, revision: Revision) -> Result<Option<&V>, Compacted>
+    where
+        K: Clone + Ord,
+    {
+        self.check(revision)?;
+        Ok(self
+            .history
+            .get(at)
+            .and_then(|timeline| value_at(timeline, revision)))
+    }
+
+    /// Get every value that `at` has held, as `(revision, value)` pairs, where
+    /// each value was set at the given revision. `None` values mean `at` was
+    /// removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = VersionedSegmentMap::new();
+    /// map.set(0..10, "a");
+    /// map.set(5..10, "b");
+    /// map.clear_range(..);
+    ///
+    /// assert_eq!(
+    ///     map.history(&7),
+    ///     &[(1, Some("a")), (2, Some("b")), (3, None)]
+    /// );
+    /// assert!(map.history(&20).is_empty());
+    /// ```
+    pub fn history(&self, at: &K) -> &[(Revision, Option<V>)]
+    where
+        K: Clone + Ord,
+    {
+        self.history
+            .get(at)
+            .map_or(&[], |timeline| timeline.as_slice())
+    }
+
+    /// Rebuild the map as it was at `revision`.
+    ///
+    /// This takes linear time in the number of distinct histories.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Compacted`] if `revision` is older than
+    /// [`VersionedSegmentMap::oldest_revision`].
+    pub fn snapshot(&self, revision: Revision) -> Result<SegmentMap<K, V>, Compacted>
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.check(revision)?;
+        Ok(collect_coalesced(self.history.iter().filter_map(
+            |(range, timeline)| {
+                value_at(timeline, revision).map(|value| (range.clone(), value.clone()))
+            },
+        )))
+    }
+
+    /// Get the differences between the map at revision `from` and revision
+    /// `to`, like [`SegmentMap::diff`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Compacted`] if either revision is older than
+    /// [`VersionedSegmentMap::oldest_revision`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = VersionedSegmentMap::new();
+    /// let r1 = map.set(0..10, "a");
+    /// map.set(10..20, "b");
+    /// let r3 = map.set(0..5, "c");
+    ///
+    /// assert_eq!(
+    ///     map.changes_between(r1, r3),
+    ///     Ok(vec![
+    ///         Change::Changed { range: Segment::from(0..5), old: "a", new: "c" },
+    ///         Change::Added { range: Segment::from(10..20), new: "b" },
+    ///     ])
+    /// );
+    /// ```
+    pub fn changes_between(
+        &self,
+        from: Revision,
+        to: Revision,
+    ) -> Result<Vec<Change<K, V>>, Compacted>
+    where
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.check(from)?;
+        self.check(to)?;
+
+        let mut changes: Vec<Change<K, V>> = Vec::new();
+        for (range, timeline) in self.history.iter() {
+            let change = match (value_at(timeline, from), value_at(timeline, to)) {
+                (Some(old), Some(new)) if old != new => Change::Changed {
+                    range: range.clone(),
+                    old: old.clone(),
+                    new: new.clone(),
+                },
+                (Some(old), None) => Change::Removed {
+                    range: range.clone(),
+                    old: old.clone(),
+                },
+                (None, Some(new)) => Change::Added {
+                    range: range.clone(),
+                    new: new.clone(),
+                },
+                _ => continue,
+            };
+
+            // Neighbouring histories can differ at other revisions, but
+            // describe the same change here
+            if let Some(last) = changes.last_mut() {
+                if last.old_value() == change.old_value()
+                    && last.new_value() == change.new_value()
+                    && last.range().touches(change.range())
+                {
+                    let end = change.range().end.clone();
+                    match last {
+                        Change::Added { range, .. }
+                        | Change::Removed { range, .. }
+                        | Change::Changed { range, .. } => range.end = end,
+                    }
+                    continue;
+                }
+            }
+            changes.push(change);
+        }
+        Ok(changes)
+    }
+
+    /// Discard history before `revision`, so that it becomes the oldest
+    /// revision that can be queried. Ranges whose histories become identical
+    /// are coalesced.
+    ///
+    /// Revisions after the current one are clamped to the current revision.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use segmap::*;
+    /// let mut map = VersionedSegmentMap::new();
+    /// map.set(0..10, "a");
+    /// let r2 = map.set(5..10, "b");
+    /// map.set(5..10, "a");
+    ///
+    /// map.compact(r2);
+    /// assert_eq!(map.oldest_revision(), r2);
+    /// assert_eq!(map.get_at(&7, r2), Ok(Some(&"b")));
+    /// assert!(map.get_at(&7, r2 - 1).is_err());
+    /// ```
+    pub fn compact(&mut self, revision: Revision)
+    where
+        K: Ord,
+        V: Eq,
+    {
+        let horizon = revision.min(self.revision);
+        if horizon <= self.oldest {
+            return;
+        }
+        self.oldest = horizon;
+
+        let history = core::mem::take(&mut self.history);
+        self.history = history.filter_map_values(|mut timeline| {
+            // Keep the value in effect at the horizon, as if it was set then
+            let first_kept = timeline.partition_point(|(rev, _)| *rev <= horizon);
+            if first_kept > 0 {
+                timeline.drain(..first_kept - 1);
+                timeline[0].0 = horizon;
+            }
+
+            // Ranges that are empty at the horizon and never set again can go
+            match timeline.as_slice() {
+                [(_, None)] => None,
+                _ => Some(timeline),
+            }
+        });
+    }
+
+    /// Set a value for the specified range, like [`SegmentMap::set`],
+    /// returning the new revision.
+    pub fn set<R>(&mut self, range: R, value: V) -> Revision
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let report = self.current.insert_with_report(range, value);
+        self.record(report.affected().iter())
+    }
+
+    /// Insert a value for empty regions in the specified range, like
+    /// [`SegmentMap::insert_in_gaps`], returning the new revision.
+    pub fn insert_in_gaps<R>(&mut self, range: R, value: V) -> Revision
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let report = self.current.insert_in_gaps_with_report(range, value);
+        self.record(report.affected().iter())
+    }
+
+    /// Remove all values in a given range, like [`SegmentMap::clear_range`],
+    /// returning the new revision.
+    pub fn clear_range<R>(&mut self, range: R) -> Revision
+    where
+        R: RangeBounds<K>,
+        K: Clone + Ord,
+        V: Clone + Eq,
+    {
+        let report = self.current.remove_with_report(range);
+        self.record(report.affected().iter())
+    }
+
+    /// Bump the revision and record the current values of `affected` ranges
+    /// in their timelines
+    fn record<'a, I>(&mut self, affected: I) -> Revision
+    where
+        I: Iterator<Item = &'a Segment<K>>,
+        K: 'a + Clone + Ord,
+        V: Clone + Eq,
+    {
+        self.revision += 1;
+        for range in affected {
+            // New values across the range, including gaps
+            let mut values = SegmentMap::new();
+            values.set(range, None);
+            for (piece, value) in self.current.iter_subset(range) {
+                values.set(piece, Some(value.clone()));
+            }
+
+            for (piece, value) in values {
+                // Make sure the whole piece has a (possibly empty) timeline,
+                // then extend each part of it
+                self.history.insert_in_gaps(&piece, Vec::new());
+                for (part, mut timeline) in self.history.remove(&piece).into_iter().flatten() {
+                    // Overwriting with the same value isn't a change
+                    if timeline.last().map(|(_, last)| last) != Some(&value) {
+                        timeline.push((self.revision, value.clone()));
+                    }
+                    self.history.set(part, timeline);
+                }
+            }
+        }
+        self.revision
+    }
+
+    fn check(&self, revision: Revision) -> Result<(), Compacted> {
+        if revision < self.oldest {
+            Err(Compacted {
+                oldest: self.oldest,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The value in `timeline` as of `revision`
+fn value_at<V>(timeline: &Timeline<V>, revision: Revision) -> Option<&V> {
+    let index = timeline.partition_point(|(rev, _)| *rev <= revision);
+    index
+        .checked_sub(1)
+        .and_then(|index| timeline[index].1.as_ref())
+}
+
+impl<K: Ord, V> Default for VersionedSegmentMap<K, V> {
+    fn default() -> Self {
+        VersionedSegmentMap::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for VersionedSegmentMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionedSegmentMap")
+            .field("revision", &self.revision)
+            .field("current", &self.current.map)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,70 @@
+use super::*;
+use alloc::{vec, vec::Vec};
+
+/// Apply a mix of sets, gap fills and removals, returning a copy of the plain
+/// map after each revision (index 0 is the empty map)
+fn build(map: &mut VersionedSegmentMap<u32, u8>) -> Vec<SegmentMap<u32, u8>> {
+    let ops: Vec<(u32, u32, Option<u8>, bool)> = vec![
+        (0, 10, Some(1), false),
+        (5, 15, Some(2), false),
+        (0, 30, Some(3), true),
+        (8, 12, Some(1), false),
+        (3, 18, None, false),
+        (10, 20, Some(3), false),
+        (12, 14, None, false),
+        (0, 40, Some(4), true),
+        (0, 40, Some(4), false),
+    ];
+
+    let mut snapshots = vec![SegmentMap::new()];
+    for (start, end, value, in_gaps) in ops {
+        let revision = match (value, in_gaps) {
+            (Some(value), false) => map.set(start..end, value),
+            (Some(value), true) => map.insert_in_gaps(start..end, value),
+            (None, _) => map.clear_range(start..end),
+        };
+        assert_eq!(revision as usize, snapshots.len());
+        snapshots.push(map.as_map().clone());
+    }
+    snapshots
+}
+
+#[test]
+fn snapshots_match_each_revision() {
+    let mut map = VersionedSegmentMap::new();
+    let snapshots = build(&mut map);
+
+    for (revision, expected) in snapshots.iter().enumerate() {
+        let revision = revision as Revision;
+        assert_eq!(&map.snapshot(revision).unwrap(), expected);
+        for at in 0..45 {
+            assert_eq!(map.get_at(&at, revision).unwrap(), expected.get(&at));
+        }
+        for (later, newer) in snapshots.iter().enumerate() {
+            let mut patched = expected.clone();
+            patched.apply_patch(&map.changes_between(revision, later as Revision).unwrap());
+            assert_eq!(&patched, newer);
+        }
+    }
+}
+
+#[test]
+fn compaction_keeps_later_revisions() {
+    let mut map = VersionedSegmentMap::new();
+    let snapshots = build(&mut map);
+    let before = map.history.len();
+
+    map.compact(5);
+    assert_eq!(map.oldest_revision(), 5);
+    assert_eq!(map.snapshot(4), Err(Compacted { oldest: 5 }));
+    assert!(map.history.len() <= before);
+    for (revision, expected) in snapshots.iter().enumerate().skip(5) {
+        assert_eq!(&map.snapshot(revision as Revision).unwrap(), expected);
+    }
+
+    // Compacting everything leaves a single entry per stored range
+    map.compact(Revision::MAX);
+    assert_eq!(map.oldest_revision(), map.revision());
+    assert_eq!(map.history.len(), map.as_map().len());
+    assert_eq!(&map.snapshot(map.revision()).unwrap(), map.as_map());
+}